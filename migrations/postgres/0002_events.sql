@@ -0,0 +1,15 @@
+-- Journal append-only de tous les changements d'état
+CREATE TABLE events (
+    event_id BIGSERIAL PRIMARY KEY,
+    occurred_at TEXT NOT NULL,
+    actor_id TEXT,
+    event_type TEXT NOT NULL,
+    poll_id TEXT,
+    voter_id TEXT,
+    delegate_id TEXT,
+    payload TEXT NOT NULL
+);
+
+CREATE INDEX events_poll_id ON events (poll_id);
+CREATE INDEX events_voter_id ON events (voter_id);
+CREATE INDEX events_delegate_id ON events (delegate_id);
@@ -0,0 +1,2 @@
+-- Une adresse email ne peut appartenir qu'à un seul électeur
+CREATE UNIQUE INDEX voters_email ON voters (email);
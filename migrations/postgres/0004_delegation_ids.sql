@@ -0,0 +1,4 @@
+-- Identifiant des délégations, pour pouvoir les adresser dans l'API REST
+ALTER TABLE delegations ADD COLUMN delegation_id TEXT;
+
+CREATE UNIQUE INDEX delegations_delegation_id ON delegations (delegation_id);
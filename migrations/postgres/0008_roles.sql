@@ -0,0 +1,17 @@
+-- Rôles des électeurs (projection des événements RoleGranted / RoleRevoked)
+CREATE TABLE voter_roles (
+    voter_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (voter_id, role),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+-- Les électeurs existants reçoivent le rôle Voter, via le journal pour
+-- survivre à rebuild-projections
+INSERT INTO events (occurred_at, actor_id, event_type, poll_id, voter_id, delegate_id, payload)
+SELECT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"'), NULL, 'RoleGranted', NULL, voter_id, NULL,
+       '{"type":"RoleGranted","data":{"voter_id":"' || voter_id || '","role":"Voter"}}'
+FROM voters;
+
+INSERT INTO voter_roles (voter_id, role)
+SELECT voter_id, 'Voter' FROM voters;
@@ -0,0 +1,13 @@
+-- Clés d'API des intégrations, identifiées par l'empreinte SHA-256 de la clé.
+-- Elles agissent au nom de l'administrateur qui les a émises, dans la limite
+-- de leurs scopes (tableau JSON).
+CREATE TABLE api_keys (
+    key_id TEXT PRIMARY KEY,
+    key_hash TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    last_used_at TEXT
+);
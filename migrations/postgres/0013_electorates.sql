@@ -0,0 +1,23 @@
+-- Règles d'éligibilité du scrutin (JSON) et taille du corps électoral, connue
+-- une fois celui-ci figé à l'ouverture
+ALTER TABLE polls ADD COLUMN eligibility TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE polls ADD COLUMN electorate_size BIGINT;
+
+-- Corps électoral figé à l'ouverture (projection de ElectorateMaterialized)
+CREATE TABLE electorates (
+    poll_id TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (poll_id, voter_id),
+    FOREIGN KEY (poll_id) REFERENCES polls(poll_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+-- Appartenance des électeurs aux groupes (projection de GroupJoined / GroupLeft)
+CREATE TABLE group_members (
+    group_name TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (group_name, voter_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+CREATE INDEX group_members_voter_id ON group_members (voter_id);
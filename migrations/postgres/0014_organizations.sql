@@ -0,0 +1,67 @@
+-- Organisations hébergées par le serveur (projection de OrganizationCreated /
+-- OrganizationUpdated) ; le slug les désigne dans le sous-domaine ou le
+-- préfixe de chemin
+CREATE TABLE organizations (
+    organization_id TEXT PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    settings TEXT NOT NULL DEFAULT '{}'
+);
+
+-- Membres des organisations (projection de MemberAdded / MemberRemoved)
+CREATE TABLE organization_members (
+    organization_id TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (organization_id, voter_id),
+    FOREIGN KEY (organization_id) REFERENCES organizations(organization_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+CREATE INDEX organization_members_voter_id ON organization_members (voter_id);
+
+-- Organisation de chaque événement, pour que les auditeurs ne lisent que le
+-- journal de la leur ; les événements de compte n'en ont pas
+ALTER TABLE events ADD COLUMN organization_id TEXT;
+
+UPDATE events SET organization_id = '00000000-0000-0000-0000-000000000001'
+WHERE event_type NOT IN ('VoterCreated', 'VoterStatusChanged', 'EmailVerified');
+
+CREATE INDEX events_organization_id ON events (organization_id, event_id);
+
+-- Tout ce qui existe revient à l'organisation par défaut, créée et peuplée
+-- via le journal pour survivre à rebuild-projections
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+VALUES (to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"'), NULL, 'OrganizationCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+        '{"type":"OrganizationCreated","data":{"organization_id":"00000000-0000-0000-0000-000000000001","slug":"default","name":"Default","settings":{}}}');
+
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+SELECT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"'), NULL, 'MemberAdded', '00000000-0000-0000-0000-000000000001', NULL, voter_id, NULL,
+       '{"type":"MemberAdded","data":{"organization_id":"00000000-0000-0000-0000-000000000001","voter_id":"' || voter_id || '"}}'
+FROM voters;
+
+INSERT INTO organizations (organization_id, slug, name, settings)
+VALUES ('00000000-0000-0000-0000-000000000001', 'default', 'Default', '{}');
+
+INSERT INTO organization_members (organization_id, voter_id)
+SELECT '00000000-0000-0000-0000-000000000001', voter_id FROM voters;
+
+-- Rôles et groupes par organisation
+ALTER TABLE voter_roles ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE voter_roles DROP CONSTRAINT voter_roles_pkey;
+ALTER TABLE voter_roles ADD PRIMARY KEY (organization_id, voter_id, role);
+
+ALTER TABLE group_members ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE group_members DROP CONSTRAINT group_members_pkey;
+ALTER TABLE group_members ADD PRIMARY KEY (organization_id, group_name, voter_id);
+
+-- Scrutins, délégations, historiques, clés d'API et connexions SSO en cours
+-- appartiennent à une organisation
+ALTER TABLE polls ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE delegations ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE vote_history ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE api_keys ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE oidc_logins ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+
+CREATE INDEX polls_organization_opening_time ON polls (organization_id, poll_opening_time, poll_id);
+CREATE INDEX delegations_organization_id ON delegations (organization_id);
+CREATE INDEX vote_history_organization_voter ON vote_history (organization_id, voter_id);
@@ -0,0 +1,5 @@
+-- Les thèmes s'emboîtent (Énergie > Nucléaire) : une délégation sur un thème
+-- couvre aussi les thèmes qui en dépendent
+ALTER TABLE topics ADD COLUMN parent_id TEXT REFERENCES topics(topic_id);
+
+CREATE INDEX topics_parent ON topics (parent_id);
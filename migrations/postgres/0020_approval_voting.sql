@@ -0,0 +1,7 @@
+-- Nombre d'approbations qu'un bulletin peut donner aux questions de type
+-- Approval, sans limite si NULL
+ALTER TABLE poll_questions ADD COLUMN min_approvals BIGINT;
+ALTER TABLE poll_questions ADD COLUMN max_approvals BIGINT;
+
+-- Taux d'approbation de chaque option (JSON)
+ALTER TABLE question_results ADD COLUMN approval_rates TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,7 @@
+-- Sièges à pourvoir et transfert des excédents des questions à vote unique
+-- transférable
+ALTER TABLE poll_questions ADD COLUMN seats BIGINT;
+ALTER TABLE poll_questions ADD COLUMN surplus_transfer TEXT;
+
+-- Dépouillement tour par tour (JSON)
+ALTER TABLE question_results ADD COLUMN stv TEXT NOT NULL DEFAULT 'null';
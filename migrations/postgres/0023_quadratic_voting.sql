@@ -0,0 +1,5 @@
+-- Crédits de chaque votant pour les questions à vote quadratique
+ALTER TABLE polls ADD COLUMN credits BIGINT;
+
+-- Votes nets et dépense des crédits (JSON)
+ALTER TABLE question_results ADD COLUMN quadratic TEXT NOT NULL DEFAULT 'null';
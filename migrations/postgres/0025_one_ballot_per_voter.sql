@@ -0,0 +1,3 @@
+-- Un seul bulletin par électeur et par scrutin : on garde le premier déposé
+DELETE FROM votes WHERE id NOT IN (SELECT MIN(id) FROM votes GROUP BY poll_id, user_id);
+CREATE UNIQUE INDEX votes_poll_user ON votes (poll_id, user_id);
@@ -0,0 +1,14 @@
+-- Poids de chaque électeur à l'ouverture du scrutin, figé avec le corps
+-- électoral : le dépouillement ne relit plus l'historique des poids
+ALTER TABLE electorates ADD COLUMN weight DOUBLE PRECISION NOT NULL DEFAULT 1;
+
+UPDATE electorates SET weight = COALESCE((
+    SELECT voting_weights.weight
+    FROM voting_weights
+    JOIN polls ON polls.organization_id = voting_weights.organization_id
+    WHERE polls.poll_id = electorates.poll_id
+      AND voting_weights.voter_id = electorates.voter_id
+      AND voting_weights.effective_from <= substr(polls.poll_opening_time, 1, 10)
+    ORDER BY voting_weights.effective_from DESC
+    LIMIT 1
+), 1);
@@ -18,10 +18,20 @@ CREATE TABLE delegations (
     FOREIGN KEY (to_delegate) REFERENCES voters(voter_id)
 );
 
+-- Créer la table des sondages
+CREATE TABLE polls (
+    creator_user_id TEXT NOT NULL,
+    poll_id TEXT PRIMARY KEY,
+    poll_opening_time TEXT NOT NULL,
+    poll_closing_time TEXT NOT NULL,
+    questions TEXT NOT NULL,
+    FOREIGN KEY (creator_user_id) REFERENCES voters(voter_id)
+);
+
 -- Créer la table des questions de sondage
 CREATE TABLE poll_questions (
     poll_id TEXT NOT NULL,
-    question_id TEXT NOT NULL,
+    question_id TEXT PRIMARY KEY,
     question_type TEXT NOT NULL,
     question_topic TEXT NOT NULL,
     question_description TEXT NOT NULL,
@@ -30,16 +40,6 @@ CREATE TABLE poll_questions (
     FOREIGN KEY (poll_id) REFERENCES polls(poll_id)
 );
 
--- Créer la table des sondages
-CREATE TABLE polls (
-    creator_user_id TEXT NOT NULL,
-    poll_id TEXT PRIMARY KEY,
-    poll_opening_time TEXT NOT NULL,
-    poll_closing_time TEXT NOT NULL,
-    questions TEXT NOT NULL,
-    FOREIGN KEY (creator_user_id) REFERENCES voters(voter_id)
-);
-
 -- Créer la table des résultats de sondage
 CREATE TABLE poll_results (
     creator_user_id TEXT NOT NULL,
@@ -64,4 +64,14 @@ CREATE TABLE vote_history (
     vote TEXT NOT NULL,
     score INTEGER NOT NULL,
     FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
-);
\ No newline at end of file
+);
+
+-- Créer la table des votes
+CREATE TABLE votes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    poll_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    vote TEXT NOT NULL,
+    FOREIGN KEY (poll_id) REFERENCES polls(poll_id),
+    FOREIGN KEY (user_id) REFERENCES voters(voter_id)
+);
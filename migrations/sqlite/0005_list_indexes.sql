@@ -0,0 +1,9 @@
+-- Index pour parcourir les listes paginées dans l'ordre de tri
+CREATE INDEX voters_last_name ON voters (last_name, voter_id);
+CREATE INDEX voters_status ON voters (status);
+CREATE INDEX polls_opening_time ON polls (poll_opening_time, poll_id);
+CREATE INDEX polls_closing_time ON polls (poll_closing_time, poll_id);
+CREATE INDEX poll_questions_poll_topic ON poll_questions (poll_id, question_topic);
+CREATE INDEX delegations_from_end_date ON delegations (from_principal, end_date, delegation_id);
+CREATE INDEX delegations_to_end_date ON delegations (to_delegate, end_date, delegation_id);
+CREATE INDEX question_results_poll ON question_results (poll_id, question_id);
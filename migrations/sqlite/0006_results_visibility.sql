@@ -0,0 +1,2 @@
+-- Visibilité des résultats provisoires pendant le scrutin
+ALTER TABLE polls ADD COLUMN results_visibility TEXT NOT NULL DEFAULT 'Secret';
@@ -0,0 +1,17 @@
+-- Mots de passe des électeurs, hachés avec Argon2id (hors du journal d'événements).
+-- Pas de clé étrangère vers voters : cette table est vidée et rejouée par
+-- rebuild-projections, alors que les comptes doivent survivre.
+CREATE TABLE credentials (
+    voter_id TEXT PRIMARY KEY,
+    password_hash TEXT NOT NULL
+);
+
+-- Sessions ouvertes, identifiées par l'empreinte SHA-256 de leur jeton
+CREATE TABLE sessions (
+    token_hash TEXT PRIMARY KEY,
+    voter_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+CREATE INDEX sessions_voter_id ON sessions (voter_id);
@@ -0,0 +1,13 @@
+-- Date à laquelle l'électeur a confirmé son adresse email
+ALTER TABLE voters ADD COLUMN email_verified_at TEXT;
+
+-- Jetons à usage unique envoyés par email (confirmation d'adresse, connexion
+-- par lien magique), identifiés par l'empreinte SHA-256 du jeton
+CREATE TABLE email_tokens (
+    token_hash TEXT PRIMARY KEY,
+    voter_id TEXT NOT NULL,
+    purpose TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+CREATE INDEX email_tokens_voter_id ON email_tokens (voter_id);
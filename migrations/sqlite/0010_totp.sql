@@ -0,0 +1,14 @@
+-- Secrets TOTP des électeurs inscrits à la double authentification
+CREATE TABLE totp_enrollments (
+    voter_id TEXT PRIMARY KEY,
+    secret TEXT NOT NULL,
+    confirmed_at TEXT,
+    last_used_step INTEGER NOT NULL
+);
+
+-- Codes de secours à usage unique, hachés
+CREATE TABLE recovery_codes (
+    voter_id TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    PRIMARY KEY (voter_id, code_hash)
+);
@@ -0,0 +1,19 @@
+-- Identités du fournisseur OpenID Connect liées à un électeur (hors du
+-- journal d'événements, comme les comptes)
+CREATE TABLE oidc_identities (
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX oidc_identities_voter_id ON oidc_identities (voter_id);
+
+-- Connexions en cours chez le fournisseur, identifiées par l'empreinte
+-- SHA-256 du paramètre state ; le vérificateur PKCE reste côté serveur
+CREATE TABLE oidc_logins (
+    state_hash TEXT PRIMARY KEY,
+    pkce_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
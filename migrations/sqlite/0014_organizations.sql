@@ -0,0 +1,90 @@
+-- Organisations hébergées par le serveur (projection de OrganizationCreated /
+-- OrganizationUpdated) ; le slug les désigne dans le sous-domaine ou le
+-- préfixe de chemin
+CREATE TABLE organizations (
+    organization_id TEXT PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    settings TEXT NOT NULL DEFAULT '{}'
+);
+
+-- Membres des organisations (projection de MemberAdded / MemberRemoved)
+CREATE TABLE organization_members (
+    organization_id TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (organization_id, voter_id),
+    FOREIGN KEY (organization_id) REFERENCES organizations(organization_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+CREATE INDEX organization_members_voter_id ON organization_members (voter_id);
+
+-- Organisation de chaque événement, pour que les auditeurs ne lisent que le
+-- journal de la leur ; les événements de compte n'en ont pas
+ALTER TABLE events ADD COLUMN organization_id TEXT;
+
+UPDATE events SET organization_id = '00000000-0000-0000-0000-000000000001'
+WHERE event_type NOT IN ('VoterCreated', 'VoterStatusChanged', 'EmailVerified');
+
+CREATE INDEX events_organization_id ON events (organization_id, event_id);
+
+-- Tout ce qui existe revient à l'organisation par défaut, créée et peuplée
+-- via le journal pour survivre à rebuild-projections
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+VALUES (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'OrganizationCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+        '{"type":"OrganizationCreated","data":{"organization_id":"00000000-0000-0000-0000-000000000001","slug":"default","name":"Default","settings":{}}}');
+
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'MemberAdded', '00000000-0000-0000-0000-000000000001', NULL, voter_id, NULL,
+       '{"type":"MemberAdded","data":{"organization_id":"00000000-0000-0000-0000-000000000001","voter_id":"' || voter_id || '"}}'
+FROM voters;
+
+INSERT INTO organizations (organization_id, slug, name, settings)
+VALUES ('00000000-0000-0000-0000-000000000001', 'default', 'Default', '{}');
+
+INSERT INTO organization_members (organization_id, voter_id)
+SELECT '00000000-0000-0000-0000-000000000001', voter_id FROM voters;
+
+-- Rôles et groupes par organisation ; SQLite ne sait pas modifier une clé
+-- primaire, d'où la copie des tables
+CREATE TABLE voter_roles_by_organization (
+    organization_id TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (organization_id, voter_id, role),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+INSERT INTO voter_roles_by_organization (organization_id, voter_id, role)
+SELECT '00000000-0000-0000-0000-000000000001', voter_id, role FROM voter_roles;
+
+DROP TABLE voter_roles;
+ALTER TABLE voter_roles_by_organization RENAME TO voter_roles;
+
+CREATE TABLE group_members_by_organization (
+    organization_id TEXT NOT NULL,
+    group_name TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    PRIMARY KEY (organization_id, group_name, voter_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+INSERT INTO group_members_by_organization (organization_id, group_name, voter_id)
+SELECT '00000000-0000-0000-0000-000000000001', group_name, voter_id FROM group_members;
+
+DROP TABLE group_members;
+ALTER TABLE group_members_by_organization RENAME TO group_members;
+
+CREATE INDEX group_members_voter_id ON group_members (voter_id);
+
+-- Scrutins, délégations, historiques, clés d'API et connexions SSO en cours
+-- appartiennent à une organisation
+ALTER TABLE polls ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE delegations ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE vote_history ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE api_keys ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE oidc_logins ADD COLUMN organization_id TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+
+CREATE INDEX polls_organization_opening_time ON polls (organization_id, poll_opening_time, poll_id);
+CREATE INDEX delegations_organization_id ON delegations (organization_id);
+CREATE INDEX vote_history_organization_voter ON vote_history (organization_id, voter_id);
@@ -0,0 +1,104 @@
+-- Thèmes définis par chaque organisation (projection de TopicCreated /
+-- TopicUpdated) ; un thème archivé reste sur les délégations et questions
+-- qui l'utilisent
+CREATE TABLE topics (
+    topic_id TEXT PRIMARY KEY,
+    organization_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    archived_at TEXT,
+    UNIQUE (organization_id, name),
+    FOREIGN KEY (organization_id) REFERENCES organizations(organization_id)
+);
+
+-- Les thèmes de l'ancienne énumération deviennent ceux de l'organisation par
+-- défaut, via le journal pour survivre à rebuild-projections ; les anciens
+-- événements qui les nomment se lisent comme ces identifiants
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+VALUES
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000001","organization_id":"00000000-0000-0000-0000-000000000001","name":"Geopolitics","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000002","organization_id":"00000000-0000-0000-0000-000000000001","name":"Defense","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000003","organization_id":"00000000-0000-0000-0000-000000000001","name":"Work","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000004","organization_id":"00000000-0000-0000-0000-000000000001","name":"Industry","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000005","organization_id":"00000000-0000-0000-0000-000000000001","name":"Family","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000006","organization_id":"00000000-0000-0000-0000-000000000001","name":"Finances","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000007","organization_id":"00000000-0000-0000-0000-000000000001","name":"Education","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000008","organization_id":"00000000-0000-0000-0000-000000000001","name":"Research","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000009","organization_id":"00000000-0000-0000-0000-000000000001","name":"Judicial","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000a","organization_id":"00000000-0000-0000-0000-000000000001","name":"Law enforcement","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000b","organization_id":"00000000-0000-0000-0000-000000000001","name":"Environment","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000c","organization_id":"00000000-0000-0000-0000-000000000001","name":"Energy","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000d","organization_id":"00000000-0000-0000-0000-000000000001","name":"Medical","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000e","organization_id":"00000000-0000-0000-0000-000000000001","name":"Culture","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-00000000000f","organization_id":"00000000-0000-0000-0000-000000000001","name":"Technology","description":"","archived_at":null}}'),
+    (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'TopicCreated', '00000000-0000-0000-0000-000000000001', NULL, NULL, NULL,
+     '{"type":"TopicCreated","data":{"topic_id":"00000000-0000-0001-0000-000000000010","organization_id":"00000000-0000-0000-0000-000000000001","name":"Sports","description":"","archived_at":null}}');
+
+INSERT INTO topics (topic_id, organization_id, name, description, archived_at)
+VALUES
+    ('00000000-0000-0001-0000-000000000001', '00000000-0000-0000-0000-000000000001', 'Geopolitics', '', NULL),
+    ('00000000-0000-0001-0000-000000000002', '00000000-0000-0000-0000-000000000001', 'Defense', '', NULL),
+    ('00000000-0000-0001-0000-000000000003', '00000000-0000-0000-0000-000000000001', 'Work', '', NULL),
+    ('00000000-0000-0001-0000-000000000004', '00000000-0000-0000-0000-000000000001', 'Industry', '', NULL),
+    ('00000000-0000-0001-0000-000000000005', '00000000-0000-0000-0000-000000000001', 'Family', '', NULL),
+    ('00000000-0000-0001-0000-000000000006', '00000000-0000-0000-0000-000000000001', 'Finances', '', NULL),
+    ('00000000-0000-0001-0000-000000000007', '00000000-0000-0000-0000-000000000001', 'Education', '', NULL),
+    ('00000000-0000-0001-0000-000000000008', '00000000-0000-0000-0000-000000000001', 'Research', '', NULL),
+    ('00000000-0000-0001-0000-000000000009', '00000000-0000-0000-0000-000000000001', 'Judicial', '', NULL),
+    ('00000000-0000-0001-0000-00000000000a', '00000000-0000-0000-0000-000000000001', 'Law enforcement', '', NULL),
+    ('00000000-0000-0001-0000-00000000000b', '00000000-0000-0000-0000-000000000001', 'Environment', '', NULL),
+    ('00000000-0000-0001-0000-00000000000c', '00000000-0000-0000-0000-000000000001', 'Energy', '', NULL),
+    ('00000000-0000-0001-0000-00000000000d', '00000000-0000-0000-0000-000000000001', 'Medical', '', NULL),
+    ('00000000-0000-0001-0000-00000000000e', '00000000-0000-0000-0000-000000000001', 'Culture', '', NULL),
+    ('00000000-0000-0001-0000-00000000000f', '00000000-0000-0000-0000-000000000001', 'Technology', '', NULL),
+    ('00000000-0000-0001-0000-000000000010', '00000000-0000-0000-0000-000000000001', 'Sports', '', NULL);
+
+-- Délégations et questions désignent désormais les thèmes par identifiant
+UPDATE delegations SET topics = REPLACE(topics, '"Geopolitics"', '"00000000-0000-0001-0000-000000000001"');
+UPDATE delegations SET topics = REPLACE(topics, '"Defense"', '"00000000-0000-0001-0000-000000000002"');
+UPDATE delegations SET topics = REPLACE(topics, '"Work"', '"00000000-0000-0001-0000-000000000003"');
+UPDATE delegations SET topics = REPLACE(topics, '"Industry"', '"00000000-0000-0001-0000-000000000004"');
+UPDATE delegations SET topics = REPLACE(topics, '"Family"', '"00000000-0000-0001-0000-000000000005"');
+UPDATE delegations SET topics = REPLACE(topics, '"Finances"', '"00000000-0000-0001-0000-000000000006"');
+UPDATE delegations SET topics = REPLACE(topics, '"Education"', '"00000000-0000-0001-0000-000000000007"');
+UPDATE delegations SET topics = REPLACE(topics, '"Research"', '"00000000-0000-0001-0000-000000000008"');
+UPDATE delegations SET topics = REPLACE(topics, '"Judicial"', '"00000000-0000-0001-0000-000000000009"');
+UPDATE delegations SET topics = REPLACE(topics, '"LawEnforcement"', '"00000000-0000-0001-0000-00000000000a"');
+UPDATE delegations SET topics = REPLACE(topics, '"Environment"', '"00000000-0000-0001-0000-00000000000b"');
+UPDATE delegations SET topics = REPLACE(topics, '"Energy"', '"00000000-0000-0001-0000-00000000000c"');
+UPDATE delegations SET topics = REPLACE(topics, '"Medical"', '"00000000-0000-0001-0000-00000000000d"');
+UPDATE delegations SET topics = REPLACE(topics, '"Culture"', '"00000000-0000-0001-0000-00000000000e"');
+UPDATE delegations SET topics = REPLACE(topics, '"Technology"', '"00000000-0000-0001-0000-00000000000f"');
+UPDATE delegations SET topics = REPLACE(topics, '"Sports"', '"00000000-0000-0001-0000-000000000010"');
+
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000001' WHERE question_topic = 'Geopolitics';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000002' WHERE question_topic = 'Defense';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000003' WHERE question_topic = 'Work';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000004' WHERE question_topic = 'Industry';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000005' WHERE question_topic = 'Family';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000006' WHERE question_topic = 'Finances';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000007' WHERE question_topic = 'Education';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000008' WHERE question_topic = 'Research';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000009' WHERE question_topic = 'Judicial';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000a' WHERE question_topic = 'LawEnforcement';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000b' WHERE question_topic = 'Environment';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000c' WHERE question_topic = 'Energy';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000d' WHERE question_topic = 'Medical';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000e' WHERE question_topic = 'Culture';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-00000000000f' WHERE question_topic = 'Technology';
+UPDATE poll_questions SET question_topic = '00000000-0000-0001-0000-000000000010' WHERE question_topic = 'Sports';
@@ -0,0 +1,12 @@
+-- Une question peut porter sur plusieurs thèmes, le plus important d'abord
+-- (tableau JSON d'identifiants)
+ALTER TABLE poll_questions ADD COLUMN question_topics TEXT NOT NULL DEFAULT '[]';
+UPDATE poll_questions SET question_topics = '["' || question_topic || '"]';
+DROP INDEX poll_questions_poll_topic;
+ALTER TABLE poll_questions DROP COLUMN question_topic;
+
+-- Dépouillement à la clôture (projection de PollTallied) : votes délégués et
+-- explication à côté des scores des bulletins
+ALTER TABLE question_results ADD COLUMN delegated_results TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE question_results ADD COLUMN report TEXT NOT NULL DEFAULT 'null';
+CREATE UNIQUE INDEX poll_results_poll ON poll_results (poll_id);
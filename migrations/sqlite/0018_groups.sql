@@ -0,0 +1,25 @@
+-- Groupes d'électeurs déclarés par l'organisation : comités, sections
+-- locales, collèges (projection de GroupCreated / GroupUpdated / GroupDeleted)
+CREATE TABLE voter_groups (
+    organization_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (organization_id, name),
+    FOREIGN KEY (organization_id) REFERENCES organizations(organization_id)
+);
+
+-- Les groupes qui ont déjà des membres deviennent des groupes déclarés, via
+-- le journal pour survivre à rebuild-projections
+INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+SELECT DISTINCT strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), NULL, 'GroupCreated', organization_id, NULL, NULL, NULL,
+       json_object('type', 'GroupCreated', 'data',
+                   json_object('organization_id', organization_id, 'name', group_name, 'description', ''))
+FROM group_members;
+
+INSERT INTO voter_groups (organization_id, name)
+SELECT DISTINCT organization_id, group_name FROM group_members;
+
+-- Ventilation des résultats par groupe et collèges pondérés (JSON)
+ALTER TABLE polls ADD COLUMN breakdown TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE question_results ADD COLUMN group_results TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE question_results ADD COLUMN college_results TEXT NOT NULL DEFAULT 'null';
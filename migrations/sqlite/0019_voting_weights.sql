@@ -0,0 +1,16 @@
+-- Poids de vote des membres, chacun à partir d'une date : l'historique est
+-- conservé pour compter chaque scrutin avec les poids de son ouverture
+-- (projection de VotingWeightSet)
+CREATE TABLE voting_weights (
+    organization_id TEXT NOT NULL,
+    voter_id TEXT NOT NULL,
+    weight REAL NOT NULL,
+    effective_from TEXT NOT NULL,
+    PRIMARY KEY (organization_id, voter_id, effective_from),
+    FOREIGN KEY (organization_id) REFERENCES organizations(organization_id),
+    FOREIGN KEY (voter_id) REFERENCES voters(voter_id)
+);
+
+-- Résultats bruts, chaque électeur pesant 1, à côté des résultats pondérés
+ALTER TABLE question_results ADD COLUMN raw_vote_results TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE question_results ADD COLUMN raw_delegated_results TEXT NOT NULL DEFAULT '[]';
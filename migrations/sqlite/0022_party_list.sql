@@ -0,0 +1,6 @@
+-- Répartition des sièges des questions à scrutin de liste
+ALTER TABLE poll_questions ADD COLUMN apportionment TEXT;
+ALTER TABLE poll_questions ADD COLUMN threshold REAL;
+
+-- Sièges obtenus par chaque liste et tableau des quotients (JSON)
+ALTER TABLE question_results ADD COLUMN seat_allocation TEXT NOT NULL DEFAULT 'null';
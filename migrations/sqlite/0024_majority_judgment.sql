@@ -0,0 +1,5 @@
+-- Échelle de mentions des questions au jugement majoritaire (JSON)
+ALTER TABLE poll_questions ADD COLUMN grades TEXT NOT NULL DEFAULT '[]';
+
+-- Profils de mérite et classement (JSON)
+ALTER TABLE question_results ADD COLUMN majority_judgment TEXT NOT NULL DEFAULT 'null';
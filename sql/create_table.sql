@@ -1,5 +0,0 @@
-CREATE TABLE votes (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    user_id TEXT NOT NULL,
-    vote TEXT NOT NULL
-);
\ No newline at end of file
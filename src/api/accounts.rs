@@ -0,0 +1,326 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use serde::Deserialize;
+use sqlx::Row;
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+
+use super::voters::{self, CreateUserPayload};
+use crate::auth::{self, EmailToken, Session};
+use crate::db;
+use crate::error::{ApiError, Json, Problem, Query};
+use crate::events::{self, Event};
+use crate::mail::Email;
+use crate::tenant::Tenant;
+use crate::{AppState, Voter};
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterPayload {
+    #[serde(flatten)]
+    voter: CreateUserPayload,
+    /// At least 12 characters.
+    password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginPayload {
+    email: String,
+    password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MagicLinkPayload {
+    email: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokenQuery {
+    /// Token from the emailed link.
+    token: String,
+}
+
+/// Registers a voter with a password as a member of the organization, logs
+/// them in and emails them a link to confirm their address.
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts",
+    tag = "accounts",
+    request_body = RegisterPayload,
+    responses(
+        (status = 201, description = "Voter registered, session cookie set", body = Voter),
+        (status = 409, description = "Email already registered", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(payload): Json<RegisterPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.password.chars().count() < auth::MIN_PASSWORD_LENGTH {
+        return Err(ApiError::Validation(format!(
+            "The password must be at least {} characters long",
+            auth::MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    let voter = voters::new_voter(payload.voter)?;
+    let password_hash = auth::hash_password(payload.password).await?;
+
+    let mut tx = state.pool.begin().await?;
+    voters::record_new_voter(&mut tx, tenant.organization_id, voter.voter_id, &voter).await?;
+    auth::set_password_hash(&mut tx, voter.voter_id, &password_hash).await?;
+    tx.commit().await?;
+
+    // The account exists either way; the voter can ask for another email.
+    if let Err(e) = send_verification(&state, &voter).await {
+        eprintln!("Failed to send the verification email to {}: {e:?}", voter.email);
+    }
+
+    let cookie = auth::start_session(&state.pool, voter.voter_id).await?;
+    let location = format!("/api/v1/voters/{}", voter.voter_id);
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location), (header::SET_COOKIE, cookie)],
+        Json(voter),
+    ))
+}
+
+/// Logs in with an email and password.
+#[utoipa::path(
+    post,
+    path = "/api/v1/session",
+    tag = "accounts",
+    request_body = LoginPayload,
+    responses(
+        (status = 200, description = "Logged in, session cookie set", body = Voter),
+        (status = 401, description = "Wrong email or password", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(payload): Json<LoginPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Unknown emails and wrong passwords are reported alike.
+    let rejected = || ApiError::Unauthorized("Wrong email or password".to_owned());
+
+    let row = sqlx::query(
+        r#"
+        SELECT voters.voter_id, credentials.password_hash
+        FROM voters
+        JOIN credentials ON credentials.voter_id = voters.voter_id
+        WHERE voters.email = $1
+        "#,
+    )
+    .bind(&payload.email)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(rejected)?;
+
+    if !auth::verify_password(payload.password, row.try_get("password_hash")?).await? {
+        return Err(rejected());
+    }
+
+    let voter = voters::load_account(&state.pool, tenant.organization_id, db::parse_column(&row, "voter_id")?).await?;
+    let cookie = auth::start_session(&state.pool, voter.voter_id).await?;
+
+    Ok(([(header::SET_COOKIE, cookie)], Json(voter)))
+}
+
+/// Returns the logged-in voter.
+#[utoipa::path(
+    get,
+    path = "/api/v1/session",
+    tag = "accounts",
+    responses(
+        (status = 200, description = "Logged-in voter", body = Voter),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_session(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+) -> Result<Json<Voter>, ApiError> {
+    Ok(Json(voters::load_account(&state.pool, tenant.organization_id, session.voter_id).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/session",
+    tag = "accounts",
+    responses(
+        (status = 204, description = "Logged out, session cookie cleared"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    session: Session,
+) -> Result<impl IntoResponse, ApiError> {
+    session.end(&state.pool).await?;
+
+    Ok((
+        StatusCode::NO_CONTENT,
+        [(header::SET_COOKIE, auth::expired_session_cookie())],
+    ))
+}
+
+/// Emails `voter` a link that confirms their address.
+pub(super) async fn send_verification(state: &AppState, voter: &Voter) -> Result<(), ApiError> {
+    let token = auth::issue_email_token(&state.pool, voter.voter_id, EmailToken::VerifyEmail).await?;
+    let email = Email {
+        to: voter.email.clone(),
+        subject: "Confirm your email address".to_owned(),
+        body: format!(
+            "Hello {},\n\nOpen this link within two days to confirm your address and take part in votes:\n\n{}/api/v1/accounts/verify?token={token}\n",
+            voter.first_name, state.public_url
+        ),
+    };
+
+    state
+        .mailer
+        .send(email)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Mailer failed: {e}")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/verify",
+    tag = "accounts",
+    params(TokenQuery),
+    responses(
+        (status = 200, description = "Email address confirmed", body = Voter),
+        (status = 401, description = "Invalid or expired link", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<TokenQuery>,
+) -> Result<Json<Voter>, ApiError> {
+    let voter_id = auth::redeem_email_token(&state.pool, &query.token, EmailToken::VerifyEmail).await?;
+    let voter = voters::load_account(&state.pool, tenant.organization_id, voter_id).await?;
+
+    Ok(Json(mark_verified(&state, voter).await?))
+}
+
+/// Sends the logged-in voter another confirmation link.
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts/verification",
+    tag = "accounts",
+    responses(
+        (status = 202, description = "Confirmation email sent"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Address already confirmed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+) -> Result<StatusCode, ApiError> {
+    if session.email_verified {
+        return Err(ApiError::Conflict("Your email address is already confirmed".to_owned()));
+    }
+
+    let voter = voters::load_account(&state.pool, tenant.organization_id, session.voter_id).await?;
+    send_verification(&state, &voter).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Emails a single-use login link to the voter registered with `email`.
+///
+/// Always answers 202, so the endpoint does not reveal who is registered.
+#[utoipa::path(
+    post,
+    path = "/api/v1/session/magic-link",
+    tag = "accounts",
+    request_body = MagicLinkPayload,
+    responses(
+        (status = 202, description = "Login link sent if the address is registered"),
+    )
+)]
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    Json(payload): Json<MagicLinkPayload>,
+) -> Result<StatusCode, ApiError> {
+    let row = sqlx::query("SELECT voter_id, first_name FROM voters WHERE email = $1")
+        .bind(&payload.email)
+        .fetch_optional(&state.pool)
+        .await?;
+
+    if let Some(row) = row {
+        let voter_id = db::parse_column(&row, "voter_id")?;
+        let token = auth::issue_email_token(&state.pool, voter_id, EmailToken::LogIn).await?;
+        let email = Email {
+            to: payload.email,
+            subject: "Your login link".to_owned(),
+            body: format!(
+                "Hello {},\n\nOpen this link within 15 minutes to log in:\n\n{}/api/v1/session/magic?token={token}\n\nIf you did not ask for it, ignore this email.\n",
+                row.try_get::<String, _>("first_name")?,
+                state.public_url
+            ),
+        };
+        state
+            .mailer
+            .send(email)
+            .await
+            .map_err(|e| ApiError::Internal(format!("Mailer failed: {e}")))?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Logs in with an emailed link and redirects to the home page. Following
+/// the link also confirms the address.
+#[utoipa::path(
+    get,
+    path = "/api/v1/session/magic",
+    tag = "accounts",
+    params(TokenQuery),
+    responses(
+        (status = 303, description = "Logged in, session cookie set"),
+        (status = 401, description = "Invalid or expired link", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn magic_login(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<TokenQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let voter_id = auth::redeem_email_token(&state.pool, &query.token, EmailToken::LogIn).await?;
+    let voter = voters::load_account(&state.pool, tenant.organization_id, voter_id).await?;
+    mark_verified(&state, voter).await?;
+
+    let cookie = auth::start_session(&state.pool, voter_id).await?;
+
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to("/")))
+}
+
+pub(super) async fn mark_verified(state: &AppState, voter: Voter) -> Result<Voter, ApiError> {
+    if voter.email_verified_at.is_some() {
+        return Ok(voter);
+    }
+
+    let verified_at = OffsetDateTime::now_utc();
+    let event = Event::EmailVerified {
+        voter_id: voter.voter_id,
+        verified_at,
+    };
+    events::record(&state.pool, Some(voter.voter_id), &event).await?;
+
+    Ok(Voter {
+        email_verified_at: Some(verified_at),
+        ..voter
+    })
+}
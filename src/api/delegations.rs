@@ -0,0 +1,351 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use time::{Date, OffsetDateTime};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::pagination::{Keyset, Order, Page};
+use crate::auth::{Authorized, Delegate, Session};
+use crate::db::{self, Pool, SqlBuilder};
+use crate::delegation::{self, delegation_from_row, AppliedDelegation};
+use crate::error::{ApiError, Json, Path, Problem, Query};
+use crate::events::{self, Event};
+use crate::taxonomy;
+use crate::tenant::{self, Tenant};
+use crate::{AppState, ConflictPolicy, Delegation, Poll};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateDelegationPayload {
+    pub to: Uuid,
+    /// Ids of active topics of the organization.
+    pub topics: Vec<Uuid>,
+    pub end_date: Date,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListDelegationsQuery {
+    /// Only delegations covering this topic.
+    topic: Option<Uuid>,
+    /// Only delegations to this voter.
+    delegate: Option<Uuid>,
+    #[serde(default)]
+    order: Order,
+    /// Page size, 50 by default and at most 500.
+    limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+/// Lists the delegations `voter_id` gave or received, by end date.
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/delegations",
+    tag = "delegations",
+    params(("voter_id" = Uuid, Path, description = "Voter id"), ListDelegationsQuery),
+    responses(
+        (status = 200, description = "A page of delegations given or received by the voter", body = Page<Delegation>),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid query", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_delegations(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+    Query(query): Query<ListDelegationsQuery>,
+) -> Result<Json<Page<Delegation>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    let keyset = Keyset::new(query.limit, query.cursor.as_deref(), query.order)?;
+
+    // Fails with a 404 for unknown voters rather than an empty list.
+    super::voters::load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT delegation_id, organization_id, from_principal, to_delegate, topics, end_date
+        FROM delegations
+        WHERE organization_id = "#,
+    );
+    sql.push_bind(tenant.organization_id.to_string())
+        .push(" AND (from_principal = ")
+        .push_bind(voter_id.to_string())
+        .push(" OR to_delegate = ")
+        .push_bind(voter_id.to_string())
+        .push(")");
+    if let Some(topic) = query.topic {
+        // `topics` is a JSON array of topic ids.
+        sql.push(" AND topics LIKE ").push_bind(format!("%\"{topic}\"%"));
+    }
+    if let Some(delegate) = query.delegate {
+        sql.push(" AND to_delegate = ").push_bind(delegate.to_string());
+    }
+    keyset.finish(&mut sql, "end_date", "delegation_id");
+
+    let delegations = sql
+        .query()
+        .fetch_all(&state.pool)
+        .await?
+        .iter()
+        .map(delegation_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(keyset.page(delegations, |delegation| {
+        (delegation.end_date.to_string(), delegation.delegation_id.to_string())
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voters/{voter_id}/delegations",
+    tag = "delegations",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("X-Second-Factor" = Option<String>, Header, description = "Current TOTP code or a recovery code, for voters who set one up"),
+    ),
+    request_body = CreateDelegationPayload,
+    responses(
+        (status = 201, description = "Delegation created", body = Delegation),
+        (status = 401, description = "Not logged in, or missing or wrong second factor", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the logged-in voter, or not a voter", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request, or the delegate is not a member of the organization", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_delegation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<Delegate>,
+    Path(voter_id): Path<Uuid>,
+    Json(payload): Json<CreateDelegationPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(voter_id)?;
+    let delegation = add_delegation(&state, tenant.organization_id, voter_id, payload).await?;
+    let location = format!(
+        "/api/v1/voters/{voter_id}/delegations/{}",
+        delegation.delegation_id
+    );
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(delegation)))
+}
+
+pub(super) async fn add_delegation(
+    state: &AppState,
+    organization_id: Uuid,
+    from: Uuid,
+    payload: CreateDelegationPayload,
+) -> Result<Delegation, ApiError> {
+    if from == payload.to {
+        return Err(ApiError::Validation("A voter cannot delegate to themselves".to_owned()));
+    }
+    if payload.topics.is_empty() {
+        return Err(ApiError::Validation("A delegation needs at least one topic".to_owned()));
+    }
+    if payload.end_date < OffsetDateTime::now_utc().date() {
+        return Err(ApiError::Validation("The delegation end date is in the past".to_owned()));
+    }
+    if !tenant::is_member(&state.pool, organization_id, payload.to).await? {
+        return Err(ApiError::Validation(format!(
+            "Voter {} is not a member of this organization",
+            payload.to
+        )));
+    }
+    taxonomy::ensure_usable(&state.pool, organization_id, &payload.topics).await?;
+
+    let delegation = Delegation {
+        delegation_id: Uuid::new_v4(),
+        organization_id,
+        from_principal: from,
+        to_delegate: payload.to,
+        topics: payload.topics,
+        end_date: payload.end_date,
+    };
+
+    events::record(
+        &state.pool,
+        Some(delegation.from_principal),
+        &Event::DelegationAdded(delegation.clone()),
+    )
+    .await?;
+
+    Ok(delegation)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/voters/{voter_id}/delegations/{delegation_id}",
+    tag = "delegations",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("delegation_id" = Uuid, Path, description = "Delegation id"),
+        ("X-Second-Factor" = Option<String>, Header, description = "Current TOTP code or a recovery code, for voters who set one up"),
+    ),
+    responses(
+        (status = 204, description = "Delegation revoked"),
+        (status = 401, description = "Not logged in, or missing or wrong second factor", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the logged-in voter, or not a voter", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown delegation", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_delegation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<Delegate>,
+    Path((voter_id, delegation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    session.ensure_is(voter_id)?;
+    remove_delegation(&state, tenant.organization_id, voter_id, delegation_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Shows, for each question of a poll, which of `voter_id`'s delegations
+/// apply: for each topic of the question, the one naming it or else the
+/// nearest broader topic, with conflicts between delegates settled by the
+/// organization's policy. Delegations ending before the poll opens are left
+/// out.
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/polls/{poll_id}/delegations",
+    tag = "delegations",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("poll_id" = Uuid, Path, description = "Poll id"),
+    ),
+    responses(
+        (status = 200, description = "The delegations applying to each question, by question", body = [AppliedDelegation]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter or poll", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_applied_delegations(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path((voter_id, poll_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<AppliedDelegation>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    super::voters::load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+    let poll = super::polls::load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+
+    let policy = tenant.settings.delegation_conflicts;
+
+    Ok(Json(applied_delegations(&state.pool, &poll, voter_id, policy).await?))
+}
+
+/// Resolves, for each question of `poll`, the delegations of `voter_id`
+/// that apply to it, see [`crate::delegation`].
+pub(super) async fn applied_delegations(
+    pool: &Pool,
+    poll: &Poll,
+    voter_id: Uuid,
+    policy: ConflictPolicy,
+) -> Result<Vec<AppliedDelegation>, sqlx::Error> {
+    let parents = taxonomy::parents(pool, poll.organization_id).await?;
+    let given = delegation::in_force(pool, poll, Some(voter_id))
+        .await?
+        .remove(&voter_id)
+        .unwrap_or_default();
+
+    Ok(poll
+        .questions
+        .iter()
+        .map(|question| delegation::resolve(&parents, &given, question, policy))
+        .collect())
+}
+
+/// Revokes a delegation given by `from`.
+pub(super) async fn remove_delegation(
+    state: &AppState,
+    organization_id: Uuid,
+    from: Uuid,
+    delegation_id: Uuid,
+) -> Result<(), ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT to_delegate
+        FROM delegations
+        WHERE delegation_id = $1 AND from_principal = $2 AND organization_id = $3
+        "#,
+    )
+    .bind(delegation_id.to_string())
+    .bind(from.to_string())
+    .bind(organization_id.to_string())
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ApiError::missing(format!("Delegation {delegation_id}")))?;
+
+    let event = Event::DelegationRemoved {
+        organization_id,
+        delegation_id,
+        from,
+        to: db::parse_column(&row, "to_delegate")?,
+    };
+
+    events::record(&state.pool, Some(from), &event)
+        .await
+        .map_err(ApiError::missing(format!("Delegation {delegation_id}")))
+}
+
+/// Loads every delegation `voter_id` is the principal or the delegate of in
+/// the organization.
+pub(super) async fn load_delegations(
+    pool: &Pool,
+    organization_id: Uuid,
+    voter_id: Uuid,
+) -> Result<Vec<Delegation>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT delegation_id, organization_id, from_principal, to_delegate, topics, end_date
+        FROM delegations
+        WHERE organization_id = $1 AND (from_principal = $2 OR to_delegate = $2)
+        ORDER BY end_date, delegation_id
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(voter_id.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(delegation_from_row).collect()
+}
+
+/// Loads every delegation given or received by any of `voter_ids` in the
+/// organization.
+pub(super) async fn load_delegations_of(
+    pool: &Pool,
+    organization_id: Uuid,
+    voter_ids: &[Uuid],
+) -> Result<Vec<Delegation>, sqlx::Error> {
+    if voter_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = voter_ids.iter().map(Uuid::to_string).collect::<Vec<_>>();
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT delegation_id, organization_id, from_principal, to_delegate, topics, end_date
+        FROM delegations
+        WHERE organization_id = "#,
+    );
+    sql.push_bind(organization_id.to_string())
+        .push(" AND (from_principal IN ")
+        .push_bind_list(ids.iter().cloned())
+        .push(" OR to_delegate IN ")
+        .push_bind_list(ids)
+        .push(") ORDER BY end_date, delegation_id");
+
+    sql.query()
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(delegation_from_row)
+        .collect()
+}
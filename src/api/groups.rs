@@ -0,0 +1,389 @@
+//! The groups of the organization: committees, local chapters, employee
+//! colleges... Polls can be restricted to some of them, and break their
+//! results down by group.
+
+use std::collections::HashSet;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{any::AnyRow, Row};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{Authorized, ManageVoters, Public, ReadPolls};
+use crate::db::{self, Pool};
+use crate::error::{ApiError, Json, Path, Problem};
+use crate::events::{self, Event};
+use crate::tenant::{self, Tenant};
+use crate::{AppState, Breakdown, Eligibility, Group};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateGroupPayload {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateGroupPayload {
+    description: String,
+}
+
+/// Members to add to a group, by id or by email address.
+#[derive(Deserialize, ToSchema)]
+pub struct ImportMembersPayload {
+    #[serde(default)]
+    voter_ids: Vec<Uuid>,
+    #[serde(default)]
+    emails: Vec<String>,
+    /// Also removes the members who are not listed.
+    #[serde(default)]
+    replace: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    /// Voters who joined the group.
+    added: u64,
+    /// Listed voters who were already in it.
+    unchanged: u64,
+    /// Members who left it, when replacing.
+    removed: u64,
+    /// Ids and addresses matching no member of the organization; they were
+    /// skipped.
+    unknown: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups",
+    tag = "groups",
+    responses(
+        (status = 200, description = "Groups of the organization", body = [Group]),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_groups(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+) -> Result<Json<Vec<Group>>, ApiError> {
+    let rows = sqlx::query(
+        "SELECT organization_id, name, description FROM voter_groups WHERE organization_id = $1 ORDER BY name",
+    )
+    .bind(tenant.organization_id.to_string())
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(rows.iter().map(group_from_row).collect::<Result<_, _>>()?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups",
+    tag = "groups",
+    request_body = CreateGroupPayload,
+    responses(
+        (status = 201, description = "Group created", body = Group),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Name already used in the organization", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Json(payload): Json<CreateGroupPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.name.trim().is_empty() {
+        return Err(ApiError::Validation("A group needs a name".to_owned()));
+    }
+    let group = Group {
+        organization_id: tenant.organization_id,
+        name: payload.name,
+        description: payload.description,
+    };
+    events::record(&state.pool, Some(session.voter_id), &Event::GroupCreated(group.clone()))
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Conflict(_) => {
+                ApiError::Conflict(format!("A group named {:?} already exists", group.name))
+            }
+            e => e,
+        })?;
+    let location = format!("/api/v1/groups/{}", group.name);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(group)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{group}",
+    tag = "groups",
+    params(("group" = String, Path, description = "Group name")),
+    responses(
+        (status = 200, description = "Group", body = Group),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown group", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Path(group): Path<String>,
+) -> Result<Json<Group>, ApiError> {
+    Ok(Json(load(&state.pool, tenant.organization_id, &group).await?))
+}
+
+/// Describes a group anew. Groups are not renamed, as polls refer to them by
+/// name.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/groups/{group}",
+    tag = "groups",
+    params(("group" = String, Path, description = "Group name")),
+    request_body = UpdateGroupPayload,
+    responses(
+        (status = 200, description = "Updated group", body = Group),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown group", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path(group): Path<String>,
+    Json(payload): Json<UpdateGroupPayload>,
+) -> Result<Json<Group>, ApiError> {
+    let group = Group {
+        description: payload.description,
+        ..load(&state.pool, tenant.organization_id, &group).await?
+    };
+    events::record(&state.pool, Some(session.voter_id), &Event::GroupUpdated(group.clone()))
+        .await
+        .map_err(ApiError::missing(format!("Group {:?}", group.name)))?;
+
+    Ok(Json(group))
+}
+
+/// Deletes a group and removes its members from it. Groups that a poll not
+/// yet tallied refers to are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{group}",
+    tag = "groups",
+    params(("group" = String, Path, description = "Group name")),
+    responses(
+        (status = 204, description = "Group deleted"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown group", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A poll not yet tallied refers to the group", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path(group): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let organization_id = tenant.organization_id;
+    if let Some(poll_id) = poll_using(&state.pool, organization_id, &group).await? {
+        return Err(ApiError::Conflict(format!(
+            "Group {group:?} is used by poll {poll_id}, which is not tallied yet"
+        )));
+    }
+
+    let missing = ApiError::missing(format!("Group {group:?}"));
+    let event = Event::GroupDeleted { organization_id, group };
+    events::record(&state.pool, Some(session.voter_id), &event)
+        .await
+        .map_err(missing)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Adds many members of the organization to a group at once, or sets its
+/// members with `replace`. Unknown voters are reported and skipped.
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups/{group}/members",
+    tag = "groups",
+    params(("group" = String, Path, description = "Group name")),
+    request_body = ImportMembersPayload,
+    responses(
+        (status = 200, description = "Members imported", body = ImportReport),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown group", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn import_members(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path(group): Path<String>,
+    Json(payload): Json<ImportMembersPayload>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let organization_id = tenant.organization_id;
+    load(&state.pool, organization_id, &group).await?;
+
+    let mut report = ImportReport {
+        added: 0,
+        unchanged: 0,
+        removed: 0,
+        unknown: Vec::new(),
+    };
+    let mut listed = Vec::new();
+    for voter_id in payload.voter_ids {
+        if tenant::is_member(&state.pool, organization_id, voter_id).await? {
+            listed.push(voter_id);
+        } else {
+            report.unknown.push(voter_id.to_string());
+        }
+    }
+    for email in payload.emails {
+        let member = sqlx::query(
+            r#"
+            SELECT voters.voter_id
+            FROM voters
+            JOIN organization_members ON organization_members.voter_id = voters.voter_id
+            WHERE organization_members.organization_id = $1 AND voters.email = $2
+            "#,
+        )
+        .bind(organization_id.to_string())
+        .bind(email.trim())
+        .fetch_optional(&state.pool)
+        .await?;
+        match member {
+            Some(row) => listed.push(db::parse_column(&row, "voter_id")?),
+            None => report.unknown.push(email),
+        }
+    }
+
+    let current = sqlx::query(
+        "SELECT voter_id FROM group_members WHERE organization_id = $1 AND group_name = $2",
+    )
+    .bind(organization_id.to_string())
+    .bind(&group)
+    .fetch_all(&state.pool)
+    .await?
+    .iter()
+    .map(|row| db::parse_column(row, "voter_id"))
+    .collect::<Result<HashSet<Uuid>, _>>()?;
+
+    let mut tx = state.pool.begin().await?;
+    let mut seen = HashSet::new();
+    for voter_id in listed {
+        if !seen.insert(voter_id) {
+            continue;
+        }
+        if current.contains(&voter_id) {
+            report.unchanged += 1;
+            continue;
+        }
+        let event = Event::GroupJoined {
+            organization_id,
+            voter_id,
+            group: group.clone(),
+        };
+        events::record_in(&mut tx, Some(session.voter_id), &event).await?;
+        report.added += 1;
+    }
+    if payload.replace {
+        let mut leaving = current.difference(&seen).copied().collect::<Vec<_>>();
+        leaving.sort();
+        for voter_id in leaving {
+            let event = Event::GroupLeft {
+                organization_id,
+                voter_id,
+                group: group.clone(),
+            };
+            events::record_in(&mut tx, Some(session.voter_id), &event).await?;
+            report.removed += 1;
+        }
+    }
+    tx.commit().await?;
+
+    Ok(Json(report))
+}
+
+/// Checks that every one of `groups` exists in the organization.
+pub(super) async fn ensure_exist(pool: &Pool, organization_id: Uuid, groups: &[String]) -> Result<(), ApiError> {
+    for group in groups {
+        load(pool, organization_id, group).await.map_err(|e| match e {
+            ApiError::NotFound(_) => {
+                ApiError::Validation(format!("Group {group:?} does not exist in this organization"))
+            }
+            e => e,
+        })?;
+    }
+
+    Ok(())
+}
+
+pub(super) async fn load(pool: &Pool, organization_id: Uuid, name: &str) -> Result<Group, ApiError> {
+    let row = sqlx::query(
+        "SELECT organization_id, name, description FROM voter_groups WHERE organization_id = $1 AND name = $2",
+    )
+    .bind(organization_id.to_string())
+    .bind(name)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Group {name:?} does not exist")))?;
+
+    Ok(group_from_row(&row)?)
+}
+
+/// A poll of the organization whose eligibility or breakdown names `group`,
+/// and whose results are not recorded yet.
+async fn poll_using(pool: &Pool, organization_id: Uuid, group: &str) -> Result<Option<Uuid>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT poll_id, eligibility, breakdown
+        FROM polls
+        WHERE organization_id = $1
+            AND NOT EXISTS (SELECT 1 FROM poll_results WHERE poll_results.poll_id = polls.poll_id)
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    for row in &rows {
+        let eligibility: Eligibility = db::json_column(row, "eligibility")?;
+        let breakdown: Breakdown = db::json_column(row, "breakdown")?;
+        if eligibility
+            .groups
+            .iter()
+            .chain(&breakdown.groups)
+            .chain(breakdown.colleges.iter().map(|college| &college.group))
+            .any(|name| name == group)
+        {
+            return Ok(Some(db::parse_column(row, "poll_id")?));
+        }
+    }
+
+    Ok(None)
+}
+
+fn group_from_row(row: &AnyRow) -> Result<Group, sqlx::Error> {
+    Ok(Group {
+        organization_id: db::parse_column(row, "organization_id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+    })
+}
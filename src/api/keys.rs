@@ -0,0 +1,124 @@
+//! API keys for machine integrations, see [`crate::api_keys`].
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::api_keys::{self, ApiKey, Scope};
+use crate::auth::{Authorized, ManageApiKeys};
+use crate::error::{ApiError, Json, Path, Problem};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyPayload {
+    name: String,
+    scopes: Vec<Scope>,
+    /// When the key stops working; it never expires if absent.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    expires_at: Option<OffsetDateTime>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    api_key: ApiKey,
+    /// Value for the `Authorization: Bearer` header. It is not stored and
+    /// cannot be shown again.
+    secret: String,
+}
+
+/// Issues an API key acting on behalf of the logged-in admin, within its
+/// scopes and the organization.
+#[utoipa::path(
+    post,
+    path = "/api/v1/api-keys",
+    tag = "accounts",
+    request_body = CreateApiKeyPayload,
+    responses(
+        (status = 201, description = "Key issued", body = IssuedApiKey),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageApiKeys>,
+    Json(payload): Json<CreateApiKeyPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.name.trim().is_empty() {
+        return Err(ApiError::Validation("The key needs a name".to_owned()));
+    }
+    if payload.scopes.is_empty() {
+        return Err(ApiError::Validation("The key needs at least one scope".to_owned()));
+    }
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc()) {
+        return Err(ApiError::Validation("The expiry must be in the future".to_owned()));
+    }
+
+    let (api_key, secret) = api_keys::issue(
+        &state.pool,
+        tenant.organization_id,
+        session.voter_id,
+        payload.name,
+        payload.scopes,
+        payload.expires_at,
+    )
+    .await?;
+    let location = format!("/api/v1/api-keys/{}", api_key.key_id);
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(IssuedApiKey { api_key, secret }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-keys",
+    tag = "accounts",
+    responses(
+        (status = 200, description = "Every key of the organization, without its secret", body = [ApiKey]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _session: Authorized<ManageApiKeys>,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    Ok(Json(api_keys::list(&state.pool, tenant.organization_id).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/api-keys/{key_id}",
+    tag = "accounts",
+    params(("key_id" = Uuid, Path, description = "Key id")),
+    responses(
+        (status = 204, description = "Key revoked"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown key", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _session: Authorized<ManageApiKeys>,
+    Path(key_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    api_keys::revoke(&state.pool, tenant.organization_id, key_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,286 @@
+//! The RPC-style routes the server exposed before `/api/v1`.
+//!
+//! They keep their original request and response shapes but are deprecated:
+//! every response carries a `Deprecation` header and they will be removed in
+//! the next release. They enforce the same roles as their replacements and
+//! act for the logged-in voter; ids in their payloads must name that voter. Each one maps onto a `/api/v1` resource:
+//!
+//! | Deprecated route                                | Replacement                                          |
+//! |-------------------------------------------------|------------------------------------------------------|
+//! | `POST /create_user`                             | `POST /api/v1/voters`                                |
+//! | `POST /set_user_status/:user_id`                | `PATCH /api/v1/voters/:voter_id`                     |
+//! | `GET /check_user_status/:user_id`               | `GET /api/v1/voters/:voter_id`                       |
+//! | `POST /add_delegation`                          | `POST /api/v1/voters/:voter_id/delegations`          |
+//! | `POST /remove_delegation`                       | `DELETE /api/v1/voters/:voter_id/delegations/:id`    |
+//! | `POST /add_to_vote_history`                     | `POST /api/v1/voters/:voter_id/history`              |
+//! | `POST /create_question`                         | `POST /api/v1/polls/:poll_id/questions`              |
+//! | `POST /create_poll`                             | `POST /api/v1/polls`                                 |
+//! | `POST /cast_ballot`                             | `POST /api/v1/polls/:poll_id/ballots`                |
+//! | `GET /get_poll_participation/:poll_id`          | `GET /api/v1/polls/:poll_id/participation`           |
+//! | `GET /get_poll_results/:poll_id`                | `GET /api/v1/polls/:poll_id/results`                 |
+//! | `GET /get_question_results/:poll_question_id`   | `GET /api/v1/polls/:poll_id/questions/:id/results`   |
+//! | `GET /events`                                   | `GET /api/v1/events`                                 |
+
+use axum::{
+    extract::State,
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use time::Date;
+use uuid::Uuid;
+
+use super::{delegations, polls, voters};
+use crate::auth::{Authorized, CastBallots, CreatePolls, Delegate, ManageVoters, Session};
+use crate::db;
+use crate::error::{ApiError, Json, Path};
+use crate::events;
+use crate::taxonomy;
+use crate::tenant::Tenant;
+use crate::{AppState, VoterStatus};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/create_user", post(create_user))
+        .route("/set_user_status/:user_id", post(set_user_status))
+        .route("/check_user_status/:user_id", get(check_user_status))
+        .route("/add_delegation", post(add_delegation))
+        .route("/remove_delegation", post(remove_delegation))
+        .route("/add_to_vote_history", post(add_to_vote_history))
+        .route("/create_question", post(create_question))
+        .route("/create_poll", post(create_poll))
+        .route("/cast_ballot", post(cast_ballot))
+        .route("/get_poll_participation/:poll_id", get(get_poll_participation))
+        .route("/get_poll_results/:poll_id", get(polls::get_poll_results))
+        .route("/get_question_results/:poll_question_id", get(get_question_results))
+        .route("/events", get(events::stream_events))
+        .layer(middleware::map_response(mark_deprecated))
+}
+
+async fn mark_deprecated(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    response
+}
+
+async fn create_user(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Json(payload): Json<voters::CreateUserPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = voters::create_user(&state, tenant.organization_id, session.voter_id, payload).await?;
+
+    Ok((StatusCode::OK, Json(user)))
+}
+
+#[derive(Deserialize)]
+struct SetUserStatusPayload {
+    status: VoterStatus,
+}
+
+async fn set_user_status(
+    State(state): State<AppState>,
+    session: Session,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<SetUserStatusPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(user_id)?;
+    voters::set_status(&state, user_id, payload.status).await?;
+
+    Ok((StatusCode::OK, "User status updated"))
+}
+
+async fn check_user_status(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(user_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_can_read(user_id)?;
+    let voter = voters::load_voter(&state.pool, tenant.organization_id, user_id).await?;
+
+    Ok((StatusCode::OK, Json(voter.status)))
+}
+
+#[derive(Deserialize)]
+struct AddDelegationPayload {
+    from: Uuid,
+    to: Uuid,
+    /// Topic ids, or names of the topics seeded from the former fixed list.
+    #[serde(deserialize_with = "taxonomy::deserialize_ids")]
+    topics: Vec<Uuid>,
+    end_date: Date,
+}
+
+async fn add_delegation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<Delegate>,
+    Json(payload): Json<AddDelegationPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(payload.from)?;
+    let delegation = delegations::add_delegation(
+        &state,
+        tenant.organization_id,
+        payload.from,
+        delegations::CreateDelegationPayload {
+            to: payload.to,
+            topics: payload.topics,
+            end_date: payload.end_date,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(delegation)))
+}
+
+#[derive(Deserialize)]
+struct RemoveDelegationPayload {
+    from: Uuid,
+    to: Uuid,
+    #[serde(deserialize_with = "taxonomy::deserialize_ids")]
+    topics: Vec<Uuid>,
+}
+
+async fn remove_delegation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<Delegate>,
+    Json(payload): Json<RemoveDelegationPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(payload.from)?;
+    let row = sqlx::query(
+        r#"
+        SELECT delegation_id
+        FROM delegations
+        WHERE from_principal = $1 AND to_delegate = $2 AND topics = $3 AND organization_id = $4
+        "#,
+    )
+    .bind(payload.from.to_string())
+    .bind(payload.to.to_string())
+    .bind(serde_json::to_string(&payload.topics)?)
+    .bind(tenant.organization_id.to_string())
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ApiError::missing(format!(
+        "Delegation from {} to {}",
+        payload.from, payload.to
+    )))?;
+
+    let delegation_id = db::parse_column(&row, "delegation_id")?;
+    delegations::remove_delegation(&state, tenant.organization_id, payload.from, delegation_id).await?;
+
+    Ok((StatusCode::OK, "Delegation removed"))
+}
+
+#[derive(Deserialize)]
+struct AddToVoteHistoryPayload {
+    voter_id: Uuid,
+    #[serde(flatten)]
+    entry: voters::AddToVoteHistoryPayload,
+}
+
+async fn add_to_vote_history(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Json(payload): Json<AddToVoteHistoryPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(payload.voter_id)?;
+    let vote_history =
+        voters::record_vote_history(&state, tenant.organization_id, payload.voter_id, payload.entry)
+            .await?;
+
+    Ok((StatusCode::OK, Json(vote_history)))
+}
+
+#[derive(Deserialize)]
+struct CreateQuestionPayload {
+    poll_id: Uuid,
+    #[serde(flatten)]
+    question: polls::CreateQuestionPayload,
+}
+
+async fn create_question(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Json(payload): Json<CreateQuestionPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let question = polls::add_question(&state, &session, tenant.organization_id, payload.poll_id, payload.question).await?;
+
+    Ok((StatusCode::OK, Json(question)))
+}
+
+#[derive(Deserialize)]
+struct CreatePollPayload {
+    creator_user_id: Uuid,
+    #[serde(flatten)]
+    poll: polls::CreatePollPayload,
+}
+
+async fn create_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<CreatePolls>,
+    Json(payload): Json<CreatePollPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(payload.creator_user_id)?;
+    let poll = polls::add_poll(&state, tenant.organization_id, session.voter_id, payload.poll).await?;
+
+    Ok((StatusCode::OK, Json(poll)))
+}
+
+#[derive(Deserialize)]
+struct CastBallotPayload {
+    poll_id: Uuid,
+    user_id: Uuid,
+    #[serde(flatten)]
+    ballot: polls::CastBallotPayload,
+}
+
+async fn cast_ballot(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<CastBallots>,
+    Json(payload): Json<CastBallotPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(payload.user_id)?;
+    let ballot = polls::cast_ballot(
+        &state,
+        tenant.organization_id,
+        session.voter_id,
+        payload.poll_id,
+        payload.ballot,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(ballot)))
+}
+
+/// Still answers with the bare ballot count.
+async fn get_poll_participation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(poll_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    polls::load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    let ballots = polls::count_ballots(&state.pool, poll_id).await?;
+
+    Ok((StatusCode::OK, Json(ballots)))
+}
+
+async fn get_question_results(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(poll_question_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let results = polls::load_question_results(&state.pool, tenant.organization_id, None, poll_question_id).await?;
+
+    Ok((StatusCode::OK, Json(results)))
+}
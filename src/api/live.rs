@@ -0,0 +1,280 @@
+//! Live participation and provisional results while a poll is open.
+//!
+//! Casting a ballot notifies the [`LiveFeed`], whose task recounts the poll
+//! once into a [`LiveUpdate`] and broadcasts it to every watcher over
+//! Server-Sent Events or a WebSocket. Ballots arriving within [`THROTTLE`]
+//! of the last count are coalesced into the next one, so a busy assembly
+//! recounts a poll at most once per [`THROTTLE`], however many screens
+//! watch it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::{
+        sse::{self, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use serde::Serialize;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tokio::time::Instant;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::polls;
+use crate::auth::{Public, ReadResults};
+use crate::db::Pool;
+use crate::error::{ApiError, Path, Problem};
+use crate::tally;
+use crate::tenant::Tenant;
+use crate::{AppState, QuestionResult, ResultsVisibility};
+
+/// Minimum delay between two counts of the same poll.
+const THROTTLE: Duration = Duration::from_secs(1);
+
+/// Recounts the polls being watched when they receive a ballot, and shares
+/// each count with all their watchers.
+#[derive(Clone)]
+pub struct LiveFeed {
+    ballots: mpsc::UnboundedSender<Uuid>,
+    watched: Arc<Mutex<HashMap<Uuid, Watched>>>,
+}
+
+/// A poll someone watches.
+struct Watched {
+    organization_id: Uuid,
+    updates: broadcast::Sender<Arc<LiveUpdate>>,
+    /// The last count, sent first to new watchers.
+    latest: Option<Arc<LiveUpdate>>,
+}
+
+impl LiveFeed {
+    /// Starts the task counting the polls of `pool`.
+    pub fn start(pool: Pool) -> Self {
+        let (ballots, received) = mpsc::unbounded_channel();
+        let feed = LiveFeed {
+            ballots,
+            watched: Arc::default(),
+        };
+        tokio::spawn(feed.clone().run(pool, received));
+        feed
+    }
+
+    pub fn notify(&self, poll_id: Uuid) {
+        // Fails only when the server is shutting down, which is fine.
+        let _ = self.ballots.send(poll_id);
+    }
+
+    /// The last count of the poll, if any, and the counts to come. Without
+    /// a last count, one is made now.
+    fn subscribe(
+        &self,
+        organization_id: Uuid,
+        poll_id: Uuid,
+    ) -> (Option<Arc<LiveUpdate>>, broadcast::Receiver<Arc<LiveUpdate>>) {
+        let mut watched = self.watched.lock().unwrap();
+        let poll = watched.entry(poll_id).or_insert_with(|| Watched {
+            organization_id,
+            updates: broadcast::channel(16).0,
+            latest: None,
+        });
+        if poll.latest.is_none() {
+            self.notify(poll_id);
+        }
+
+        (poll.latest.clone(), poll.updates.subscribe())
+    }
+
+    /// Counts each poll that received a ballot as soon as [`THROTTLE`] has
+    /// passed since its last count, until the feed is dropped.
+    async fn run(self, pool: Pool, mut ballots: mpsc::UnboundedReceiver<Uuid>) {
+        // When each poll was last counted, and when the polls that received
+        // a ballot since are due.
+        let mut counted: HashMap<Uuid, Instant> = HashMap::new();
+        let mut due: HashMap<Uuid, Instant> = HashMap::new();
+
+        loop {
+            let next = due.values().min().copied();
+            tokio::select! {
+                poll_id = ballots.recv() => {
+                    let Some(poll_id) = poll_id else { return };
+                    if !self.watched.lock().unwrap().contains_key(&poll_id) {
+                        continue;
+                    }
+                    let now = Instant::now();
+                    let at = counted.get(&poll_id).map_or(now, |last| now.max(*last + THROTTLE));
+                    due.entry(poll_id).or_insert(at);
+                }
+                () = tokio::time::sleep_until(next.unwrap_or_else(Instant::now)), if next.is_some() => {
+                    let now = Instant::now();
+                    counted.retain(|_, last| now < *last + THROTTLE);
+                    let ready: Vec<Uuid> = due.iter().filter(|(_, at)| **at <= now).map(|(id, _)| *id).collect();
+                    for poll_id in ready {
+                        due.remove(&poll_id);
+                        counted.insert(poll_id, now);
+                        self.publish(&pool, poll_id).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Counts the poll and sends the count to its watchers; forgets polls
+    /// nobody watches anymore.
+    async fn publish(&self, pool: &Pool, poll_id: Uuid) {
+        let organization_id = {
+            let mut watched = self.watched.lock().unwrap();
+            match watched.get(&poll_id) {
+                Some(poll) if poll.updates.receiver_count() > 0 => poll.organization_id,
+                _ => {
+                    watched.remove(&poll_id);
+                    return;
+                }
+            }
+        };
+
+        let update = match snapshot(pool, organization_id, poll_id).await {
+            Ok(update) => Arc::new(update),
+            Err(error) => {
+                if let ApiError::Internal(cause) = error {
+                    eprintln!("Live results of poll {poll_id}: {cause}");
+                }
+                // Closes the streams; the poll may have been deleted.
+                self.watched.lock().unwrap().remove(&poll_id);
+                return;
+            }
+        };
+
+        let mut watched = self.watched.lock().unwrap();
+        if let Some(poll) = watched.get_mut(&poll_id) {
+            poll.latest = Some(update.clone());
+            if poll.updates.send(update).is_err() {
+                watched.remove(&poll_id);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LiveUpdate {
+    poll_id: Uuid,
+    /// Number of ballots cast so far.
+    participation: i64,
+    /// Running tally of each question, delegated votes included, only for
+    /// polls with public results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Vec<QuestionResult>>,
+}
+
+/// Streams updates of the poll as Server-Sent Events, or over a WebSocket
+/// when the request asks for an upgrade.
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}/live",
+    tag = "results",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "One `update` event per change", body = LiveUpdate, content_type = "text/event-stream"),
+        (status = 101, description = "WebSocket carrying one JSON update per message"),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the results:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn watch_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadResults>,
+    Path(poll_id): Path<Uuid>,
+    upgrade: Option<WebSocketUpgrade>,
+) -> Result<Response, ApiError> {
+    // Fails with a 404 before switching protocols.
+    let organization_id = tenant.organization_id;
+    polls::load_poll(&state.pool, organization_id, poll_id).await?;
+
+    let (latest, updates) = state.live.subscribe(organization_id, poll_id);
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(watch(latest, updates, tx));
+
+    Ok(match upgrade {
+        Some(upgrade) => upgrade.on_upgrade(move |socket| forward(socket, rx)),
+        None => {
+            let events = ReceiverStream::new(rx)
+                .map(|update| sse::Event::default().event("update").json_data(&*update));
+            Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+        }
+    })
+}
+
+/// Relays `latest`, then each count of the poll, to `tx` until it closes.
+async fn watch(
+    latest: Option<Arc<LiveUpdate>>,
+    mut updates: broadcast::Receiver<Arc<LiveUpdate>>,
+    tx: mpsc::Sender<Arc<LiveUpdate>>,
+) {
+    if let Some(update) = latest {
+        if tx.send(update).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        let update = tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => update,
+                // Newer counts follow the missed ones.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            () = tx.closed() => return,
+        };
+        if tx.send(update).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn forward(mut socket: WebSocket, mut rx: mpsc::Receiver<Arc<LiveUpdate>>) {
+    loop {
+        tokio::select! {
+            update = rx.recv() => {
+                let Some(update) = update else { break };
+                let text = serde_json::to_string(&*update).unwrap();
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                // Clients have nothing to say; pings are answered by axum.
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn snapshot(pool: &Pool, organization_id: Uuid, poll_id: Uuid) -> Result<LiveUpdate, ApiError> {
+    let poll = polls::load_poll(pool, organization_id, poll_id).await?;
+    let participation = polls::count_ballots(pool, poll_id).await?;
+
+    let results = match poll.results_visibility {
+        ResultsVisibility::Secret => None,
+        ResultsVisibility::Public => Some(tally::compute(pool, &poll).await?),
+    };
+
+    Ok(LiveUpdate {
+        poll_id,
+        participation,
+        results,
+    })
+}
@@ -0,0 +1,135 @@
+//! HTTP API. Resources live under `/api/v1` and are described by the
+//! OpenAPI document in [`openapi`]; the routes the server exposed before
+//! versioning are kept in [`legacy`] for one more release, and [`pages`]
+//! renders a few of them as HTML.
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::events;
+use crate::AppState;
+
+mod accounts;
+mod delegations;
+mod groups;
+mod keys;
+mod legacy;
+mod live;
+mod openapi;
+mod organizations;
+mod pages;
+mod pagination;
+mod polls;
+mod sso;
+mod topics;
+mod two_factor;
+mod voters;
+
+pub use live::LiveFeed;
+pub(crate) use polls::load_poll;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/api/v1", v1())
+        .merge(openapi::router())
+        .merge(legacy::router())
+        .merge(pages::router())
+}
+
+fn v1() -> Router<AppState> {
+    Router::new()
+        .route("/accounts", post(accounts::register))
+        .route("/accounts/verify", get(accounts::verify_email))
+        .route("/accounts/verification", post(accounts::resend_verification))
+        .route(
+            "/accounts/totp",
+            post(two_factor::start_totp).delete(two_factor::disable_totp),
+        )
+        .route("/accounts/totp/confirm", post(two_factor::confirm_totp))
+        .route(
+            "/accounts/totp/recovery-codes",
+            post(two_factor::regenerate_recovery_codes),
+        )
+        .route(
+            "/session",
+            get(accounts::get_session).post(accounts::login).delete(accounts::logout),
+        )
+        .route("/session/magic-link", post(accounts::request_magic_link))
+        .route("/session/magic", get(accounts::magic_login))
+        .route("/session/oidc", get(sso::start_sso))
+        .route("/session/oidc/callback", get(sso::finish_sso))
+        .route(
+            "/organization",
+            get(organizations::get_organization).patch(organizations::update_organization),
+        )
+        .route(
+            "/organizations",
+            get(organizations::list_organizations).post(organizations::create_organization),
+        )
+        .route("/topics", get(topics::list_topics).post(topics::create_topic))
+        .route("/topics/:topic_id", get(topics::get_topic).patch(topics::update_topic))
+        .route("/groups", get(groups::list_groups).post(groups::create_group))
+        .route(
+            "/groups/:group",
+            get(groups::get_group).patch(groups::update_group).delete(groups::delete_group),
+        )
+        .route("/groups/:group/members", post(groups::import_members))
+        .route("/api-keys", get(keys::list_api_keys).post(keys::create_api_key))
+        .route("/api-keys/:key_id", delete(keys::revoke_api_key))
+        .route("/voters", get(voters::list_voters).post(voters::create_voter))
+        .route(
+            "/voters/:voter_id",
+            get(voters::get_voter).patch(voters::update_voter).delete(voters::remove_voter),
+        )
+        .route(
+            "/voters/:voter_id/delegations",
+            get(delegations::list_delegations).post(delegations::create_delegation),
+        )
+        .route(
+            "/voters/:voter_id/delegations/:delegation_id",
+            delete(delegations::delete_delegation),
+        )
+        .route(
+            "/voters/:voter_id/polls/:poll_id/delegations",
+            get(delegations::list_applied_delegations),
+        )
+        .route("/voters/:voter_id/roles", get(voters::list_roles))
+        .route(
+            "/voters/:voter_id/roles/:role",
+            put(voters::grant_role).delete(voters::revoke_role),
+        )
+        .route("/voters/:voter_id/groups", get(voters::list_groups))
+        .route(
+            "/voters/:voter_id/groups/:group",
+            put(voters::join_group).delete(voters::leave_group),
+        )
+        .route(
+            "/voters/:voter_id/weights",
+            get(voters::list_weights).post(voters::set_weight),
+        )
+        .route(
+            "/voters/:voter_id/history",
+            get(voters::get_vote_history).post(voters::add_to_vote_history),
+        )
+        .route("/polls", get(polls::list_polls).post(polls::create_poll))
+        .route(
+            "/polls/:poll_id",
+            get(polls::get_poll).patch(polls::update_poll).delete(polls::delete_poll),
+        )
+        .route("/polls/:poll_id/close", post(polls::close_poll))
+        .route(
+            "/polls/:poll_id/questions",
+            get(polls::list_questions).post(polls::create_question),
+        )
+        .route("/polls/:poll_id/ballots", post(polls::create_ballot))
+        .route("/polls/:poll_id/live", get(live::watch_poll))
+        .route("/polls/:poll_id/participation", get(polls::get_poll_participation))
+        .route("/polls/:poll_id/results", get(polls::list_poll_results))
+        .route(
+            "/polls/:poll_id/questions/:question_id/results",
+            get(polls::get_question_results),
+        )
+        .route("/events", get(events::stream_events))
+}
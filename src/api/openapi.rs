@@ -0,0 +1,103 @@
+//! OpenAPI 3.1 description of `/api/v1`, generated from the handler
+//! annotations and the payload and domain types.
+
+use axum::Router;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::{
+    accounts, delegations, groups, keys, live, organizations, polls, sso, topics, two_factor, voters,
+};
+use crate::events;
+use crate::AppState;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "vote_server", description = "Polls, ballots and delegations of a liquid democracy."),
+    paths(
+        accounts::register,
+        accounts::login,
+        accounts::get_session,
+        accounts::logout,
+        accounts::verify_email,
+        accounts::resend_verification,
+        accounts::request_magic_link,
+        accounts::magic_login,
+        sso::start_sso,
+        sso::finish_sso,
+        two_factor::start_totp,
+        two_factor::confirm_totp,
+        two_factor::regenerate_recovery_codes,
+        two_factor::disable_totp,
+        organizations::get_organization,
+        organizations::update_organization,
+        organizations::list_organizations,
+        organizations::create_organization,
+        topics::list_topics,
+        topics::create_topic,
+        topics::get_topic,
+        topics::update_topic,
+        groups::list_groups,
+        groups::create_group,
+        groups::get_group,
+        groups::update_group,
+        groups::delete_group,
+        groups::import_members,
+        keys::create_api_key,
+        keys::list_api_keys,
+        keys::revoke_api_key,
+        voters::list_voters,
+        voters::create_voter,
+        voters::get_voter,
+        voters::update_voter,
+        voters::remove_voter,
+        voters::list_roles,
+        voters::grant_role,
+        voters::revoke_role,
+        voters::list_groups,
+        voters::join_group,
+        voters::leave_group,
+        voters::list_weights,
+        voters::set_weight,
+        voters::get_vote_history,
+        voters::add_to_vote_history,
+        delegations::list_delegations,
+        delegations::create_delegation,
+        delegations::delete_delegation,
+        delegations::list_applied_delegations,
+        polls::list_polls,
+        polls::create_poll,
+        polls::get_poll,
+        polls::update_poll,
+        polls::delete_poll,
+        polls::close_poll,
+        polls::list_questions,
+        polls::create_question,
+        polls::create_ballot,
+        polls::get_poll_participation,
+        live::watch_poll,
+        polls::list_poll_results,
+        polls::get_question_results,
+        events::stream_events,
+    ),
+    tags(
+        (name = "accounts", description = "Registration and sessions"),
+        (name = "organizations", description = "Organizations sharing the server"),
+        (name = "topics", description = "Topics of delegations and questions"),
+        (name = "groups", description = "Groups of voters"),
+        (name = "voters", description = "Voter registry"),
+        (name = "delegations", description = "Topic delegations between voters"),
+        (name = "polls", description = "Polls and their questions"),
+        (name = "ballots", description = "Ballot casting"),
+        (name = "results", description = "Participation and results"),
+        (name = "audit", description = "Event log"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Serves the document at `/api/openapi.json` and a Swagger UI at `/api/docs`.
+pub fn router() -> Router<AppState> {
+    SwaggerUi::new("/api/docs")
+        .url("/api/openapi.json", ApiDoc::openapi())
+        .into()
+}
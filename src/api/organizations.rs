@@ -0,0 +1,138 @@
+//! The organizations sharing the server, see [`crate::tenant`].
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::auth::{Authorized, CreateOrganizations, ManageOrganization, Session};
+use crate::error::{ApiError, Json, Problem};
+use crate::events::{self, Event};
+use crate::oidc;
+use crate::tenant::{self, Tenant};
+use crate::{AppState, Organization, OrganizationSettings};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateOrganizationPayload {
+    /// 1 to 63 lowercase letters, digits and inner hyphens, used in the
+    /// `/o/{slug}` prefix and as a subdomain.
+    slug: String,
+    name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateOrganizationPayload {
+    name: Option<String>,
+    settings: Option<OrganizationSettings>,
+}
+
+/// Returns the organization the request was sent to.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organization",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Current organization", body = Organization),
+        (status = 404, description = "Unknown organization", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_organization(tenant: Tenant) -> Json<Organization> {
+    Json(tenant.0.redacted())
+}
+
+/// Renames the organization or changes its settings. The slug cannot change,
+/// as links and host names depend on it. Settings naming an identity
+/// provider without a client secret keep the current one.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/organization",
+    tag = "organizations",
+    request_body = UpdateOrganizationPayload,
+    responses(
+        (status = 200, description = "Updated organization", body = Organization),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not an admin of the organization", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_organization(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageOrganization>,
+    Json(payload): Json<UpdateOrganizationPayload>,
+) -> Result<Json<Organization>, ApiError> {
+    let Tenant(organization) = tenant;
+    if payload.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(ApiError::Validation("The organization needs a name".to_owned()));
+    }
+
+    let mut settings = payload.settings.unwrap_or_else(|| organization.settings.clone());
+    if let Some(sso) = &mut settings.sso {
+        if sso.client_secret.is_none() {
+            sso.client_secret = organization.settings.sso.as_ref().and_then(|current| current.client_secret.clone());
+        }
+        oidc::Config::new(sso, &state.public_url)?;
+    }
+
+    let organization = Organization {
+        name: payload.name.unwrap_or(organization.name),
+        settings,
+        ..organization
+    };
+    events::record(
+        &state.pool,
+        Some(session.voter_id),
+        &Event::OrganizationUpdated(organization.clone()),
+    )
+    .await?;
+
+    Ok(Json(organization.redacted()))
+}
+
+/// Lists the organizations the logged-in voter is a member of, by name.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Organizations of the logged-in voter", body = [Organization]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_organizations(
+    State(state): State<AppState>,
+    session: Session,
+) -> Result<Json<Vec<Organization>>, ApiError> {
+    let organizations = tenant::memberships(&state.pool, session.voter_id).await?;
+
+    Ok(Json(organizations.into_iter().map(Organization::redacted).collect()))
+}
+
+/// Creates an organization, of which the logged-in admin becomes an admin.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    request_body = CreateOrganizationPayload,
+    responses(
+        (status = 201, description = "Organization created", body = Organization),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not an admin of the default organization", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Slug already taken", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_organization(
+    State(state): State<AppState>,
+    session: Authorized<CreateOrganizations>,
+    Json(payload): Json<CreateOrganizationPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let organization =
+        tenant::create(&state.pool, Some(session.voter_id), payload.slug, payload.name).await?;
+    let location = format!("/o/{}/api/v1/organization", organization.slug);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(organization)))
+}
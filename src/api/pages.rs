@@ -0,0 +1,369 @@
+//! Pages rendered on the server with Leptos, for voters using a browser
+//! rather than the API. They use the same session cookie as `/api/v1`.
+
+use std::collections::HashMap;
+
+use axum::{extract::State, response::Html, routing::get, Router};
+use leptos::prelude::*;
+use uuid::Uuid;
+
+use super::{delegations, polls, voters};
+use crate::auth::Session;
+use crate::error::{ApiError, Path};
+use crate::taxonomy;
+use crate::tenant::Tenant;
+use crate::{AppState, ConflictPolicy, PollType, VoteOption};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/polls/:poll_id/ballot", get(poll_ballot))
+        .route("/polls/:poll_id/delegations", get(poll_delegations))
+}
+
+/// How a question is answered on the ballot page.
+#[derive(Clone, Copy)]
+enum Answer {
+    /// One checkbox per option.
+    Approve,
+    /// One option among all.
+    Choose,
+    /// A score for each option.
+    Score,
+    /// Votes for each option, negative against it.
+    Weigh,
+}
+
+/// A question of the ballot, as the page shows it.
+struct BallotQuestion {
+    question_id: String,
+    description: String,
+    hint: String,
+    answer: Answer,
+    options: Vec<VoteOption>,
+}
+
+/// Submits the ballot form to `/api/v1/polls/:poll_id/ballots`, relative to
+/// the page so that it stays in its organization.
+const CAST_BALLOT: &str = r#"
+document.getElementById("ballot").addEventListener("submit", async (event) => {
+    event.preventDefault();
+    const form = event.target;
+    const vote = [];
+    for (const input of form.querySelectorAll("input[data-vote]")) {
+        const chosen = input.type === "checkbox" || input.type === "radio";
+        if ((chosen && !input.checked) || (!chosen && input.value === "")) {
+            continue;
+        }
+        const score = chosen ? 1 : Number(input.value);
+        vote.push({
+            question_id: input.name,
+            vote: input.dataset.vote,
+            vote_description: input.dataset.description,
+            score: Math.abs(score),
+            against: score < 0,
+        });
+    }
+    const headers = { "Content-Type": "application/json" };
+    if (form.second_factor.value) {
+        headers["X-Second-Factor"] = form.second_factor.value;
+    }
+    const response = await fetch(`../../api/v1/polls/${form.dataset.poll}/ballots`, {
+        method: "POST",
+        headers,
+        body: JSON.stringify({ vote }),
+    });
+    document.getElementById("outcome").textContent = response.ok
+        ? "Your ballot was cast."
+        : (await response.json()).detail;
+});
+"#;
+
+/// The ballot of a poll, as a form the logged-in voter fills in.
+async fn poll_ballot(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _session: Session,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Html<String>, ApiError> {
+    let poll = polls::load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    let credits = poll.credits.unwrap_or(0);
+    let questions = poll
+        .questions
+        .into_iter()
+        .map(|question| {
+            let (answer, hint) = match question.question_type {
+                PollType::Approval => {
+                    let hint = match (question.min_approvals, question.max_approvals) {
+                        (Some(min), Some(max)) => format!("Approve between {min} and {max} options."),
+                        (Some(min), None) => format!("Approve at least {min} options."),
+                        (None, Some(max)) => format!("Approve at most {max} options."),
+                        (None, None) => "Approve as many options as you like.".to_owned(),
+                    };
+                    (Answer::Approve, hint)
+                }
+                PollType::Referendum => (Answer::Choose, "Choose one option.".to_owned()),
+                PollType::PartyList => (Answer::Choose, "Choose one list.".to_owned()),
+                PollType::MajorityJudgment => {
+                    let last = question.grades.len().saturating_sub(1);
+                    let scale = question
+                        .grades
+                        .iter()
+                        .enumerate()
+                        .map(|(index, grade)| format!("{} for {grade}", last - index))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    (Answer::Score, format!("Grade every option: {scale}; options left blank get the last grade."))
+                }
+                PollType::QuadraticVoting => (
+                    Answer::Weigh,
+                    format!(
+                        "Vote for options, or against them with negative votes; k votes cost k² of the {credits} \
+                         credits you have for the whole poll."
+                    ),
+                ),
+                PollType::SingleTransferableVote => (
+                    Answer::Score,
+                    format!(
+                        "Rank the options you want to, 1 for your first choice; {} will be elected.",
+                        question.seats.unwrap_or(1)
+                    ),
+                ),
+                _ => (Answer::Score, "Score the options you want to.".to_owned()),
+            };
+            BallotQuestion {
+                question_id: question.question_id.to_string(),
+                description: question.question_description,
+                hint,
+                answer,
+                options: question.vote_options,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Html(view! { <PollBallot poll_id=poll_id.to_string() questions/> }.to_html()))
+}
+
+#[component]
+fn PollBallot(poll_id: String, questions: Vec<BallotQuestion>) -> impl IntoView {
+    view! {
+        <!DOCTYPE html>
+        <html lang="en">
+            <head>
+                <meta charset="utf-8"/>
+                <title>"Your ballot"</title>
+            </head>
+            <body>
+                <h1>"Your ballot"</h1>
+                <form id="ballot" data-poll=poll_id>
+                    {questions
+                        .into_iter()
+                        .map(|question| {
+                            let answer = question.answer;
+                            let name = question.question_id;
+                            view! {
+                                <fieldset>
+                                    <legend>{question.description}</legend>
+                                    <p>{question.hint}</p>
+                                    {question
+                                        .options
+                                        .into_iter()
+                                        .map(|option| {
+                                            let input = match answer {
+                                                Answer::Approve => "checkbox",
+                                                Answer::Choose => "radio",
+                                                Answer::Score | Answer::Weigh => "number",
+                                            };
+                                            let min = (!matches!(answer, Answer::Weigh)).then_some("0");
+                                            view! {
+                                                <label>
+                                                    <input
+                                                        type=input
+                                                        name=name.clone()
+                                                        min=min
+                                                        data-vote=option.vote
+                                                        data-description=option.vote_description.clone()
+                                                    />
+                                                    {option.vote_description}
+                                                </label>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </fieldset>
+                            }
+                        })
+                        .collect_view()}
+                    <label>
+                        "Code from your authenticator app, if you set one up "
+                        <input type="text" name="second_factor" autocomplete="one-time-code"/>
+                    </label>
+                    <button type="submit">"Cast my ballot"</button>
+                </form>
+                <p id="outcome"></p>
+                <script inner_html=CAST_BALLOT></script>
+            </body>
+        </html>
+    }
+}
+
+/// A question of the poll, and who votes on it for the logged-in voter.
+struct QuestionDelegates {
+    question: String,
+    topics: String,
+    delegates: String,
+}
+
+/// Lists the questions of a poll with the delegations that apply to each,
+/// see [`crate::delegation`].
+async fn poll_delegations(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Html<String>, ApiError> {
+    let organization_id = tenant.organization_id;
+    let policy = tenant.settings.delegation_conflicts;
+    let poll = polls::load_poll(&state.pool, organization_id, poll_id).await?;
+    let applied =
+        delegations::applied_delegations(&state.pool, &poll, session.voter_id, policy).await?;
+    let topics = taxonomy::list(&state.pool, organization_id, true)
+        .await?
+        .into_iter()
+        .map(|topic| (topic.topic_id, topic.name))
+        .collect::<HashMap<_, _>>();
+    let topic_name = |topic_id: Uuid| {
+        topics.get(&topic_id).cloned().unwrap_or_else(|| topic_id.to_string())
+    };
+
+    let mut rows = Vec::with_capacity(applied.len());
+    for (question, applied) in poll.questions.iter().zip(applied) {
+        let mut delegates = Vec::new();
+        for share in &applied.shares {
+            let delegate =
+                voters::load_account(&state.pool, organization_id, share.delegation.to_delegate).await?;
+            let mut line = format!("{} {}", delegate.first_name, delegate.last_name);
+            if share.via_topic != share.topic {
+                line += &format!(", through your delegation on {}", topic_name(share.via_topic));
+            }
+            if share.weight < 1.0 {
+                line += &format!(", for {:.0}% of your vote", share.weight * 100.0);
+            }
+            delegates.push(line);
+        }
+
+        let delegates = match applied.settled_by {
+            Some(ConflictPolicy::Abstain) => {
+                "Nobody: you delegated its topics to different people, so your vote is not counted"
+                    .to_owned()
+            }
+            Some(ConflictPolicy::Priority) => format!(
+                "{}, as the delegate for the first of its topics you delegated; you delegated others to other people",
+                delegates.join("; ")
+            ),
+            _ if delegates.is_empty() => "You vote yourself".to_owned(),
+            _ => delegates.join("; "),
+        };
+        rows.push(QuestionDelegates {
+            question: question.question_description.clone(),
+            topics: question
+                .question_topics
+                .iter()
+                .map(|&topic_id| topic_name(topic_id))
+                .collect::<Vec<_>>()
+                .join(", "),
+            delegates,
+        });
+    }
+
+    Ok(Html(view! { <PollDelegations rows/> }.to_html()))
+}
+
+#[component]
+fn PollDelegations(rows: Vec<QuestionDelegates>) -> impl IntoView {
+    view! {
+        <!DOCTYPE html>
+        <html lang="en">
+            <head>
+                <meta charset="utf-8"/>
+                <title>"Your delegations"</title>
+            </head>
+            <body>
+                <h1>"Who votes for you"</h1>
+                <table>
+                    <thead>
+                        <tr>
+                            <th>"Question"</th>
+                            <th>"Topics"</th>
+                            <th>"Delegates"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {rows
+                            .into_iter()
+                            .map(|row| {
+                                view! {
+                                    <tr>
+                                        <td>{row.question}</td>
+                                        <td>{row.topics}</td>
+                                        <td>{row.delegates}</td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view()}
+                    </tbody>
+                </table>
+            </body>
+        </html>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vote_options;
+
+    #[test]
+    fn ballot_renders_an_input_per_option() {
+        let questions = vec![
+            BallotQuestion {
+                question_id: "q1".to_owned(),
+                description: "Which days?".to_owned(),
+                hint: "Approve as many options as you like.".to_owned(),
+                answer: Answer::Approve,
+                options: vote_options(&["mon", "tue"]),
+            },
+            BallotQuestion {
+                question_id: "q2".to_owned(),
+                description: "How much?".to_owned(),
+                hint: "Vote for options, or against them with negative votes.".to_owned(),
+                answer: Answer::Weigh,
+                options: vote_options(&["more"]),
+            },
+        ];
+
+        let html = view! { <PollBallot poll_id="p1".to_owned() questions/> }.to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(r#"data-poll="p1""#));
+        assert!(html.contains("<legend>Which days?</legend>"));
+        assert_eq!(html.matches(r#"type="checkbox""#).count(), 2);
+        assert!(html.contains(r#"data-vote="tue""#));
+        // Votes against are negative, so only the checkboxes have a minimum.
+        assert_eq!(html.matches(r#"min="0""#).count(), 2);
+        assert!(html.contains(r#"type="number""#));
+        assert!(html.contains("X-Second-Factor"));
+    }
+
+    #[test]
+    fn delegations_render_a_row_per_question() {
+        let rows = vec![QuestionDelegates {
+            question: "Budget".to_owned(),
+            topics: "Finance".to_owned(),
+            delegates: "Ada Lovelace".to_owned(),
+        }];
+
+        let html = view! { <PollDelegations rows/> }.to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>Budget</td><td>Finance</td><td>Ada Lovelace</td>"));
+    }
+}
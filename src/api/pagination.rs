@@ -0,0 +1,117 @@
+//! Keyset pagination shared by the list endpoints.
+//!
+//! Lists are ordered by a sort column, then by the row id so that the order
+//! is total and stable. A page's `next_cursor` encodes the sort value and id
+//! of its last item; passing it back as `cursor` resumes right after that
+//! item, whatever was inserted or deleted in the meantime.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::SqlBuilder;
+use crate::error::ApiError;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+#[derive(Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, absent on the last one.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    key: String,
+    id: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    fn decode(token: &str) -> Result<Self, ApiError> {
+        URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| ApiError::Validation(format!("{token:?} is not a valid cursor")))
+    }
+}
+
+/// Where a list request starts and how much it returns.
+pub struct Keyset {
+    limit: u32,
+    order: Order,
+    after: Option<Cursor>,
+}
+
+impl Keyset {
+    pub fn new(limit: Option<u32>, cursor: Option<&str>, order: Order) -> Result<Self, ApiError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(ApiError::Validation(format!(
+                "limit must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+
+        Ok(Keyset {
+            limit,
+            order,
+            after: cursor.map(Cursor::decode).transpose()?,
+        })
+    }
+
+    /// Ends a query whose `WHERE` clause is already open: skips the rows up to
+    /// the cursor, orders by `key_column` then `id_column`, and fetches one
+    /// row more than the limit so [`Keyset::page`] knows if another page follows.
+    pub fn finish(&self, sql: &mut SqlBuilder, key_column: &str, id_column: &str) {
+        let (cmp, direction) = match self.order {
+            Order::Asc => (">", "ASC"),
+            Order::Desc => ("<", "DESC"),
+        };
+
+        if let Some(after) = &self.after {
+            sql.push(&format!(" AND ({key_column} {cmp} "))
+                .push_bind(after.key.as_str())
+                .push(&format!(" OR ({key_column} = "))
+                .push_bind(after.key.as_str())
+                .push(&format!(" AND {id_column} {cmp} "))
+                .push_bind(after.id.as_str())
+                .push("))");
+        }
+
+        sql.push(&format!(
+            " ORDER BY {key_column} {direction}, {id_column} {direction} LIMIT {}",
+            self.limit + 1
+        ));
+    }
+
+    /// Trims the extra row fetched by [`Keyset::finish`] and derives the next
+    /// cursor from the last item kept. `cursor_of` returns an item's sort
+    /// value and id, as stored in the database.
+    pub fn page<T>(&self, mut items: Vec<T>, cursor_of: impl Fn(&T) -> (String, String)) -> Page<T> {
+        let next_cursor = if items.len() > self.limit as usize {
+            items.truncate(self.limit as usize);
+            items.last().map(|last| {
+                let (key, id) = cursor_of(last);
+                Cursor { key, id }.encode()
+            })
+        } else {
+            None
+        };
+
+        Page { items, next_cursor }
+    }
+}
@@ -0,0 +1,1251 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{any::AnyRow, Row};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::pagination::{Keyset, Order, Page};
+use crate::auth::{Authorized, CastBallots, CreatePolls, Public, ReadPolls, ReadResults, Session};
+use crate::db::{self, Pool, SqlBuilder};
+use crate::electorate;
+use crate::error::{ApiError, Json, Path, Problem, Query};
+use crate::events::{self, Event};
+use crate::quadratic;
+use crate::tally;
+use crate::taxonomy;
+use crate::tenant::Tenant;
+use crate::{
+    AppState, ApportionmentMethod, Breakdown, College, Eligibility, Poll, PollQuestion, PollResult, PollType,
+    QuestionResult, RankedChoiceVote, ResultsVisibility, ScoredVote, SurplusTransfer, VoteOption,
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePollPayload {
+    poll_id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    poll_opening_time: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    poll_closing_time: OffsetDateTime,
+    #[serde(default)]
+    results_visibility: ResultsVisibility,
+    /// Who may vote; every voter when absent.
+    #[serde(default)]
+    eligibility: Eligibility,
+    /// Groups to break the results down by, and colleges to weigh.
+    #[serde(default)]
+    breakdown: Breakdown,
+    /// Credits each voter may spend on the `QuadraticVoting` questions.
+    credits: Option<u32>,
+    questions: Vec<PollQuestion>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdatePollPayload {
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    poll_opening_time: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    poll_closing_time: Option<OffsetDateTime>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateQuestionPayload {
+    question_id: Uuid,
+    question_type: PollType,
+    /// Ids of active topics of the organization, most important first; a
+    /// single `question_topic` is still accepted.
+    #[serde(alias = "question_topic", deserialize_with = "taxonomy::deserialize_question_topics")]
+    question_topics: Vec<Uuid>,
+    question_description: String,
+    vote_options: Vec<VoteOption>,
+    /// Fewest options an `Approval` ballot may approve.
+    min_approvals: Option<u32>,
+    /// Most options an `Approval` ballot may approve.
+    max_approvals: Option<u32>,
+    /// Options a `SingleTransferableVote` question elects, or seats the
+    /// lists of a `PartyList` question share.
+    seats: Option<u32>,
+    /// For `SingleTransferableVote` questions, `Gregory` unless set.
+    surplus_transfer: Option<SurplusTransfer>,
+    /// For `PartyList` questions, `DHondt` unless set.
+    apportionment: Option<ApportionmentMethod>,
+    /// For `PartyList` questions, the percentage of the votes a list needs
+    /// to win seats.
+    threshold: Option<f64>,
+    /// For `MajorityJudgment` questions, the names of the grades, best
+    /// first.
+    #[serde(default)]
+    grades: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CastBallotPayload {
+    pub vote: Vec<ScoredVote>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Participation {
+    /// Ballots cast so far.
+    ballots: i64,
+    /// Voters in the electorate, known once the poll has opened.
+    eligible: Option<i64>,
+    /// Share of the electorate that voted, from 0 to 1.
+    turnout: Option<f64>,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PollState {
+    Upcoming,
+    Open,
+    Closed,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PollSort {
+    #[default]
+    OpeningTime,
+    ClosingTime,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListPollsQuery {
+    /// Only polls with at least one question on this topic, among others.
+    topic: Option<Uuid>,
+    /// Only polls in this state right now.
+    state: Option<PollState>,
+    /// Only polls opening at or after this time.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    opens_after: Option<OffsetDateTime>,
+    /// Only polls opening before this time.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    opens_before: Option<OffsetDateTime>,
+    #[serde(default)]
+    sort: PollSort,
+    #[serde(default)]
+    order: Order,
+    /// Page size, 50 by default and at most 500.
+    limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListResultsQuery {
+    /// Page size, 50 by default and at most 500.
+    limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls",
+    tag = "polls",
+    params(ListPollsQuery),
+    responses(
+        (status = 200, description = "A page of polls with their questions", body = Page<Poll>),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid query", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_polls(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Query(query): Query<ListPollsQuery>,
+) -> Result<Json<Page<Poll>>, ApiError> {
+    let keyset = Keyset::new(query.limit, query.cursor.as_deref(), query.order)?;
+
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT creator_user_id, poll_id, organization_id, poll_opening_time, poll_closing_time, results_visibility, eligibility, breakdown,
+            credits
+        FROM polls
+        WHERE organization_id = "#,
+    );
+    sql.push_bind(tenant.organization_id.to_string());
+    if let Some(topic) = query.topic {
+        sql.push(
+            " AND EXISTS (SELECT 1 FROM poll_questions \
+             WHERE poll_questions.poll_id = polls.poll_id AND question_topics LIKE ",
+        )
+        // `question_topics` is a JSON array of topic ids.
+        .push_bind(format!("%\"{topic}\"%"))
+        .push(")");
+    }
+    if let Some(poll_state) = query.state {
+        let now = db::format_timestamp(OffsetDateTime::now_utc());
+        match poll_state {
+            PollState::Upcoming => sql.push(" AND poll_opening_time > ").push_bind(now),
+            PollState::Open => sql
+                .push(" AND poll_opening_time <= ")
+                .push_bind(now.as_str())
+                .push(" AND poll_closing_time > ")
+                .push_bind(now.as_str()),
+            PollState::Closed => sql.push(" AND poll_closing_time <= ").push_bind(now),
+        };
+    }
+    if let Some(opens_after) = query.opens_after {
+        sql.push(" AND poll_opening_time >= ").push_bind(db::format_timestamp(opens_after));
+    }
+    if let Some(opens_before) = query.opens_before {
+        sql.push(" AND poll_opening_time < ").push_bind(db::format_timestamp(opens_before));
+    }
+    let sort_column = match query.sort {
+        PollSort::OpeningTime => "poll_opening_time",
+        PollSort::ClosingTime => "poll_closing_time",
+    };
+    keyset.finish(&mut sql, sort_column, "poll_id");
+
+    let mut polls = sql
+        .query()
+        .fetch_all(&state.pool)
+        .await?
+        .iter()
+        .map(poll_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !polls.is_empty() {
+        let mut questions = SqlBuilder::new(
+            r#"
+            SELECT poll_id, question_id, question_type, question_topics, question_description, vote_options, votes,
+            min_approvals, max_approvals, seats, surplus_transfer, apportionment, threshold, grades
+            FROM poll_questions
+            WHERE poll_id IN "#,
+        );
+        questions
+            .push_bind_list(polls.iter().map(|poll| poll.poll_id.to_string()))
+            .push(" ORDER BY question_id");
+
+        for row in questions.query().fetch_all(&state.pool).await? {
+            let question = question_from_row(&row)?;
+            if let Some(poll) = polls.iter_mut().find(|poll| poll.poll_id == question.poll_id) {
+                poll.questions.push(question);
+            }
+        }
+    }
+
+    Ok(Json(keyset.page(polls, |poll| {
+        let key = match query.sort {
+            PollSort::OpeningTime => poll.poll_opening_time,
+            PollSort::ClosingTime => poll.poll_closing_time,
+        };
+        (db::format_timestamp(key), poll.poll_id.to_string())
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/polls",
+    tag = "polls",
+    request_body = CreatePollPayload,
+    responses(
+        (status = 201, description = "Poll created", body = Poll),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not a poll creator", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll id already used", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<CreatePolls>,
+    Json(payload): Json<CreatePollPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let poll = add_poll(&state, tenant.organization_id, session.voter_id, payload).await?;
+    let location = format!("/api/v1/polls/{}", poll.poll_id);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(poll)))
+}
+
+/// Creates a poll of the organization on behalf of `creator`.
+pub(super) async fn add_poll(
+    state: &AppState,
+    organization_id: Uuid,
+    creator: Uuid,
+    payload: CreatePollPayload,
+) -> Result<Poll, ApiError> {
+    if payload.poll_closing_time <= payload.poll_opening_time {
+        return Err(ApiError::Validation("A poll must close after it opens".to_owned()));
+    }
+    if let Some(question) = payload
+        .questions
+        .iter()
+        .find(|question| question.poll_id != payload.poll_id)
+    {
+        return Err(ApiError::Validation(format!(
+            "Question {} belongs to another poll",
+            question.question_id
+        )));
+    }
+    if let Some(voter_id) = payload
+        .eligibility
+        .include
+        .iter()
+        .find(|voter_id| payload.eligibility.exclude.contains(voter_id))
+    {
+        return Err(ApiError::Validation(format!(
+            "Voter {voter_id} is both included and excluded"
+        )));
+    }
+    ensure_colleges(&payload.breakdown.colleges)?;
+    let groups = payload
+        .eligibility
+        .groups
+        .iter()
+        .chain(&payload.breakdown.groups)
+        .chain(payload.breakdown.colleges.iter().map(|college| &college.group))
+        .cloned()
+        .collect::<Vec<_>>();
+    super::groups::ensure_exist(&state.pool, organization_id, &groups).await?;
+    if payload.credits == Some(0) {
+        return Err(ApiError::Validation("A poll's voters need at least one credit".to_owned()));
+    }
+    for question in &payload.questions {
+        ensure_topics(question.question_id, &question.question_topics)?;
+        ensure_counting(question, payload.credits)?;
+    }
+    let topics = payload
+        .questions
+        .iter()
+        .flat_map(|question| question.question_topics.iter().copied())
+        .collect::<Vec<_>>();
+    taxonomy::ensure_usable(&state.pool, organization_id, &topics).await?;
+
+    let poll = Poll {
+        creator_user_id: creator,
+        poll_id: payload.poll_id,
+        organization_id,
+        poll_opening_time: payload.poll_opening_time,
+        poll_closing_time: payload.poll_closing_time,
+        results_visibility: payload.results_visibility,
+        eligibility: payload.eligibility,
+        breakdown: payload.breakdown,
+        credits: payload.credits,
+        questions: payload.questions,
+    };
+
+    events::record(
+        &state.pool,
+        Some(poll.creator_user_id),
+        &Event::PollCreated(poll.clone()),
+    )
+    .await?;
+
+    Ok(poll)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Poll with its questions", body = Poll),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Json<Poll>, ApiError> {
+    Ok(Json(load_poll(&state.pool, tenant.organization_id, poll_id).await?))
+}
+
+/// Reschedules a poll that has not opened yet.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/polls/{poll_id}",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    request_body = UpdatePollPayload,
+    responses(
+        (status = 200, description = "Rescheduled poll", body = Poll),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the creator of the poll", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll already opened", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(poll_id): Path<Uuid>,
+    Json(payload): Json<UpdatePollPayload>,
+) -> Result<Json<Poll>, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    session.ensure_can_edit(&poll)?;
+    ensure_not_opened(&poll)?;
+
+    let poll_opening_time = payload.poll_opening_time.unwrap_or(poll.poll_opening_time);
+    let poll_closing_time = payload.poll_closing_time.unwrap_or(poll.poll_closing_time);
+    if poll_closing_time <= poll_opening_time {
+        return Err(ApiError::Validation("A poll must close after it opens".to_owned()));
+    }
+
+    let event = Event::PollRescheduled {
+        poll_id,
+        poll_opening_time,
+        poll_closing_time,
+    };
+    events::record(&state.pool, Some(session.voter_id), &event).await?;
+
+    Ok(Json(Poll {
+        poll_opening_time,
+        poll_closing_time,
+        ..poll
+    }))
+}
+
+/// Deletes a poll nobody has voted in yet.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/polls/{poll_id}",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 204, description = "Poll deleted"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the creator of the poll", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll already has ballots", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(poll_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    session.ensure_can_edit(&poll)?;
+    if count_ballots(&state.pool, poll_id).await? > 0 {
+        return Err(ApiError::Conflict(format!(
+            "Poll {poll_id} already has ballots and cannot be deleted"
+        )));
+    }
+
+    events::record(&state.pool, Some(session.voter_id), &Event::PollDeleted { poll_id }).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Closes an open poll before its scheduled closing time.
+#[utoipa::path(
+    post,
+    path = "/api/v1/polls/{poll_id}/close",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Closed poll", body = Poll),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the creator of the poll", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll is not open", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn close_poll(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Json<Poll>, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    session.ensure_can_edit(&poll)?;
+
+    let now = OffsetDateTime::now_utc();
+    if now < poll.poll_opening_time || now >= poll.poll_closing_time {
+        return Err(ApiError::PollClosed(poll_id));
+    }
+
+    let event = Event::PollClosed {
+        poll_id,
+        closed_at: now,
+    };
+    events::record(&state.pool, Some(session.voter_id), &event).await?;
+
+    Ok(Json(Poll {
+        poll_closing_time: now,
+        ..poll
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}/questions",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Questions of the poll", body = [PollQuestion]),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_questions(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Json<Vec<PollQuestion>>, ApiError> {
+    Ok(Json(load_poll(&state.pool, tenant.organization_id, poll_id).await?.questions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/polls/{poll_id}/questions",
+    tag = "polls",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    request_body = CreateQuestionPayload,
+    responses(
+        (status = 201, description = "Question added", body = PollQuestion),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the creator of the poll", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll already opened", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_question(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(poll_id): Path<Uuid>,
+    Json(payload): Json<CreateQuestionPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let question = add_question(&state, &session, tenant.organization_id, poll_id, payload).await?;
+    let location = format!("/api/v1/polls/{poll_id}/questions/{}", question.question_id);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(question)))
+}
+
+pub(super) async fn add_question(
+    state: &AppState,
+    session: &Session,
+    organization_id: Uuid,
+    poll_id: Uuid,
+    payload: CreateQuestionPayload,
+) -> Result<PollQuestion, ApiError> {
+    if payload.question_description.trim().is_empty() {
+        return Err(ApiError::Validation("The question description is required".to_owned()));
+    }
+    if payload.vote_options.is_empty() {
+        return Err(ApiError::Validation("A question needs at least one vote option".to_owned()));
+    }
+
+    let poll = load_poll(&state.pool, organization_id, poll_id).await?;
+    session.ensure_can_edit(&poll)?;
+    ensure_not_opened(&poll)?;
+    ensure_topics(payload.question_id, &payload.question_topics)?;
+    taxonomy::ensure_usable(&state.pool, organization_id, &payload.question_topics).await?;
+
+    let question = PollQuestion {
+        poll_id,
+        question_id: payload.question_id,
+        question_type: payload.question_type,
+        question_topics: payload.question_topics,
+        question_description: payload.question_description,
+        vote_options: payload.vote_options,
+        votes: Vec::new(),
+        min_approvals: payload.min_approvals,
+        max_approvals: payload.max_approvals,
+        seats: payload.seats,
+        surplus_transfer: payload.surplus_transfer,
+        apportionment: payload.apportionment,
+        threshold: payload.threshold,
+        grades: payload.grades,
+    };
+    ensure_counting(&question, poll.credits)?;
+
+    events::record(
+        &state.pool,
+        Some(session.voter_id),
+        &Event::QuestionCreated(question.clone()),
+    )
+    .await?;
+
+    Ok(question)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/polls/{poll_id}/ballots",
+    tag = "ballots",
+    params(
+        ("poll_id" = Uuid, Path, description = "Poll id"),
+        ("X-Second-Factor" = Option<String>, Header, description = "Current TOTP code or a recovery code, for voters who set one up"),
+    ),
+    request_body = CastBallotPayload,
+    responses(
+        (status = 201, description = "Ballot cast", body = RankedChoiceVote),
+        (status = 401, description = "Not logged in, or missing or wrong second factor", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed to vote, or not in the electorate", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Poll is not open, or the voter already voted", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_ballot(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<CastBallots>,
+    Path(poll_id): Path<Uuid>,
+    Json(payload): Json<CastBallotPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let ballot = cast_ballot(&state, tenant.organization_id, session.voter_id, poll_id, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ballot)))
+}
+
+/// Casts a ballot of `voter_id` in a poll of the organization.
+pub(super) async fn cast_ballot(
+    state: &AppState,
+    organization_id: Uuid,
+    voter_id: Uuid,
+    poll_id: Uuid,
+    payload: CastBallotPayload,
+) -> Result<RankedChoiceVote, ApiError> {
+    if payload.vote.is_empty() {
+        return Err(ApiError::Validation("The ballot is empty".to_owned()));
+    }
+
+    let poll = load_poll(&state.pool, organization_id, poll_id).await?;
+    let now = OffsetDateTime::now_utc();
+    if now < poll.poll_opening_time || now >= poll.poll_closing_time {
+        return Err(ApiError::PollClosed(poll_id));
+    }
+    electorate::ensure(&state.pool, &poll).await?;
+    if !electorate::includes(&state.pool, poll_id, voter_id).await? {
+        return Err(ApiError::Forbidden(format!(
+            "You are not in the electorate of poll {poll_id}"
+        )));
+    }
+
+    let vote = assign_questions(&poll, payload.vote)?;
+    ensure_valid_ballot(&poll, &vote)?;
+    if has_voted(&state.pool, poll_id, voter_id).await? {
+        return Err(already_voted(poll_id));
+    }
+
+    let ballot = RankedChoiceVote {
+        user_id: voter_id,
+        poll_id,
+        vote_time: now.time(),
+        vote,
+    };
+
+    // `votes` is unique by voter and poll, should another ballot slip in.
+    events::record(&state.pool, Some(ballot.user_id), &Event::BallotCast(ballot.clone()))
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Conflict(_) => already_voted(poll_id),
+            e => e,
+        })?;
+    state.live.notify(poll_id);
+
+    Ok(ballot)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}/participation",
+    tag = "results",
+    params(("poll_id" = Uuid, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Ballots cast against the size of the electorate", body = Participation),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the results:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_poll_participation(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadResults>,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Json<Participation>, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    electorate::ensure(&state.pool, &poll).await?;
+
+    let ballots = count_ballots(&state.pool, poll_id).await?;
+    let eligible = electorate::size(&state.pool, poll_id).await?;
+
+    Ok(Json(Participation {
+        ballots,
+        eligible,
+        turnout: eligible
+            .filter(|&eligible| eligible > 0)
+            .map(|eligible| ballots as f64 / eligible as f64),
+    }))
+}
+
+/// Lists the results of each question of the poll, by question id.
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}/results",
+    tag = "results",
+    params(("poll_id" = Uuid, Path, description = "Poll id"), ListResultsQuery),
+    responses(
+        (status = 200, description = "A page of question results", body = Page<QuestionResult>),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the results:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown poll", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid query", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_poll_results(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadResults>,
+    Path(poll_id): Path<Uuid>,
+    Query(query): Query<ListResultsQuery>,
+) -> Result<Json<Page<QuestionResult>>, ApiError> {
+    let keyset = Keyset::new(query.limit, query.cursor.as_deref(), Order::Asc)?;
+
+    // Fails with a 404 for unknown polls rather than an empty list.
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    tally::ensure(&state.pool, &poll).await?;
+
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT poll_id, question_id, vote_results, delegated_results, raw_vote_results, raw_delegated_results,
+            approval_rates, stv, seat_allocation, quadratic, majority_judgment, report, group_results,
+            college_results
+        FROM question_results
+        WHERE poll_id = "#,
+    );
+    sql.push_bind(poll_id.to_string());
+    // Results are keyed by question alone, hence the id as both sort keys.
+    keyset.finish(&mut sql, "question_id", "question_id");
+
+    let results = sql
+        .query()
+        .fetch_all(&state.pool)
+        .await?
+        .iter()
+        .map(question_result_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(keyset.page(results, |result| {
+        (result.question_id.to_string(), result.question_id.to_string())
+    })))
+}
+
+/// The per-poll summaries behind the deprecated `GET /get_poll_results/:poll_id`.
+pub(super) async fn get_poll_results(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(poll_id): Path<Uuid>,
+) -> Result<Json<Vec<PollResult>>, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    tally::ensure(&state.pool, &poll).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT creator_user_id, poll_id, results
+        FROM poll_results
+        WHERE poll_id = $1 AND poll_id IN (SELECT poll_id FROM polls WHERE organization_id = $2)
+        "#,
+    )
+    .bind(poll_id.to_string())
+    .bind(tenant.organization_id.to_string())
+    .fetch_all(&state.pool)
+    .await?;
+
+    let results = rows
+        .iter()
+        .map(|row| {
+            Ok(PollResult {
+                creator_user_id: db::parse_column(row, "creator_user_id")?,
+                poll_id: db::parse_column(row, "poll_id")?,
+                results: db::json_column(row, "results")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    Ok(Json(results))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/polls/{poll_id}/questions/{question_id}/results",
+    tag = "results",
+    params(
+        ("poll_id" = Uuid, Path, description = "Poll id"),
+        ("question_id" = Uuid, Path, description = "Question id"),
+    ),
+    responses(
+        (status = 200, description = "Question results", body = [QuestionResult]),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the results:read scope", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_question_results(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadResults>,
+    Path((poll_id, question_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<QuestionResult>>, ApiError> {
+    let poll = load_poll(&state.pool, tenant.organization_id, poll_id).await?;
+    tally::ensure(&state.pool, &poll).await?;
+
+    Ok(Json(
+        load_question_results(&state.pool, tenant.organization_id, Some(poll_id), question_id).await?,
+    ))
+}
+
+/// Results of the question, in polls of the organization.
+pub(super) async fn load_question_results(
+    pool: &Pool,
+    organization_id: Uuid,
+    poll_id: Option<Uuid>,
+    question_id: Uuid,
+) -> Result<Vec<QuestionResult>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT poll_id, question_id, vote_results, delegated_results, raw_vote_results, raw_delegated_results,
+            approval_rates, stv, seat_allocation, quadratic, majority_judgment, report, group_results,
+            college_results
+        FROM question_results
+        WHERE question_id = $1 AND ($2 IS NULL OR poll_id = $2)
+            AND poll_id IN (SELECT poll_id FROM polls WHERE organization_id = $3)
+        "#,
+    )
+    .bind(question_id.to_string())
+    .bind(poll_id.map(|id| id.to_string()))
+    .bind(organization_id.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(question_result_from_row).collect()
+}
+
+fn question_result_from_row(row: &AnyRow) -> Result<QuestionResult, sqlx::Error> {
+    Ok(QuestionResult {
+        poll_id: db::parse_column(row, "poll_id")?,
+        question_id: db::parse_column(row, "question_id")?,
+        vote_results: db::json_column(row, "vote_results")?,
+        delegated_results: db::json_column(row, "delegated_results")?,
+        raw_vote_results: db::json_column(row, "raw_vote_results")?,
+        raw_delegated_results: db::json_column(row, "raw_delegated_results")?,
+        approval_rates: db::json_column(row, "approval_rates")?,
+        stv: db::json_column(row, "stv")?,
+        seat_allocation: db::json_column(row, "seat_allocation")?,
+        quadratic: db::json_column(row, "quadratic")?,
+        majority_judgment: db::json_column(row, "majority_judgment")?,
+        report: db::json_column(row, "report")?,
+        groups: db::json_column(row, "group_results")?,
+        college_results: db::json_column(row, "college_results")?,
+    })
+}
+
+/// Colleges are distinct groups whose shares add up to 1.
+fn ensure_colleges(colleges: &[College]) -> Result<(), ApiError> {
+    if colleges.is_empty() {
+        return Ok(());
+    }
+    if let Some(college) = colleges
+        .iter()
+        .find(|college| !(college.share > 0.0 && college.share <= 1.0))
+    {
+        return Err(ApiError::Validation(format!(
+            "The share of college {:?} must be above 0 and at most 1",
+            college.group
+        )));
+    }
+    if let Some(college) = colleges.iter().enumerate().find_map(|(index, college)| {
+        colleges[..index].iter().any(|other| other.group == college.group).then_some(college)
+    }) {
+        return Err(ApiError::Validation(format!(
+            "Group {:?} is listed as a college twice",
+            college.group
+        )));
+    }
+    let total: f64 = colleges.iter().map(|college| college.share).sum();
+    if (total - 1.0).abs() > 1e-9 {
+        return Err(ApiError::Validation(format!("College shares add up to {total}, not 1")));
+    }
+    Ok(())
+}
+
+/// A question needs at least one topic, each listed once.
+fn ensure_topics(question_id: Uuid, topics: &[Uuid]) -> Result<(), ApiError> {
+    if topics.is_empty() {
+        return Err(ApiError::Validation(format!("Question {question_id} needs at least one topic")));
+    }
+    if let Some(topic_id) = topics
+        .iter()
+        .enumerate()
+        .find_map(|(index, topic_id)| topics[..index].contains(topic_id).then_some(topic_id))
+    {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} lists topic {topic_id} twice"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the settings of `question` that only some poll types take, in a
+/// poll giving voters `credits`.
+fn ensure_counting(question: &PollQuestion, credits: Option<u32>) -> Result<(), ApiError> {
+    let question_id = question.question_id;
+    match question.question_type {
+        PollType::Approval => {
+            let min = question.min_approvals.unwrap_or(0);
+            let max = question.max_approvals.unwrap_or(u32::MAX);
+            if max == 0 || min > max {
+                return Err(ApiError::Validation(format!(
+                    "Question {question_id} must allow at least one approval, and no fewer than min_approvals"
+                )));
+            }
+            if min as usize > question.vote_options.len() {
+                return Err(ApiError::Validation(format!(
+                    "Question {question_id} requires more approvals than it has options"
+                )));
+            }
+        }
+        PollType::SingleTransferableVote => {
+            let seats = question.seats.unwrap_or(0);
+            if seats == 0 || seats as usize > question.vote_options.len() {
+                return Err(ApiError::Validation(format!(
+                    "Question {question_id} needs a number of seats between 1 and its number of options"
+                )));
+            }
+        }
+        PollType::QuadraticVoting if credits.is_none() => {
+            return Err(ApiError::Validation(format!(
+                "Question {question_id} is a quadratic voting question, and its poll gives voters no credits"
+            )));
+        }
+        PollType::MajorityJudgment => {
+            if question.grades.len() < 2 {
+                return Err(ApiError::Validation(format!(
+                    "Question {question_id} needs a scale of at least two grades"
+                )));
+            }
+            let blank = question.grades.iter().any(|grade| grade.trim().is_empty());
+            let repeated = question
+                .grades
+                .iter()
+                .enumerate()
+                .any(|(index, grade)| question.grades[..index].contains(grade));
+            if blank || repeated {
+                return Err(ApiError::Validation(format!(
+                    "The grades of question {question_id} must be named, each differently"
+                )));
+            }
+        }
+        PollType::PartyList => {
+            if question.seats.unwrap_or(0) == 0 {
+                return Err(ApiError::Validation(format!(
+                    "Question {question_id} needs at least one seat to share between its lists"
+                )));
+            }
+            if question.threshold.is_some_and(|threshold| !(0.0..=100.0).contains(&threshold)) {
+                return Err(ApiError::Validation(format!(
+                    "The threshold of question {question_id} must be a percentage between 0 and 100"
+                )));
+            }
+        }
+        _ => {}
+    }
+
+    let approval = matches!(question.question_type, PollType::Approval);
+    if !approval && (question.min_approvals.is_some() || question.max_approvals.is_some()) {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} is not an approval question and takes no min_approvals or max_approvals"
+        )));
+    }
+    let stv = matches!(question.question_type, PollType::SingleTransferableVote);
+    let party_list = matches!(question.question_type, PollType::PartyList);
+    if !stv && !party_list && question.seats.is_some() {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} elects nobody and takes no seats"
+        )));
+    }
+    if !stv && question.surplus_transfer.is_some() {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} is not a single transferable vote question and takes no surplus_transfer"
+        )));
+    }
+    if !matches!(question.question_type, PollType::MajorityJudgment) && !question.grades.is_empty() {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} is not a majority judgment question and takes no grades"
+        )));
+    }
+    if !party_list && (question.apportionment.is_some() || question.threshold.is_some()) {
+        return Err(ApiError::Validation(format!(
+            "Question {question_id} is not a party-list question and takes no apportionment or threshold"
+        )));
+    }
+    Ok(())
+}
+
+/// Gives each answer of `vote` the question it answers, refusing answers
+/// to no question of `poll`, and answers without a question to an option
+/// several questions have.
+fn assign_questions(poll: &Poll, mut vote: Vec<ScoredVote>) -> Result<Vec<ScoredVote>, ApiError> {
+    for answer in &mut vote {
+        let mut questions = poll.questions.iter().filter(|question| {
+            question.vote_options.iter().any(|option| answer.answers(question.question_id, option))
+        });
+        match (questions.next(), questions.next()) {
+            (Some(question), None) => answer.question_id = Some(question.question_id),
+            (Some(_), Some(_)) => {
+                return Err(ApiError::Validation(format!(
+                    "Several questions of poll {} have an option {:?}: give the question_id it answers",
+                    poll.poll_id, answer.vote
+                )));
+            }
+            (None, _) => {
+                return Err(ApiError::Validation(match answer.question_id {
+                    Some(question_id) => format!(
+                        "Question {question_id} of poll {} has no option {:?}",
+                        poll.poll_id, answer.vote
+                    ),
+                    None => format!("No question of poll {} has an option {:?}", poll.poll_id, answer.vote),
+                }));
+            }
+        }
+    }
+    Ok(vote)
+}
+
+/// Checks `vote` against the rules of the questions of `poll`.
+fn ensure_valid_ballot(poll: &Poll, vote: &[ScoredVote]) -> Result<(), ApiError> {
+    for question in &poll.questions {
+        let question_id = question.question_id;
+        let answers = vote
+            .iter()
+            .filter(|vote| question.vote_options.iter().any(|option| vote.answers(question_id, option)))
+            .collect::<Vec<_>>();
+
+        let repeated = answers.iter().enumerate().find_map(|(index, answer)| {
+            answers[..index].iter().any(|other| other.vote == answer.vote).then_some(answer)
+        });
+
+        let quadratic = matches!(question.question_type, PollType::QuadraticVoting);
+        if !quadratic && answers.iter().any(|answer| answer.against) {
+            return Err(ApiError::Validation(format!(
+                "Question {question_id} is not a quadratic voting question and takes no votes against"
+            )));
+        }
+
+        match question.question_type {
+            PollType::Approval => {
+                if answers.iter().any(|answer| answer.score > 1) {
+                    return Err(ApiError::Validation(format!(
+                        "Question {question_id} takes approvals: score each option 1 to approve it, or 0"
+                    )));
+                }
+                if let Some(answer) = repeated {
+                    return Err(ApiError::Validation(format!(
+                        "Option {:?} of question {question_id} is answered twice",
+                        answer.vote
+                    )));
+                }
+                let approved = answers.iter().filter(|answer| answer.score == 1).count() as u32;
+                if question.min_approvals.is_some_and(|min| approved < min)
+                    || question.max_approvals.is_some_and(|max| approved > max)
+                {
+                    return Err(ApiError::Validation(format!(
+                        "Question {question_id} takes between {} and {} approvals, not {approved}",
+                        question.min_approvals.unwrap_or(0),
+                        question.max_approvals.unwrap_or(question.vote_options.len() as u32),
+                    )));
+                }
+            }
+            PollType::SingleTransferableVote => {
+                if let Some(answer) = repeated {
+                    return Err(ApiError::Validation(format!(
+                        "Option {:?} of question {question_id} is answered twice",
+                        answer.vote
+                    )));
+                }
+                // Ranks 1, 2, 3... with 0 for options left unranked.
+                let mut ranks = answers
+                    .iter()
+                    .map(|answer| answer.score)
+                    .filter(|&rank| rank > 0)
+                    .collect::<Vec<_>>();
+                ranks.sort_unstable();
+                if ranks.iter().zip(1..).any(|(&rank, expected)| rank != expected) {
+                    return Err(ApiError::Validation(format!(
+                        "Question {question_id} takes a ranking: score options 1, 2, 3... in order of \
+                         preference, each rank once, and 0 for those left out"
+                    )));
+                }
+            }
+            PollType::PartyList => {
+                let chosen = answers.iter().filter(|answer| answer.score > 0).collect::<Vec<_>>();
+                if chosen.len() > 1 || chosen.iter().any(|answer| answer.score > 1) {
+                    return Err(ApiError::Validation(format!(
+                        "Question {question_id} takes one list: score it 1, and the others 0"
+                    )));
+                }
+            }
+            PollType::QuadraticVoting => {
+                if let Some(answer) = repeated {
+                    return Err(ApiError::Validation(format!(
+                        "Option {:?} of question {question_id} is answered twice",
+                        answer.vote
+                    )));
+                }
+            }
+            PollType::MajorityJudgment => {
+                if let Some(answer) = repeated {
+                    return Err(ApiError::Validation(format!(
+                        "Option {:?} of question {question_id} is answered twice",
+                        answer.vote
+                    )));
+                }
+                let best = question.grades.len() - 1;
+                if answers.iter().any(|answer| answer.score as usize > best) {
+                    return Err(ApiError::Validation(format!(
+                        "Question {question_id} takes grades: score each option from 0 for {:?} to {best} for {:?}",
+                        question.grades[best], question.grades[0]
+                    )));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let credits = poll.credits.unwrap_or(0);
+    match quadratic::spent(&poll.questions, vote) {
+        Some(spent) if spent <= u64::from(credits) => Ok(()),
+        Some(spent) => Err(ApiError::Validation(format!(
+            "This ballot spends {spent} credits, more than the {credits} the poll gives each voter"
+        ))),
+        None => Err(ApiError::Validation(format!(
+            "This ballot spends far more than the {credits} credits the poll gives each voter"
+        ))),
+    }
+}
+
+fn ensure_not_opened(poll: &Poll) -> Result<(), ApiError> {
+    if OffsetDateTime::now_utc() >= poll.poll_opening_time {
+        return Err(ApiError::Conflict(format!(
+            "Poll {} has already opened and can no longer be edited",
+            poll.poll_id
+        )));
+    }
+    Ok(())
+}
+
+async fn has_voted(pool: &Pool, poll_id: Uuid, voter_id: Uuid) -> Result<bool, sqlx::Error> {
+    Ok(sqlx::query("SELECT 1 FROM votes WHERE poll_id = $1 AND user_id = $2")
+        .bind(poll_id.to_string())
+        .bind(voter_id.to_string())
+        .fetch_optional(pool)
+        .await?
+        .is_some())
+}
+
+fn already_voted(poll_id: Uuid) -> ApiError {
+    ApiError::Conflict(format!("You already voted in poll {poll_id}; a ballot cannot be changed"))
+}
+
+pub(super) async fn count_ballots(pool: &Pool, poll_id: Uuid) -> Result<i64, sqlx::Error> {
+    let participation = sqlx::query(
+        r#"
+        SELECT COUNT(*) AS count
+        FROM votes
+        WHERE poll_id = $1
+        "#,
+    )
+    .bind(poll_id.to_string())
+    .fetch_one(pool)
+    .await?;
+
+    participation.try_get("count")
+}
+
+/// Loads a poll of the organization along with its questions.
+pub(crate) async fn load_poll(pool: &Pool, organization_id: Uuid, poll_id: Uuid) -> Result<Poll, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT creator_user_id, poll_id, organization_id, poll_opening_time, poll_closing_time, results_visibility, eligibility, breakdown,
+            credits
+        FROM polls
+        WHERE poll_id = $1 AND organization_id = $2
+        "#,
+    )
+    .bind(poll_id.to_string())
+    .bind(organization_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(ApiError::missing(format!("Poll {poll_id}")))?;
+
+    let questions = sqlx::query(
+        r#"
+        SELECT poll_id, question_id, question_type, question_topics, question_description, vote_options, votes,
+            min_approvals, max_approvals, seats, surplus_transfer, apportionment, threshold, grades
+        FROM poll_questions
+        WHERE poll_id = $1
+        ORDER BY question_id
+        "#,
+    )
+    .bind(poll_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(question_from_row)
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Poll {
+        questions,
+        ..poll_from_row(&row)?
+    })
+}
+
+/// Builds a poll from a `polls` row, without its questions.
+pub(super) fn poll_from_row(row: &AnyRow) -> Result<Poll, sqlx::Error> {
+    Ok(Poll {
+        creator_user_id: db::parse_column(row, "creator_user_id")?,
+        poll_id: db::parse_column(row, "poll_id")?,
+        organization_id: db::parse_column(row, "organization_id")?,
+        poll_opening_time: db::timestamp_column(row, "poll_opening_time")?,
+        poll_closing_time: db::timestamp_column(row, "poll_closing_time")?,
+        results_visibility: db::parse_column(row, "results_visibility")?,
+        eligibility: db::json_column(row, "eligibility")?,
+        breakdown: db::json_column(row, "breakdown")?,
+        credits: row.try_get::<Option<i64>, _>("credits")?.map(|n| n as u32),
+        questions: Vec::new(),
+    })
+}
+
+pub(super) fn question_from_row(row: &AnyRow) -> Result<PollQuestion, sqlx::Error> {
+    Ok(PollQuestion {
+        poll_id: db::parse_column(row, "poll_id")?,
+        question_id: db::parse_column(row, "question_id")?,
+        question_type: db::parse_column(row, "question_type")?,
+        question_topics: db::json_column(row, "question_topics")?,
+        question_description: row.try_get("question_description")?,
+        vote_options: db::json_column(row, "vote_options")?,
+        votes: db::json_column(row, "votes")?,
+        min_approvals: row.try_get::<Option<i64>, _>("min_approvals")?.map(|n| n as u32),
+        max_approvals: row.try_get::<Option<i64>, _>("max_approvals")?.map(|n| n as u32),
+        seats: row.try_get::<Option<i64>, _>("seats")?.map(|n| n as u32),
+        surplus_transfer: db::optional_parse_column(row, "surplus_transfer")?,
+        apportionment: db::optional_parse_column(row, "apportionment")?,
+        threshold: row.try_get("threshold")?,
+        grades: db::json_column(row, "grades")?,
+    })
+}
@@ -0,0 +1,260 @@
+//! Single sign-on through the organization's identity provider, see
+//! [`crate::oidc`].
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{AppendHeaders, IntoResponse, Redirect},
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use super::accounts;
+use super::voters::{self, CreateUserPayload};
+use crate::auth;
+use crate::db;
+use crate::error::{ApiError, Problem, Query};
+use crate::events::{self, Event};
+use crate::oidc::{self, Identity};
+use crate::tenant::{self, Tenant};
+use crate::{AppState, Organization, Voter, VoterStatus};
+
+/// Ties the callback to the browser that started the login.
+const STATE_COOKIE: &str = "oidc_state";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CallbackQuery {
+    /// Authorization code, when the voter logged in.
+    code: Option<String>,
+    state: Option<String>,
+    /// Error code, when the provider refused the login.
+    error: Option<String>,
+}
+
+/// Sends the browser to the identity provider to log in to the organization.
+#[utoipa::path(
+    get,
+    path = "/api/v1/session/oidc",
+    tag = "accounts",
+    responses(
+        (status = 303, description = "Redirect to the identity provider"),
+        (status = 404, description = "Single sign-on is not configured in the organization", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn start_sso(State(state): State<AppState>, tenant: Tenant) -> Result<impl IntoResponse, ApiError> {
+    let config = config(&state, &tenant)?;
+    let (url, login_state) = oidc::start_login(&state.pool, &config, tenant.organization_id).await?;
+
+    Ok((
+        [(header::SET_COOKIE, state_cookie(&login_state, oidc::LOGIN_LIFETIME.whole_seconds()))],
+        Redirect::to(&url),
+    ))
+}
+
+/// Where the identity provider sends the browser back. Logs the voter in,
+/// registering them in the organization the login started in first if
+/// allowed, and redirects to the home page.
+#[utoipa::path(
+    get,
+    path = "/api/v1/session/oidc/callback",
+    tag = "accounts",
+    params(CallbackQuery),
+    responses(
+        (status = 303, description = "Logged in, session cookie set"),
+        (status = 401, description = "Login refused, expired or started in another browser", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "No voter matches the identity and registration is closed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Single sign-on is not configured in the organization", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn finish_sso(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(error) = query.error {
+        return Err(ApiError::Unauthorized(format!(
+            "The identity provider refused the login: {error}"
+        )));
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(ApiError::Validation("The callback needs a code and a state".to_owned()));
+    };
+    if auth::cookie(&headers, STATE_COOKIE) != Some(login_state.as_str()) {
+        return Err(ApiError::Unauthorized(
+            "This login was started in another browser".to_owned(),
+        ));
+    }
+
+    let login = oidc::take_login(&state.pool, &login_state).await?;
+    let organization_id = login.organization_id;
+    let config = config(&state, &tenant::load(&state.pool, organization_id).await?)?;
+    let identity = oidc::finish_login(&config, login, code).await?;
+    let mut voter = find_or_provision(&state, &config, organization_id, &identity).await?;
+    if identity.email_verified && identity.email.as_deref() == Some(voter.email.as_str()) {
+        voter = accounts::mark_verified(&state, voter).await?;
+    }
+    sync_roles(&state, &config, organization_id, voter.voter_id, &identity.groups).await?;
+
+    let cookie = auth::start_session(&state.pool, voter.voter_id).await?;
+
+    Ok((
+        AppendHeaders([
+            (header::SET_COOKIE, cookie),
+            (header::SET_COOKIE, state_cookie("", 0)),
+        ]),
+        Redirect::to("/"),
+    ))
+}
+
+/// The identity provider `organization` configured; single sign-on is off
+/// in the others.
+fn config(state: &AppState, organization: &Organization) -> Result<oidc::Config, ApiError> {
+    let settings = organization.settings.sso.as_ref().ok_or_else(|| {
+        ApiError::NotFound("Single sign-on is not configured in this organization".to_owned())
+    })?;
+
+    oidc::Config::new(settings, &state.public_url)
+}
+
+fn state_cookie(value: &str, max_age: i64) -> String {
+    format!(
+        "{STATE_COOKIE}={value}; Path=/api/v1/session/oidc; Max-Age={max_age}; HttpOnly; Secure; SameSite=Lax"
+    )
+}
+
+/// The voter linked to `identity`. On a first login, links the member of
+/// the organization registered with the same verified address, or registers
+/// a new voter. Voters who are not members of the organization join it if
+/// registration is open.
+async fn find_or_provision(
+    state: &AppState,
+    config: &oidc::Config,
+    organization_id: Uuid,
+    identity: &Identity,
+) -> Result<Voter, ApiError> {
+    let linked = sqlx::query("SELECT voter_id FROM oidc_identities WHERE issuer = $1 AND subject = $2")
+        .bind(config.issuer())
+        .bind(&identity.subject)
+        .fetch_optional(&state.pool)
+        .await?;
+    if let Some(row) = linked {
+        let voter_id = db::parse_column(&row, "voter_id")?;
+        join(state, config, organization_id, voter_id).await?;
+        return voters::load_account(&state.pool, organization_id, voter_id).await;
+    }
+
+    // Only an address the provider checked may claim an existing voter, and
+    // only a member: the provider is trusted by this organization alone.
+    let existing = match identity.email.as_deref().filter(|_| identity.email_verified) {
+        Some(email) => sqlx::query(
+            r#"
+            SELECT voters.voter_id
+            FROM voters
+            JOIN organization_members ON organization_members.voter_id = voters.voter_id
+            WHERE organization_members.organization_id = $1 AND voters.email = $2
+            "#,
+        )
+        .bind(organization_id.to_string())
+        .bind(email)
+        .fetch_optional(&state.pool)
+        .await?,
+        None => None,
+    };
+
+    let mut tx = state.pool.begin().await?;
+    let voter = match existing {
+        Some(row) => {
+            let voter_id = db::parse_column(&row, "voter_id")?;
+            join(state, config, organization_id, voter_id).await?;
+            voters::load_account(&state.pool, organization_id, voter_id).await?
+        }
+        None if config.auto_provision => {
+            let voter = provisioned_voter(identity)?;
+            voters::record_new_voter(&mut tx, organization_id, voter.voter_id, &voter).await?;
+            voter
+        }
+        None => {
+            return Err(ApiError::Forbidden(
+                "No voter is registered for this identity; ask an admin to register you".to_owned(),
+            ))
+        }
+    };
+    sqlx::query("INSERT INTO oidc_identities (issuer, subject, voter_id) VALUES ($1, $2, $3)")
+        .bind(config.issuer())
+        .bind(&identity.subject)
+        .bind(voter.voter_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(voter)
+}
+
+/// Makes `voter_id` a member of the organization when registration is open;
+/// otherwise they log in without being one, and have no roles there.
+async fn join(
+    state: &AppState,
+    config: &oidc::Config,
+    organization_id: Uuid,
+    voter_id: Uuid,
+) -> Result<(), ApiError> {
+    if config.auto_provision && !tenant::is_member(&state.pool, organization_id, voter_id).await? {
+        let mut conn = state.pool.acquire().await?;
+        voters::add_member(&mut conn, organization_id, voter_id, voter_id).await?;
+    }
+
+    Ok(())
+}
+
+fn provisioned_voter(identity: &Identity) -> Result<Voter, ApiError> {
+    let missing = |claim: &str| {
+        ApiError::Forbidden(format!(
+            "The identity provider did not share your {claim}, which registration needs"
+        ))
+    };
+
+    voters::new_voter(CreateUserPayload {
+        first_name: identity.first_name.clone().ok_or_else(|| missing("first name"))?,
+        last_name: identity.last_name.clone().ok_or_else(|| missing("last name"))?,
+        email: identity.email.clone().ok_or_else(|| missing("email address"))?,
+        birth_date: identity.birth_date.ok_or_else(|| missing("birth date"))?,
+        status: VoterStatus::Private,
+    })
+}
+
+/// Grants and revokes the roles the group mapping manages in the
+/// organization so that they match the groups the provider lists; other
+/// roles are left alone, and non-members get none.
+async fn sync_roles(
+    state: &AppState,
+    config: &oidc::Config,
+    organization_id: Uuid,
+    voter_id: Uuid,
+    groups: &[String],
+) -> Result<(), ApiError> {
+    if !tenant::is_member(&state.pool, organization_id, voter_id).await? {
+        return Ok(());
+    }
+    let current = auth::load_roles(&state.pool, organization_id, voter_id).await?;
+
+    for (role, granted) in config.roles_from_groups(groups) {
+        let event = match (granted, current.contains(&role)) {
+            (true, false) => Event::RoleGranted {
+                organization_id,
+                voter_id,
+                role,
+            },
+            (false, true) => Event::RoleRevoked {
+                organization_id,
+                voter_id,
+                role,
+            },
+            _ => continue,
+        };
+        events::record(&state.pool, None, &event).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! The topics of the organization, see [`crate::taxonomy`].
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Deserializer};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::auth::{Authorized, ManageTopics, Public, ReadPolls};
+use crate::error::{ApiError, Json, Path, Problem, Query};
+use crate::events::{self, Event};
+use crate::taxonomy;
+use crate::tenant::Tenant;
+use crate::{AppState, Topic};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTopicPayload {
+    /// Broader topic to nest this one under.
+    parent_id: Option<Uuid>,
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTopicPayload {
+    /// Moves the topic under another one, or to the top level when `null`.
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<Uuid>)]
+    parent_id: Option<Option<Uuid>>,
+    name: Option<String>,
+    description: Option<String>,
+    /// Archives the topic, or restores it when `false`.
+    archived: Option<bool>,
+}
+
+/// Tells an explicit `null` apart from a missing field.
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error> {
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTopicsQuery {
+    /// Also list archived topics.
+    #[serde(default)]
+    archived: bool,
+}
+
+/// Lists the topics of the organization by name.
+#[utoipa::path(
+    get,
+    path = "/api/v1/topics",
+    tag = "topics",
+    params(ListTopicsQuery),
+    responses(
+        (status = 200, description = "Topics of the organization", body = [Topic]),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_topics(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Query(query): Query<ListTopicsQuery>,
+) -> Result<Json<Vec<Topic>>, ApiError> {
+    Ok(Json(taxonomy::list(&state.pool, tenant.organization_id, query.archived).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/topics",
+    tag = "topics",
+    request_body = CreateTopicPayload,
+    responses(
+        (status = 201, description = "Topic created", body = Topic),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Name already used in the organization", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_topic(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageTopics>,
+    Json(payload): Json<CreateTopicPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let topic = Topic {
+        topic_id: Uuid::new_v4(),
+        organization_id: tenant.organization_id,
+        parent_id: payload.parent_id,
+        name: payload.name,
+        description: payload.description,
+        archived_at: None,
+    };
+    save(&state, session.voter_id, Event::TopicCreated(topic.clone()), &topic).await?;
+    let location = format!("/api/v1/topics/{}", topic.topic_id);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(topic)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/topics/{topic_id}",
+    tag = "topics",
+    params(("topic_id" = Uuid, Path, description = "Topic id")),
+    responses(
+        (status = 200, description = "Topic", body = Topic),
+        (status = 401, description = "Invalid, revoked or expired API key", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "API key without the polls:read scope", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown topic", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_topic(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _access: Public<ReadPolls>,
+    Path(topic_id): Path<Uuid>,
+) -> Result<Json<Topic>, ApiError> {
+    Ok(Json(taxonomy::load(&state.pool, tenant.organization_id, topic_id).await?))
+}
+
+/// Renames, describes, moves, archives or restores a topic. Topics are
+/// archived rather than deleted, as delegations and questions keep referring
+/// to them.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/topics/{topic_id}",
+    tag = "topics",
+    params(("topic_id" = Uuid, Path, description = "Topic id")),
+    request_body = UpdateTopicPayload,
+    responses(
+        (status = 200, description = "Updated topic", body = Topic),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown topic", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Name already used in the organization", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_topic(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageTopics>,
+    Path(topic_id): Path<Uuid>,
+    Json(payload): Json<UpdateTopicPayload>,
+) -> Result<Json<Topic>, ApiError> {
+    let topic = taxonomy::load(&state.pool, tenant.organization_id, topic_id).await?;
+    let archived_at = match payload.archived {
+        Some(true) => topic.archived_at.or(Some(OffsetDateTime::now_utc())),
+        Some(false) => None,
+        None => topic.archived_at,
+    };
+
+    let topic = Topic {
+        parent_id: payload.parent_id.unwrap_or(topic.parent_id),
+        name: payload.name.unwrap_or(topic.name),
+        description: payload.description.unwrap_or(topic.description),
+        archived_at,
+        ..topic
+    };
+    save(&state, session.voter_id, Event::TopicUpdated(topic.clone()), &topic).await?;
+
+    Ok(Json(topic))
+}
+
+async fn save(state: &AppState, actor: Uuid, event: Event, topic: &Topic) -> Result<(), ApiError> {
+    if topic.name.trim().is_empty() {
+        return Err(ApiError::Validation("A topic needs a name".to_owned()));
+    }
+    if let Some(parent_id) = topic.parent_id {
+        let parents = taxonomy::parents(&state.pool, topic.organization_id).await?;
+        if !parents.contains_key(&parent_id) {
+            return Err(ApiError::Validation(format!(
+                "Topic {parent_id} does not exist in this organization"
+            )));
+        }
+        if taxonomy::ancestry(&parents, parent_id).contains(&topic.topic_id) {
+            return Err(ApiError::Validation(format!(
+                "Topic {} cannot be nested under itself or one of its subtopics",
+                topic.topic_id
+            )));
+        }
+    }
+
+    events::record(&state.pool, Some(actor), &event)
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Conflict(_) => {
+                ApiError::Conflict(format!("A topic named {:?} already exists", topic.name))
+            }
+            e => e,
+        })
+}
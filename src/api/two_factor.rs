@@ -0,0 +1,170 @@
+//! Enrollment in the TOTP second factor, see [`crate::totp`].
+
+use axum::{extract::State, http::HeaderMap, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::auth::Session;
+use crate::error::{ApiError, Json, Problem};
+use crate::totp::{self, Enrollment};
+use crate::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct TotpSetup {
+    /// Base32 secret, for apps that cannot scan the URL.
+    secret: String,
+    /// `otpauth://` URL to show as a QR code.
+    otpauth_url: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ConfirmTotpPayload {
+    /// First code shown by the authenticator app.
+    code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodes {
+    /// Single-use codes standing in for the authenticator app; shown once.
+    recovery_codes: Vec<String>,
+}
+
+/// Starts setting up TOTP for the logged-in voter. The setup takes effect
+/// once confirmed with a first code; starting over discards a pending one.
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts/totp",
+    tag = "accounts",
+    responses(
+        (status = 200, description = "Secret to load into an authenticator app", body = TotpSetup),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Two-factor authentication already set up", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn start_totp(
+    State(state): State<AppState>,
+    session: Session,
+) -> Result<Json<TotpSetup>, ApiError> {
+    if load_confirmed(&state, &session).await?.is_some() {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already set up".to_owned(),
+        ));
+    }
+
+    let email = super::voters::load_account(&state.pool, session.organization_id, session.voter_id)
+        .await?
+        .email;
+    let enrollment = totp::start_enrollment(&state.pool, session.voter_id, &email).await?;
+
+    Ok(Json(TotpSetup {
+        secret: enrollment.secret(),
+        otpauth_url: enrollment.otpauth_url(),
+    }))
+}
+
+/// Confirms the pending setup with a first code and returns recovery codes.
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts/totp/confirm",
+    tag = "accounts",
+    request_body = ConfirmTotpPayload,
+    responses(
+        (status = 200, description = "Two-factor authentication set up", body = RecoveryCodes),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No pending setup", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Two-factor authentication already set up", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Wrong or expired code", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    session: Session,
+    Json(payload): Json<ConfirmTotpPayload>,
+) -> Result<Json<RecoveryCodes>, ApiError> {
+    let enrollment = totp::load(&state.pool, session.voter_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No two-factor setup is pending".to_owned()))?;
+    if enrollment.confirmed() {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already set up".to_owned(),
+        ));
+    }
+    if !totp::check_code(&state.pool, session.voter_id, &enrollment, &payload.code).await? {
+        return Err(ApiError::Validation("The code is wrong or expired".to_owned()));
+    }
+
+    let recovery_codes = totp::confirm(&state.pool, session.voter_id).await?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+/// Replaces the recovery codes of the logged-in voter.
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts/totp/recovery-codes",
+    tag = "accounts",
+    params(("X-Second-Factor" = String, Header, description = "Current TOTP code or a recovery code")),
+    responses(
+        (status = 200, description = "New recovery codes; the previous ones no longer work", body = RecoveryCodes),
+        (status = 401, description = "Not logged in, or missing or wrong second factor", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Two-factor authentication not set up", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<Json<RecoveryCodes>, ApiError> {
+    require_confirmed(&state, &session).await?;
+    session.verify_second_factor(&state, &headers).await?;
+
+    let recovery_codes = totp::regenerate_recovery_codes(&state.pool, session.voter_id).await?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+/// Turns TOTP off for the logged-in voter, unless the server requires it.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/accounts/totp",
+    tag = "accounts",
+    params(("X-Second-Factor" = String, Header, description = "Current TOTP code or a recovery code")),
+    responses(
+        (status = 204, description = "Two-factor authentication turned off"),
+        (status = 401, description = "Not logged in, or missing or wrong second factor", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Two-factor authentication is mandatory", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Two-factor authentication not set up", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn disable_totp(
+    State(state): State<AppState>,
+    session: Session,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    if session.totp_mandatory(&state) {
+        return Err(ApiError::Forbidden(
+            "Two-factor authentication is mandatory in this organization".to_owned(),
+        ));
+    }
+    require_confirmed(&state, &session).await?;
+    session.verify_second_factor(&state, &headers).await?;
+
+    totp::disable(&state.pool, session.voter_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn load_confirmed(state: &AppState, session: &Session) -> Result<Option<Enrollment>, ApiError> {
+    Ok(totp::load(&state.pool, session.voter_id)
+        .await?
+        .filter(Enrollment::confirmed))
+}
+
+async fn require_confirmed(state: &AppState, session: &Session) -> Result<(), ApiError> {
+    match load_confirmed(state, session).await? {
+        Some(_) => Ok(()),
+        None => Err(ApiError::NotFound(
+            "Two-factor authentication is not set up".to_owned(),
+        )),
+    }
+}
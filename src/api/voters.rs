@@ -0,0 +1,799 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use sqlx::{any::AnyRow, AnyConnection, Row};
+use time::{Date, OffsetDateTime};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::pagination::{Keyset, Order, Page};
+use crate::auth::{self, Authorized, BrowseVoters, ManageVoters, Session};
+use crate::db::{self, Pool, SqlBuilder};
+use crate::error::{ApiError, Json, Path, Problem, Query};
+use crate::events::{self, Event};
+use crate::tenant::{self, Tenant};
+use crate::weights;
+use crate::{AppState, Delegation, Role, VoteHistory, Voter, VoterStatus, VotingWeight};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateUserPayload {
+    pub(super) first_name: String,
+    pub(super) last_name: String,
+    pub(super) email: String,
+    pub(super) birth_date: Date,
+    pub(super) status: VoterStatus,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateVoterPayload {
+    pub status: VoterStatus,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddToVoteHistoryPayload {
+    pub vote: String,
+    pub score: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetWeightPayload {
+    pub weight: f64,
+    /// First day the weight applies, today by default; it cannot be in the
+    /// past.
+    pub effective_from: Option<Date>,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VoterSort {
+    #[default]
+    LastName,
+    Email,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListVotersQuery {
+    /// Only voters with this status.
+    status: Option<VoterStatus>,
+    /// Only members of this group.
+    group: Option<String>,
+    #[serde(default)]
+    sort: VoterSort,
+    #[serde(default)]
+    order: Order,
+    /// Page size, 50 by default and at most 500.
+    limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters",
+    tag = "voters",
+    params(ListVotersQuery),
+    responses(
+        (status = 200, description = "A page of the members of the organization with their delegations", body = Page<Voter>),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid query", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_voters(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _session: Authorized<BrowseVoters>,
+    Query(query): Query<ListVotersQuery>,
+) -> Result<Json<Page<Voter>>, ApiError> {
+    let keyset = Keyset::new(query.limit, query.cursor.as_deref(), query.order)?;
+
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT voter_id, first_name, last_name, email, birth_date, status, email_verified_at
+        FROM voters
+        WHERE voter_id IN (SELECT voter_id FROM organization_members WHERE organization_id = "#,
+    );
+    sql.push_bind(tenant.organization_id.to_string()).push(")");
+    if let Some(status) = query.status {
+        sql.push(" AND status = ").push_bind(status.to_string());
+    }
+    if let Some(group) = query.group {
+        sql.push(" AND voter_id IN (SELECT voter_id FROM group_members WHERE organization_id = ")
+            .push_bind(tenant.organization_id.to_string())
+            .push(" AND group_name = ")
+            .push_bind(group)
+            .push(")");
+    }
+    let sort_column = match query.sort {
+        VoterSort::LastName => "last_name",
+        VoterSort::Email => "email",
+    };
+    keyset.finish(&mut sql, sort_column, "voter_id");
+
+    let mut voters = sql
+        .query()
+        .fetch_all(&state.pool)
+        .await?
+        .iter()
+        .map(voter_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let today = OffsetDateTime::now_utc().date();
+    let weights = weights::on(&state.pool, tenant.organization_id, today).await?;
+    for voter in voters.iter_mut() {
+        voter.voting_weight = weights.get(&voter.voter_id).copied().unwrap_or_else(weights::unit);
+    }
+
+    let ids = voters.iter().map(|voter| voter.voter_id).collect::<Vec<_>>();
+    for delegation in super::delegations::load_delegations_of(&state.pool, tenant.organization_id, &ids).await? {
+        for voter in voters.iter_mut() {
+            if voter.voter_id == delegation.from_principal {
+                voter.delegations_given.push(delegation.clone());
+            } else if voter.voter_id == delegation.to_delegate {
+                voter.delegations_received.push(delegation.clone());
+            }
+        }
+    }
+
+    Ok(Json(keyset.page(voters, |voter| {
+        let key = match query.sort {
+            VoterSort::LastName => voter.last_name.clone(),
+            VoterSort::Email => voter.email.clone(),
+        };
+        (key, voter.voter_id.to_string())
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voters",
+    tag = "voters",
+    request_body = CreateUserPayload,
+    responses(
+        (status = 201, description = "Voter created", body = Voter),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Email already registered", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+/// Registers a voter without a password, on behalf of an admin. They are
+/// emailed a link to confirm their address and can log in by magic link.
+///
+/// An address already registered is refused the same way whichever
+/// organization it belongs to, so that admins learn nothing about other
+/// organizations' voters.
+pub async fn create_voter(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Json(payload): Json<CreateUserPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = create_user(&state, tenant.organization_id, session.voter_id, payload).await?;
+    if user.email_verified_at.is_none() {
+        if let Err(e) = super::accounts::send_verification(&state, &user).await {
+            eprintln!("Failed to send the verification email to {}: {e:?}", user.email);
+        }
+    }
+    let location = format!("/api/v1/voters/{}", user.voter_id);
+
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(user)))
+}
+
+pub(super) async fn create_user(
+    state: &AppState,
+    organization_id: Uuid,
+    actor: Uuid,
+    payload: CreateUserPayload,
+) -> Result<Voter, ApiError> {
+    let user = new_voter(payload)?;
+
+    let mut tx = state.pool.begin().await?;
+    record_new_voter(&mut tx, organization_id, actor, &user).await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+/// Validates `payload` into a voter that has yet to be recorded.
+pub(super) fn new_voter(payload: CreateUserPayload) -> Result<Voter, ApiError> {
+    if payload.first_name.trim().is_empty() || payload.last_name.trim().is_empty() {
+        return Err(ApiError::Validation("First and last name are required".to_owned()));
+    }
+    if !is_plausible_email(&payload.email) {
+        return Err(ApiError::Validation(format!("{:?} is not a valid email address", payload.email)));
+    }
+
+    Ok(Voter {
+        voter_id: Uuid::new_v4(),
+        first_name: payload.first_name,
+        last_name: payload.last_name,
+        email: payload.email,
+        birth_date: payload.birth_date,
+        status: payload.status,
+        email_verified_at: None,
+        voting_weight: weights::unit(),
+        delegations_received: Vec::new(),
+        delegations_given: Vec::new(),
+    })
+}
+
+/// Records the creation of `voter`, who joins the organization.
+pub(super) async fn record_new_voter(
+    conn: &mut AnyConnection,
+    organization_id: Uuid,
+    actor: Uuid,
+    voter: &Voter,
+) -> Result<(), ApiError> {
+    events::record_in(conn, Some(actor), &Event::VoterCreated(voter.clone()))
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Conflict(_) => {
+                ApiError::Conflict(format!("A voter with email {} already exists", voter.email))
+            }
+            e => e,
+        })?;
+
+    add_member(conn, organization_id, actor, voter.voter_id).await
+}
+
+/// Makes `voter_id` a member of the organization, with the [`Role::Voter`]
+/// role.
+pub(super) async fn add_member(
+    conn: &mut AnyConnection,
+    organization_id: Uuid,
+    actor: Uuid,
+    voter_id: Uuid,
+) -> Result<(), ApiError> {
+    events::record_in(conn, Some(actor), &Event::MemberAdded { organization_id, voter_id }).await?;
+
+    let event = Event::RoleGranted {
+        organization_id,
+        voter_id,
+        role: Role::Voter,
+    };
+    events::record_in(conn, Some(actor), &event).await?;
+
+    Ok(())
+}
+
+/// Only catches obvious typos; the address is really checked by sending mail to it.
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.contains('@'),
+        None => false,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 200, description = "Voter with their delegations", body = Voter),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_voter(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+) -> Result<Json<Voter>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    Ok(Json(load_voter(&state.pool, tenant.organization_id, voter_id).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/voters/{voter_id}",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    request_body = UpdateVoterPayload,
+    responses(
+        (status = 200, description = "Updated voter", body = Voter),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the logged-in voter", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_voter(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+    Json(payload): Json<UpdateVoterPayload>,
+) -> Result<Json<Voter>, ApiError> {
+    session.ensure_is(voter_id)?;
+    load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+    set_status(&state, voter_id, payload.status).await?;
+
+    Ok(Json(load_voter(&state.pool, tenant.organization_id, voter_id).await?))
+}
+
+/// Removes the voter from the organization, along with their roles, groups
+/// and delegations there. Their account and other memberships are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/voters/{voter_id}",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 204, description = "The voter is no longer a member"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Not a member of the organization", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn remove_voter(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path(voter_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let event = Event::MemberRemoved {
+        organization_id: tenant.organization_id,
+        voter_id,
+    };
+    events::record(&state.pool, Some(session.voter_id), &event)
+        .await
+        .map_err(ApiError::missing(format!("Voter {voter_id}")))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub(super) async fn set_status(
+    state: &AppState,
+    voter_id: Uuid,
+    status: VoterStatus,
+) -> Result<(), ApiError> {
+    let event = Event::VoterStatusChanged { voter_id, status };
+
+    events::record(&state.pool, Some(voter_id), &event)
+        .await
+        .map_err(ApiError::missing(format!("Voter {voter_id}")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/history",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 200, description = "Vote history", body = [VoteHistory]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_vote_history(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+) -> Result<Json<Vec<VoteHistory>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    // Fails with a 404 for unknown voters rather than an empty history.
+    load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT vote, score
+        FROM vote_history
+        WHERE organization_id = $1 AND voter_id = $2
+        "#,
+    )
+    .bind(tenant.organization_id.to_string())
+    .bind(voter_id.to_string())
+    .fetch_all(&state.pool)
+    .await?;
+
+    let history = rows
+        .iter()
+        .map(|row| {
+            Ok(VoteHistory {
+                vote: row.try_get("vote")?,
+                score: row.try_get::<i64, _>("score")? as u32,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voters/{voter_id}/history",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    request_body = AddToVoteHistoryPayload,
+    responses(
+        (status = 201, description = "History entry added", body = VoteHistory),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the logged-in voter", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Not a member of the organization", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn add_to_vote_history(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+    Json(payload): Json<AddToVoteHistoryPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    session.ensure_is(voter_id)?;
+    let vote_history = record_vote_history(&state, tenant.organization_id, voter_id, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(vote_history)))
+}
+
+pub(super) async fn record_vote_history(
+    state: &AppState,
+    organization_id: Uuid,
+    voter_id: Uuid,
+    payload: AddToVoteHistoryPayload,
+) -> Result<VoteHistory, ApiError> {
+    load_voter(&state.pool, organization_id, voter_id).await?;
+
+    let vote_history = VoteHistory {
+        vote: payload.vote,
+        score: payload.score,
+    };
+
+    let event = Event::VoteHistoryAdded {
+        organization_id,
+        voter_id,
+        vote: vote_history.vote.clone(),
+        score: vote_history.score,
+    };
+
+    events::record(&state.pool, Some(voter_id), &event).await?;
+
+    Ok(vote_history)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/roles",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 200, description = "Roles of the voter", body = [Role]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_roles(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+) -> Result<Json<Vec<Role>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    // Fails with a 404 for unknown voters rather than an empty list.
+    load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+
+    Ok(Json(auth::load_roles(&state.pool, tenant.organization_id, voter_id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/voters/{voter_id}/roles/{role}",
+    tag = "voters",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("role" = Role, Path, description = "Role to grant"),
+    ),
+    responses(
+        (status = 204, description = "The voter has the role"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn grant_role(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path((voter_id, role)): Path<(Uuid, Role)>,
+) -> Result<StatusCode, ApiError> {
+    let organization_id = tenant.organization_id;
+    load_voter(&state.pool, organization_id, voter_id).await?;
+
+    // Granting a role twice is a no-op rather than a conflict.
+    if !auth::load_roles(&state.pool, organization_id, voter_id).await?.contains(&role) {
+        let event = Event::RoleGranted {
+            organization_id,
+            voter_id,
+            role,
+        };
+        events::record(&state.pool, Some(session.voter_id), &event).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/voters/{voter_id}/roles/{role}",
+    tag = "voters",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("role" = Role, Path, description = "Role to revoke"),
+    ),
+    responses(
+        (status = 204, description = "Role revoked"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The voter does not have the role", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn revoke_role(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path((voter_id, role)): Path<(Uuid, Role)>,
+) -> Result<StatusCode, ApiError> {
+    let event = Event::RoleRevoked {
+        organization_id: tenant.organization_id,
+        voter_id,
+        role,
+    };
+    events::record(&state.pool, Some(session.voter_id), &event)
+        .await
+        .map_err(ApiError::missing(format!("Role {role} of voter {voter_id}")))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/groups",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 200, description = "Groups of the voter", body = [String]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_groups(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+
+    Ok(Json(load_groups(&state.pool, tenant.organization_id, voter_id).await?))
+}
+
+/// Adds the voter to a group of the organization, see `/groups`.
+#[utoipa::path(
+    put,
+    path = "/api/v1/voters/{voter_id}/groups/{group}",
+    tag = "voters",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("group" = String, Path, description = "Group name"),
+    ),
+    responses(
+        (status = 204, description = "The voter is in the group"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter or group", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn join_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path((voter_id, group)): Path<(Uuid, String)>,
+) -> Result<StatusCode, ApiError> {
+    let organization_id = tenant.organization_id;
+    load_voter(&state.pool, organization_id, voter_id).await?;
+    super::groups::load(&state.pool, organization_id, &group).await?;
+
+    if !load_groups(&state.pool, organization_id, voter_id).await?.contains(&group) {
+        let event = Event::GroupJoined {
+            organization_id,
+            voter_id,
+            group,
+        };
+        events::record(&state.pool, Some(session.voter_id), &event).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/voters/{voter_id}/groups/{group}",
+    tag = "voters",
+    params(
+        ("voter_id" = Uuid, Path, description = "Voter id"),
+        ("group" = String, Path, description = "Group name"),
+    ),
+    responses(
+        (status = 204, description = "The voter left the group"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The voter is not in the group", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn leave_group(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path((voter_id, group)): Path<(Uuid, String)>,
+) -> Result<StatusCode, ApiError> {
+    let missing = ApiError::missing(format!("Membership of voter {voter_id} in group {group:?}"));
+    let event = Event::GroupLeft {
+        organization_id: tenant.organization_id,
+        voter_id,
+        group,
+    };
+    events::record(&state.pool, Some(session.voter_id), &event)
+        .await
+        .map_err(missing)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voters/{voter_id}/weights",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    responses(
+        (status = 200, description = "Weights given to the voter, oldest first", body = [VotingWeight]),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_weights(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Session,
+    Path(voter_id): Path<Uuid>,
+) -> Result<Json<Vec<VotingWeight>>, ApiError> {
+    session.ensure_can_read(voter_id)?;
+    load_voter(&state.pool, tenant.organization_id, voter_id).await?;
+
+    Ok(Json(weights::history(&state.pool, tenant.organization_id, voter_id).await?))
+}
+
+/// Gives the voter a weight from a day on, today or later. Polls count with
+/// the weights in force on the day they open, recorded with their
+/// electorate, so polls already open do not change.
+#[utoipa::path(
+    post,
+    path = "/api/v1/voters/{voter_id}/weights",
+    tag = "voters",
+    params(("voter_id" = Uuid, Path, description = "Voter id")),
+    request_body = SetWeightPayload,
+    responses(
+        (status = 201, description = "Weight set", body = VotingWeight),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not allowed", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown voter", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Negative or invalid weight, or a day in the past", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn set_weight(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    session: Authorized<ManageVoters>,
+    Path(voter_id): Path<Uuid>,
+    Json(payload): Json<SetWeightPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !payload.weight.is_finite() || payload.weight < 0.0 {
+        return Err(ApiError::Validation(format!(
+            "{} is not a valid weight; weights are positive, or 0",
+            payload.weight
+        )));
+    }
+    let today = OffsetDateTime::now_utc().date();
+    let effective_from = payload.effective_from.unwrap_or(today);
+    if effective_from < today {
+        return Err(ApiError::Validation(format!(
+            "Weights cannot apply from a past day, such as {effective_from}"
+        )));
+    }
+    let organization_id = tenant.organization_id;
+    load_voter(&state.pool, organization_id, voter_id).await?;
+
+    let weight = VotingWeight {
+        organization_id,
+        voter_id,
+        weight: payload.weight,
+        effective_from,
+    };
+    events::record(&state.pool, Some(session.voter_id), &Event::VotingWeightSet(weight.clone())).await?;
+
+    Ok((StatusCode::CREATED, Json(weight)))
+}
+
+async fn load_groups(pool: &Pool, organization_id: Uuid, voter_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query(
+        "SELECT group_name FROM group_members WHERE organization_id = $1 AND voter_id = $2 ORDER BY group_name",
+    )
+    .bind(organization_id.to_string())
+    .bind(voter_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| row.try_get("group_name"))
+    .collect()
+}
+
+/// Loads a member of the organization along with the delegations they gave
+/// and received there; voters who are not members are not found.
+pub(super) async fn load_voter(pool: &Pool, organization_id: Uuid, voter_id: Uuid) -> Result<Voter, ApiError> {
+    if !tenant::is_member(pool, organization_id, voter_id).await? {
+        return Err(ApiError::NotFound(format!("Voter {voter_id} does not exist")));
+    }
+
+    load_account(pool, organization_id, voter_id).await
+}
+
+/// Loads any voter along with the delegations they gave and received in the
+/// organization and their weight there, for the account endpoints, which work in organizations one
+/// is not a member of.
+pub(super) async fn load_account(pool: &Pool, organization_id: Uuid, voter_id: Uuid) -> Result<Voter, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT voter_id, first_name, last_name, email, birth_date, status, email_verified_at
+        FROM voters
+        WHERE voter_id = $1
+        "#,
+    )
+    .bind(voter_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(ApiError::missing(format!("Voter {voter_id}")))?;
+
+    let delegations = super::delegations::load_delegations(pool, organization_id, voter_id).await?;
+    let (delegations_given, delegations_received) = delegations
+        .into_iter()
+        .partition(|delegation: &Delegation| delegation.from_principal == voter_id);
+
+    let today = OffsetDateTime::now_utc().date();
+
+    Ok(Voter {
+        voting_weight: weights::of(pool, organization_id, voter_id, today).await?,
+        delegations_received,
+        delegations_given,
+        ..voter_from_row(&row)?
+    })
+}
+
+/// Builds a voter from a `voters` row, without their delegations and
+/// weighing 1.
+pub(super) fn voter_from_row(row: &AnyRow) -> Result<Voter, sqlx::Error> {
+    Ok(Voter {
+        voter_id: db::parse_column(row, "voter_id")?,
+        first_name: row.try_get("first_name")?,
+        last_name: row.try_get("last_name")?,
+        email: row.try_get("email")?,
+        birth_date: db::date_column(row, "birth_date")?,
+        status: db::parse_column(row, "status")?,
+        email_verified_at: db::optional_timestamp_column(row, "email_verified_at")?,
+        voting_weight: weights::unit(),
+        delegations_received: Vec::new(),
+        delegations_given: Vec::new(),
+    })
+}
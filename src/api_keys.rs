@@ -0,0 +1,165 @@
+//! API keys for machine integrations.
+//!
+//! An admin issues a key with a set of [`Scope`]s; the key then acts on
+//! behalf of that admin, but only for the permissions its scopes cover (see
+//! [`crate::auth::Permission::SCOPE`]). Clients send it in an
+//! `Authorization: Bearer` header, and only in the organization it was issued
+//! in. Like session tokens, keys are stored as SHA-256 digests and shown
+//! once, when issued.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{any::AnyRow, Row};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth;
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+
+/// Prefix of every key, so leaked keys are easy to recognize.
+const KEY_PREFIX: &str = "vsk_";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum Scope {
+    /// List and register voters and manage their roles.
+    #[serde(rename = "voters:write")]
+    VotersWrite,
+    /// Read polls and their questions.
+    #[serde(rename = "polls:read")]
+    PollsRead,
+    /// Read participation and results.
+    #[serde(rename = "results:read")]
+    ResultsRead,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKey {
+    pub key_id: Uuid,
+    /// What the key is for, such as the name of the integration.
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Admin who issued the key, and on whose behalf it acts.
+    pub created_by: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// The key stops working at this time; never when absent.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+/// Stores a new key and returns it with its secret, which is not kept.
+pub async fn issue(
+    pool: &Pool,
+    organization_id: Uuid,
+    created_by: Uuid,
+    name: String,
+    scopes: Vec<Scope>,
+    expires_at: Option<OffsetDateTime>,
+) -> Result<(ApiKey, String), ApiError> {
+    let secret = format!("{KEY_PREFIX}{}", auth::random_token());
+    let key = ApiKey {
+        key_id: Uuid::new_v4(),
+        name,
+        scopes,
+        created_by,
+        created_at: OffsetDateTime::now_utc(),
+        expires_at,
+        last_used_at: None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO api_keys (key_id, organization_id, key_hash, name, scopes, created_by, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(key.key_id.to_string())
+    .bind(organization_id.to_string())
+    .bind(auth::digest(&secret))
+    .bind(&key.name)
+    .bind(serde_json::to_string(&key.scopes).unwrap())
+    .bind(key.created_by.to_string())
+    .bind(db::format_timestamp(key.created_at))
+    .bind(key.expires_at.map(db::format_timestamp))
+    .execute(pool)
+    .await?;
+
+    Ok((key, secret))
+}
+
+pub async fn list(pool: &Pool, organization_id: Uuid) -> Result<Vec<ApiKey>, ApiError> {
+    sqlx::query(
+        r#"
+        SELECT key_id, name, scopes, created_by, created_at, expires_at, last_used_at
+        FROM api_keys
+        WHERE organization_id = $1
+        ORDER BY created_at, key_id
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| Ok(key_from_row(row)?))
+    .collect()
+}
+
+pub async fn revoke(pool: &Pool, organization_id: Uuid, key_id: Uuid) -> Result<(), ApiError> {
+    let deleted = sqlx::query("DELETE FROM api_keys WHERE key_id = $1 AND organization_id = $2")
+        .bind(key_id.to_string())
+        .bind(organization_id.to_string())
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound(format!("API key {key_id} not found")));
+    }
+
+    Ok(())
+}
+
+/// Looks up the unexpired key `secret` of the organization and records that
+/// it was just used.
+pub async fn authenticate(pool: &Pool, organization_id: Uuid, secret: &str) -> Result<ApiKey, ApiError> {
+    let key_hash = auth::digest(secret);
+    let now = OffsetDateTime::now_utc();
+
+    let row = sqlx::query(
+        r#"
+        SELECT key_id, name, scopes, created_by, created_at, expires_at, last_used_at
+        FROM api_keys
+        WHERE key_hash = $1 AND organization_id = $2 AND (expires_at IS NULL OR expires_at > $3)
+        "#,
+    )
+    .bind(&key_hash)
+    .bind(organization_id.to_string())
+    .bind(db::format_timestamp(now))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::Unauthorized("The API key is invalid, revoked or expired".to_owned()))?;
+
+    sqlx::query("UPDATE api_keys SET last_used_at = $1 WHERE key_hash = $2")
+        .bind(db::format_timestamp(now))
+        .bind(&key_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(ApiKey {
+        last_used_at: Some(now),
+        ..key_from_row(&row)?
+    })
+}
+
+fn key_from_row(row: &AnyRow) -> Result<ApiKey, sqlx::Error> {
+    Ok(ApiKey {
+        key_id: db::parse_column(row, "key_id")?,
+        name: row.try_get("name")?,
+        scopes: db::json_column(row, "scopes")?,
+        created_by: db::parse_column(row, "created_by")?,
+        created_at: db::timestamp_column(row, "created_at")?,
+        expires_at: db::optional_timestamp_column(row, "expires_at")?,
+        last_used_at: db::optional_timestamp_column(row, "last_used_at")?,
+    })
+}
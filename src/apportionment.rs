@@ -0,0 +1,317 @@
+//! Party-list proportional representation.
+//!
+//! Voters choose a list, and the lists share the question's seats in
+//! proportion to their votes. Lists with less than the threshold, a
+//! percentage of the votes cast for any list, win nothing; the others share
+//! the seats by one of three methods:
+//!
+//! - `DHondt`: seats go one by one to the list with the highest quotient,
+//!   votes / (seats won + 1), which slightly favours larger lists;
+//! - `SainteLague`: the same with votes / (2 × seats won + 1), closer to
+//!   exact proportionality;
+//! - `LargestRemainder`: each list wins a seat for every whole Hare quota,
+//!   votes / seats, its votes hold, and the seats left go to the lists with
+//!   the largest remainders.
+//!
+//! A tie for the last seats goes to the list with more votes, then to the
+//! one listed first, and is recorded.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{ApportionmentMethod, VoteOption};
+
+/// Quotients this close are tied; weighted votes are sums of floats.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SeatAllocation {
+    pub seats: u32,
+    pub method: ApportionmentMethod,
+    /// Percentage of the votes a list needs to win seats.
+    pub threshold: f64,
+    /// Votes of each list, delegated votes included, in the order of the
+    /// options.
+    pub votes: Vec<f64>,
+    /// Percentage of the votes cast for any list each list won.
+    pub shares: Vec<f64>,
+    /// Whether each list reached the threshold.
+    pub qualified: Vec<bool>,
+    /// With `LargestRemainder`, the votes of the qualified lists divided by
+    /// the seats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<f64>,
+    /// Divisors heading the columns of `quotients`; empty with
+    /// `LargestRemainder`.
+    pub divisors: Vec<f64>,
+    /// The quotient table: for each qualified list, its votes divided by
+    /// each divisor, or with `LargestRemainder` by the quota; empty for the
+    /// lists below the threshold.
+    pub quotients: Vec<Vec<f64>>,
+    /// Seats won by each list.
+    pub allocated: Vec<u32>,
+    /// Seats in the order they were given.
+    pub awards: Vec<SeatAward>,
+    pub tie_breaks: Vec<ApportionmentTie>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SeatAward {
+    pub seat: u32,
+    pub list: String,
+    /// The quotient that won the seat; with `LargestRemainder`, the list's
+    /// votes divided by the quota for seats won by whole quotas, and its
+    /// remainder for the others.
+    pub quotient: f64,
+}
+
+/// Lists tied for a seat when fewer seats were left than lists.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ApportionmentTie {
+    pub seat: u32,
+    pub between: Vec<String>,
+    pub winner: String,
+}
+
+/// Shares `seats` between the lists of `options`, which won `votes`.
+pub fn allocate(
+    options: &[VoteOption],
+    votes: Vec<f64>,
+    seats: u32,
+    method: ApportionmentMethod,
+    threshold: f64,
+) -> SeatAllocation {
+    let total = votes.iter().sum::<f64>();
+    let shares = votes
+        .iter()
+        .map(|votes| if total > 0.0 { 100.0 * votes / total } else { 0.0 })
+        .collect::<Vec<_>>();
+    let qualified = votes
+        .iter()
+        .zip(&shares)
+        .map(|(&votes, &share)| votes > 0.0 && share >= threshold)
+        .collect::<Vec<_>>();
+
+    let mut allocation = SeatAllocation {
+        seats,
+        method,
+        threshold,
+        allocated: vec![0; votes.len()],
+        votes,
+        shares,
+        qualified,
+        quota: None,
+        divisors: Vec::new(),
+        quotients: Vec::new(),
+        awards: Vec::new(),
+        tie_breaks: Vec::new(),
+    };
+    match method {
+        ApportionmentMethod::DHondt | ApportionmentMethod::SainteLague => allocation.by_divisors(options),
+        ApportionmentMethod::LargestRemainder => allocation.by_remainders(options),
+    }
+    allocation
+}
+
+impl SeatAllocation {
+    fn by_divisors(&mut self, options: &[VoteOption]) {
+        self.divisors = (0..self.seats)
+            .map(|won| match self.method {
+                ApportionmentMethod::SainteLague => (2 * won + 1) as f64,
+                _ => (won + 1) as f64,
+            })
+            .collect();
+        self.quotients = self
+            .votes
+            .iter()
+            .zip(&self.qualified)
+            .map(|(votes, &qualified)| {
+                if qualified {
+                    self.divisors.iter().map(|divisor| votes / divisor).collect()
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        let lists = (0..self.votes.len()).filter(|&list| self.qualified[list]).collect::<Vec<_>>();
+        for seat in 1..=self.seats {
+            // No list can have won every seat yet, so each has a next quotient.
+            let next = lists
+                .iter()
+                .map(|&list| (list, self.quotients[list][self.allocated[list] as usize]))
+                .collect::<Vec<_>>();
+            let Some(list) = self.award(options, seat, &next) else {
+                break;
+            };
+            self.allocated[list] += 1;
+        }
+    }
+
+    fn by_remainders(&mut self, options: &[VoteOption]) {
+        let total = (0..self.votes.len())
+            .filter(|&list| self.qualified[list])
+            .map(|list| self.votes[list])
+            .sum::<f64>();
+        if total <= 0.0 || self.seats == 0 {
+            return;
+        }
+        let quota = total / self.seats as f64;
+        self.quota = Some(quota);
+        self.quotients = self
+            .votes
+            .iter()
+            .zip(&self.qualified)
+            .map(|(votes, &qualified)| if qualified { vec![votes / quota] } else { Vec::new() })
+            .collect();
+
+        let mut seat = 0;
+        let mut remainders = Vec::new();
+        for (list, quotients) in self.quotients.iter().enumerate() {
+            let Some(&quotient) = quotients.first() else {
+                continue;
+            };
+            let whole = (quotient + TOLERANCE).floor();
+            for _ in 0..whole as u32 {
+                if seat == self.seats {
+                    break;
+                }
+                seat += 1;
+                self.allocated[list] += 1;
+                self.awards.push(SeatAward {
+                    seat,
+                    list: options[list].vote.clone(),
+                    quotient,
+                });
+            }
+            remainders.push((list, (quotient - whole).max(0.0)));
+        }
+
+        while seat < self.seats {
+            seat += 1;
+            let Some(list) = self.award(options, seat, &remainders) else {
+                break;
+            };
+            self.allocated[list] += 1;
+            remainders.retain(|&(other, _)| other != list);
+        }
+    }
+
+    /// Gives `seat` to the list with the highest of the `candidates`
+    /// quotients, settling ties.
+    fn award(&mut self, options: &[VoteOption], seat: u32, candidates: &[(usize, f64)]) -> Option<usize> {
+        let highest = candidates.iter().map(|&(_, quotient)| quotient).reduce(f64::max)?;
+        let tied = candidates
+            .iter()
+            .filter(|&&(_, quotient)| highest - quotient <= TOLERANCE * highest.max(1.0))
+            .map(|&(list, _)| list)
+            .collect::<Vec<_>>();
+        let list = tied
+            .iter()
+            .copied()
+            .min_by(|&a, &b| self.votes[b].total_cmp(&self.votes[a]).then(a.cmp(&b)))?;
+
+        // Ties only matter when some of the tied lists go without.
+        let left = self.seats - seat + 1;
+        if tied.len() as u32 > left {
+            self.tie_breaks.push(ApportionmentTie {
+                seat,
+                between: tied.iter().map(|&list| options[list].vote.clone()).collect(),
+                winner: options[list].vote.clone(),
+            });
+        }
+        self.awards.push(SeatAward {
+            seat,
+            list: options[list].vote.clone(),
+            quotient: highest,
+        });
+        Some(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vote_options;
+
+    /// The example of the Wikipedia articles on D'Hondt and Sainte-Laguë:
+    /// 7 seats and 230,000 votes.
+    fn seven_seats(method: ApportionmentMethod, threshold: f64) -> SeatAllocation {
+        let lists = vote_options(&["A", "B", "C", "D"]);
+        allocate(&lists, vec![100_000.0, 80_000.0, 30_000.0, 20_000.0], 7, method, threshold)
+    }
+
+    fn awarded(allocation: &SeatAllocation) -> Vec<&str> {
+        allocation.awards.iter().map(|award| award.list.as_str()).collect()
+    }
+
+    #[test]
+    fn dhondt_favours_the_larger_lists() {
+        let allocation = seven_seats(ApportionmentMethod::DHondt, 0.0);
+
+        assert_eq!(allocation.allocated, [3, 3, 1, 0]);
+        assert_eq!(awarded(&allocation), ["A", "B", "A", "B", "A", "C", "B"]);
+        assert_eq!(allocation.divisors, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(allocation.quotients[3][..2], [20_000.0, 10_000.0]);
+        assert!(allocation.tie_breaks.is_empty());
+    }
+
+    #[test]
+    fn sainte_lague_is_closer_to_proportional() {
+        let allocation = seven_seats(ApportionmentMethod::SainteLague, 0.0);
+
+        assert_eq!(allocation.allocated, [3, 2, 1, 1]);
+        assert_eq!(awarded(&allocation), ["A", "B", "A", "C", "B", "A", "D"]);
+        assert_eq!(allocation.divisors[..4], [1.0, 3.0, 5.0, 7.0]);
+    }
+
+    #[test]
+    fn largest_remainders_fill_the_seats_whole_quotas_leave() {
+        let allocation = seven_seats(ApportionmentMethod::LargestRemainder, 0.0);
+
+        assert_eq!(allocation.allocated, [3, 2, 1, 1]);
+        assert_eq!(allocation.quota, Some(230_000.0 / 7.0));
+        // Five seats by whole quotas, then C's remainder of 0.913 and D's
+        // of 0.609 beat B's 0.435.
+        assert_eq!(awarded(&allocation), ["A", "A", "A", "B", "B", "C", "D"]);
+        assert!((allocation.awards[6].quotient - 0.6087).abs() < 1e-4);
+        assert!(allocation.divisors.is_empty());
+    }
+
+    #[test]
+    fn lists_below_the_threshold_win_nothing() {
+        let allocation = seven_seats(ApportionmentMethod::SainteLague, 10.0);
+
+        assert_eq!(allocation.qualified, [true, true, true, false]);
+        assert!(allocation.quotients[3].is_empty());
+        assert_eq!(allocation.allocated, [3, 3, 1, 0]);
+
+        // The quota only counts the qualified lists' votes.
+        let allocation = seven_seats(ApportionmentMethod::LargestRemainder, 10.0);
+        assert_eq!(allocation.quota, Some(210_000.0 / 7.0));
+        assert_eq!(allocation.allocated, [3, 3, 1, 0]);
+    }
+
+    #[test]
+    fn ties_go_to_more_votes_then_to_the_list_listed_first() {
+        let lists = vote_options(&["A", "B", "C"]);
+        let allocation = allocate(&lists, vec![60.0, 30.0, 30.0], 3, ApportionmentMethod::DHondt, 0.0);
+
+        assert_eq!(allocation.allocated, [2, 1, 0]);
+        let ties = allocation
+            .tie_breaks
+            .iter()
+            .map(|tie| (tie.seat, tie.between.join(" "), tie.winner.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(ties, [(2, "A B C".to_owned(), "A"), (3, "B C".to_owned(), "B")]);
+
+        // A tie that leaves no list without is not one worth recording.
+        let allocation = allocate(&lists, vec![60.0, 30.0, 30.0], 4, ApportionmentMethod::DHondt, 0.0);
+        assert_eq!(allocation.allocated, [2, 1, 1]);
+        assert!(allocation.tie_breaks.is_empty());
+
+        let allocation = allocate(&lists[..2], vec![50.0, 50.0], 1, ApportionmentMethod::LargestRemainder, 0.0);
+        assert_eq!(allocation.allocated, [1, 0]);
+        assert_eq!(allocation.tie_breaks[0].winner, "A");
+    }
+}
@@ -0,0 +1,660 @@
+//! Password accounts and server-side sessions.
+//!
+//! Passwords are hashed with Argon2id. A session is a random token handed to
+//! the browser in an `HttpOnly`, `Secure`, `SameSite=Lax` cookie; only its
+//! SHA-256 digest is stored, so a copy of the `sessions` table cannot be
+//! replayed. Neither credentials nor sessions go through the event log,
+//! which is readable by auditors.
+//!
+//! What a session may do depends on the [`Role`]s its voter holds in the
+//! organization the request is for (see [`crate::tenant`]): handlers
+//! declare it by extracting [`Authorized<P>`] for one of the permissions
+//! below, and check per-resource rules (acting for oneself, editing one's own
+//! polls) with the [`Session`] methods. High-stakes permissions also ask
+//! voters enrolled in TOTP for a fresh code in the `X-Second-Factor` header.
+//!
+//! [`Authorized`] also accepts an API key in an `Authorization: Bearer`
+//! header, for the permissions one of its scopes covers; the key then acts
+//! as the admin who issued it.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::AnyConnection;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::api_keys::{self, Scope};
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+use crate::events::{self, Event};
+use crate::tenant::{self, Tenant};
+use crate::totp::{self, Enrollment};
+use crate::{AppState, Poll, Role};
+
+const SESSION_COOKIE: &str = "session";
+const SECOND_FACTOR_HEADER: &str = "x-second-factor";
+const SESSION_LIFETIME: Duration = Duration::days(30);
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+
+pub async fn hash_password(password: String) -> Result<String, ApiError> {
+    // Argon2 is deliberately slow; keep it off the async workers.
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| ApiError::Internal(e.to_string()))
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?
+}
+
+pub async fn verify_password(password: String, hash: String) -> Result<bool, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash).map_err(|e| ApiError::Internal(e.to_string()))?;
+        Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok())
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?
+}
+
+/// Stores the password hash of `voter_id`, replacing any previous one.
+pub async fn set_password_hash(
+    conn: &mut AnyConnection,
+    voter_id: Uuid,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM credentials WHERE voter_id = $1")
+        .bind(voter_id.to_string())
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("INSERT INTO credentials (voter_id, password_hash) VALUES ($1, $2)")
+        .bind(voter_id.to_string())
+        .bind(password_hash)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Opens a session for `voter_id` and returns the `Set-Cookie` value
+/// carrying its token.
+pub async fn start_session(pool: &Pool, voter_id: Uuid) -> Result<String, sqlx::Error> {
+    let token = random_token();
+
+    let now = OffsetDateTime::now_utc();
+    sqlx::query(
+        r#"
+        INSERT INTO sessions (token_hash, voter_id, created_at, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(digest(&token))
+    .bind(voter_id.to_string())
+    .bind(db::format_timestamp(now))
+    .bind(db::format_timestamp(now + SESSION_LIFETIME))
+    .execute(pool)
+    .await?;
+
+    Ok(session_cookie(&token, SESSION_LIFETIME.whole_seconds()))
+}
+
+/// The `Set-Cookie` value that makes the browser forget its session.
+pub fn expired_session_cookie() -> String {
+    session_cookie("", 0)
+}
+
+fn session_cookie(token: &str, max_age: i64) -> String {
+    format!("{SESSION_COOKIE}={token}; Path=/; Max-Age={max_age}; HttpOnly; Secure; SameSite=Lax")
+}
+
+/// 256 random bits, base64url-encoded.
+pub fn random_token() -> String {
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+/// SHA-256 digest under which tokens are stored.
+pub fn digest(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+/// What a token sent by email lets its bearer do, once.
+#[derive(Clone, Copy)]
+pub enum EmailToken {
+    VerifyEmail,
+    LogIn,
+}
+
+impl EmailToken {
+    fn purpose(self) -> &'static str {
+        match self {
+            EmailToken::VerifyEmail => "VerifyEmail",
+            EmailToken::LogIn => "LogIn",
+        }
+    }
+
+    fn lifetime(self) -> Duration {
+        match self {
+            EmailToken::VerifyEmail => Duration::days(2),
+            EmailToken::LogIn => Duration::minutes(15),
+        }
+    }
+}
+
+pub async fn issue_email_token(
+    pool: &Pool,
+    voter_id: Uuid,
+    kind: EmailToken,
+) -> Result<String, sqlx::Error> {
+    let token = random_token();
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_tokens (token_hash, voter_id, purpose, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(digest(&token))
+    .bind(voter_id.to_string())
+    .bind(kind.purpose())
+    .bind(db::format_timestamp(OffsetDateTime::now_utc() + kind.lifetime()))
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Consumes `token` and returns the voter it was issued to.
+pub async fn redeem_email_token(
+    pool: &Pool,
+    token: &str,
+    kind: EmailToken,
+) -> Result<Uuid, ApiError> {
+    let invalid = || ApiError::Unauthorized("This link is invalid or has expired".to_owned());
+    let token_hash = digest(token);
+
+    let row = sqlx::query(
+        r#"
+        SELECT voter_id
+        FROM email_tokens
+        WHERE token_hash = $1 AND purpose = $2 AND expires_at > $3
+        "#,
+    )
+    .bind(&token_hash)
+    .bind(kind.purpose())
+    .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(invalid)?;
+
+    // Whoever deletes the token first gets to use it.
+    let deleted = sqlx::query("DELETE FROM email_tokens WHERE token_hash = $1")
+        .bind(&token_hash)
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(invalid());
+    }
+
+    Ok(db::parse_column(&row, "voter_id")?)
+}
+
+/// Value of the cookie called `name`, if the request carries it.
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find_map(|(cookie, value)| (cookie == name).then_some(value))
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// The voter a request acts for, read from its session cookie.
+///
+/// Handlers that change state take this extractor (or [`Authorized`]) and use
+/// `voter_id` as the actor instead of any id found in the request; requests
+/// without a valid session are rejected with a 401. Sessions work in every
+/// organization, but `roles` are those held in the tenant of the request,
+/// none for voters who are not members.
+pub struct Session {
+    pub voter_id: Uuid,
+    pub organization_id: Uuid,
+    pub roles: Vec<Role>,
+    pub email_verified: bool,
+    /// Whether the organization makes TOTP mandatory.
+    totp_required: bool,
+    token_hash: String,
+}
+
+impl Session {
+    pub fn has(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+
+    /// Fails unless the session belongs to `voter_id`.
+    pub fn ensure_is(&self, voter_id: Uuid) -> Result<(), ApiError> {
+        if self.voter_id != voter_id {
+            return Err(ApiError::Forbidden(format!(
+                "You cannot act on behalf of voter {voter_id}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Voters see their own records; admins and auditors see everyone's.
+    pub fn ensure_can_read(&self, voter_id: Uuid) -> Result<(), ApiError> {
+        if self.voter_id == voter_id || self.has(Role::Admin) || self.has(Role::Auditor) {
+            return Ok(());
+        }
+        Err(ApiError::Forbidden(format!("You cannot see the records of voter {voter_id}")))
+    }
+
+    /// Only the creator of a poll and admins may change it.
+    pub fn ensure_can_edit(&self, poll: &Poll) -> Result<(), ApiError> {
+        if self.voter_id == poll.creator_user_id || self.has(Role::Admin) {
+            return Ok(());
+        }
+        Err(ApiError::Forbidden(format!("Only the creator of poll {} may change it", poll.poll_id)))
+    }
+
+    /// Step-up check for high-stakes actions: a voter enrolled in TOTP must
+    /// send a current code, or one of their recovery codes, in the
+    /// `X-Second-Factor` header. When `REQUIRE_TOTP` is set or the
+    /// organization requires it, voters who have not enrolled are turned away.
+    pub async fn verify_second_factor(
+        &self,
+        state: &AppState,
+        headers: &HeaderMap,
+    ) -> Result<(), ApiError> {
+        let enrollment = totp::load(&state.pool, self.voter_id)
+            .await?
+            .filter(Enrollment::confirmed);
+        let Some(enrollment) = enrollment else {
+            if self.totp_mandatory(state) {
+                return Err(ApiError::Forbidden(
+                    "Set up two-factor authentication first".to_owned(),
+                ));
+            }
+            return Ok(());
+        };
+
+        let code = headers
+            .get(SECOND_FACTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                ApiError::SecondFactorRequired(
+                    "Send a code from your authenticator app in the X-Second-Factor header".to_owned(),
+                )
+            })?;
+
+        if totp::check_code(&state.pool, self.voter_id, &enrollment, code).await?
+            || totp::use_recovery_code(&state.pool, self.voter_id, code).await?
+        {
+            Ok(())
+        } else {
+            Err(ApiError::SecondFactorRequired(
+                "The code is wrong, expired or already used".to_owned(),
+            ))
+        }
+    }
+
+    /// Whether the server or the organization makes TOTP mandatory.
+    pub fn totp_mandatory(&self, state: &AppState) -> bool {
+        state.require_totp || self.totp_required
+    }
+
+    pub async fn end(self, pool: &Pool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+            .bind(self.token_hash)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Session {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(token) = cookie(&parts.headers, SESSION_COOKIE) else {
+            if bearer_token(&parts.headers).is_some() {
+                return Err(ApiError::Forbidden("API keys cannot be used for this action".to_owned()));
+            }
+            return Err(ApiError::Unauthorized("Log in to perform this action".to_owned()));
+        };
+        let token_hash = digest(token);
+        let tenant = Tenant::from_request_parts(parts, state).await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT sessions.voter_id, voters.email_verified_at
+            FROM sessions
+            JOIN voters ON voters.voter_id = sessions.voter_id
+            WHERE sessions.token_hash = $1 AND sessions.expires_at > $2
+            "#,
+        )
+        .bind(&token_hash)
+        .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("The session has expired, log in again".to_owned()))?;
+        let voter_id = db::parse_column(&row, "voter_id")?;
+
+        Ok(Session {
+            voter_id,
+            organization_id: tenant.organization_id,
+            roles: load_roles(&state.pool, tenant.organization_id, voter_id).await?,
+            email_verified: db::optional_timestamp_column(&row, "email_verified_at")?.is_some(),
+            totp_required: tenant.settings.require_totp,
+            token_hash,
+        })
+    }
+}
+
+/// Something a request may need to be allowed to do.
+pub trait Permission {
+    /// Completes "You are not allowed to ...".
+    const ACTION: &'static str;
+    /// Whether the voter must have confirmed their email address, so that
+    /// mistyped or duplicate addresses cannot take part.
+    const NEEDS_VERIFIED_EMAIL: bool = false;
+    /// Whether the action needs a second factor on top of the session, see
+    /// [`Session::verify_second_factor`].
+    const NEEDS_SECOND_FACTOR: bool = false;
+    /// Scope an API key needs for the action; keys cannot do it at all when
+    /// `None`.
+    const SCOPE: Option<Scope> = None;
+
+    fn allows(session: &Session) -> bool;
+}
+
+/// Any voter except auditors, who observe polls without taking part.
+pub struct CastBallots;
+
+impl Permission for CastBallots {
+    const ACTION: &'static str = "cast ballots";
+    const NEEDS_VERIFIED_EMAIL: bool = true;
+    const NEEDS_SECOND_FACTOR: bool = true;
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Voter) && !session.has(Role::Auditor)
+    }
+}
+
+pub struct Delegate;
+
+impl Permission for Delegate {
+    const ACTION: &'static str = "delegate your vote";
+    const NEEDS_VERIFIED_EMAIL: bool = true;
+    const NEEDS_SECOND_FACTOR: bool = true;
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Voter)
+    }
+}
+
+pub struct CreatePolls;
+
+impl Permission for CreatePolls {
+    const ACTION: &'static str = "create polls";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::PollCreator) || session.has(Role::Admin)
+    }
+}
+
+pub struct BrowseVoters;
+
+impl Permission for BrowseVoters {
+    const ACTION: &'static str = "browse voters";
+    const SCOPE: Option<Scope> = Some(Scope::VotersWrite);
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin) || session.has(Role::Auditor)
+    }
+}
+
+pub struct ReadAuditLog;
+
+impl Permission for ReadAuditLog {
+    const ACTION: &'static str = "read the event log";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Auditor) || session.has(Role::Admin)
+    }
+}
+
+pub struct ManageVoters;
+
+impl Permission for ManageVoters {
+    const ACTION: &'static str = "manage voters and their roles";
+    const SCOPE: Option<Scope> = Some(Scope::VotersWrite);
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin)
+    }
+}
+
+pub struct ManageApiKeys;
+
+impl Permission for ManageApiKeys {
+    const ACTION: &'static str = "manage API keys";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin)
+    }
+}
+
+pub struct ManageOrganization;
+
+impl Permission for ManageOrganization {
+    const ACTION: &'static str = "change the settings of the organization";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin)
+    }
+}
+
+pub struct ManageTopics;
+
+impl Permission for ManageTopics {
+    const ACTION: &'static str = "manage topics";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin)
+    }
+}
+
+/// Admins of the default organization run the server for everyone else.
+pub struct CreateOrganizations;
+
+impl Permission for CreateOrganizations {
+    const ACTION: &'static str = "create organizations";
+
+    fn allows(session: &Session) -> bool {
+        session.has(Role::Admin) && session.organization_id == tenant::DEFAULT_ORGANIZATION
+    }
+}
+
+/// Polls are public; only API keys need the scope, see [`Public`].
+pub struct ReadPolls;
+
+impl Permission for ReadPolls {
+    const ACTION: &'static str = "read polls";
+    const SCOPE: Option<Scope> = Some(Scope::PollsRead);
+
+    fn allows(_session: &Session) -> bool {
+        true
+    }
+}
+
+/// Results are public; only API keys need the scope, see [`Public`].
+pub struct ReadResults;
+
+impl Permission for ReadResults {
+    const ACTION: &'static str = "read results";
+    const SCOPE: Option<Scope> = Some(Scope::ResultsRead);
+
+    fn allows(_session: &Session) -> bool {
+        true
+    }
+}
+
+/// A [`Session`] whose voter holds permission `P`, or an API key whose scopes
+/// cover it; others get a 403.
+pub struct Authorized<P> {
+    session: Session,
+    permission: PhantomData<fn() -> P>,
+}
+
+impl<P> Deref for Authorized<P> {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.session
+    }
+}
+
+#[async_trait]
+impl<P: Permission + 'static> FromRequestParts<AppState> for Authorized<P> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let session = match bearer_token(&parts.headers).map(str::to_owned) {
+            Some(secret) => {
+                let tenant = Tenant::from_request_parts(parts, state).await?;
+                key_session::<P>(state, &tenant, &secret).await?
+            }
+            None => Session::from_request_parts(parts, state).await?,
+        };
+        if !P::allows(&session) {
+            return Err(ApiError::Forbidden(format!("You are not allowed to {}", P::ACTION)));
+        }
+        if P::NEEDS_VERIFIED_EMAIL && !session.email_verified {
+            return Err(ApiError::Forbidden(format!(
+                "Confirm your email address before you {}",
+                P::ACTION
+            )));
+        }
+        if P::NEEDS_SECOND_FACTOR {
+            session.verify_second_factor(state, &parts.headers).await?;
+        }
+
+        Ok(Authorized {
+            session,
+            permission: PhantomData,
+        })
+    }
+}
+
+/// The session of the admin who issued the API key `secret`, if the key may
+/// act with permission `P`. Keys only work in the organization they were
+/// issued in.
+async fn key_session<P: Permission>(
+    state: &AppState,
+    tenant: &Tenant,
+    secret: &str,
+) -> Result<Session, ApiError> {
+    let key = api_keys::authenticate(&state.pool, tenant.organization_id, secret).await?;
+    if !P::SCOPE.is_some_and(|scope| key.scopes.contains(&scope)) {
+        return Err(ApiError::Forbidden(format!("This API key is not allowed to {}", P::ACTION)));
+    }
+
+    Ok(Session {
+        voter_id: key.created_by,
+        organization_id: tenant.organization_id,
+        roles: load_roles(&state.pool, tenant.organization_id, key.created_by).await?,
+        // Keys have no scope for actions that need these.
+        email_verified: false,
+        totp_required: false,
+        token_hash: digest(secret),
+    })
+}
+
+/// Lets anyone in, but checks the API key of requests that carry one, so
+/// that a revoked, expired or out-of-scope key is refused rather than
+/// ignored, and its use is recorded. Session cookies are not looked at.
+pub struct Public<P>(PhantomData<fn() -> P>);
+
+#[async_trait]
+impl<P: Permission + 'static> FromRequestParts<AppState> for Public<P> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if bearer_token(&parts.headers).is_some() {
+            Authorized::<P>::from_request_parts(parts, state).await?;
+        }
+        Ok(Public(PhantomData))
+    }
+}
+
+/// Roles of `voter_id` in the organization.
+pub async fn load_roles(
+    pool: &Pool,
+    organization_id: Uuid,
+    voter_id: Uuid,
+) -> Result<Vec<Role>, sqlx::Error> {
+    sqlx::query("SELECT role FROM voter_roles WHERE organization_id = $1 AND voter_id = $2 ORDER BY role")
+        .bind(organization_id.to_string())
+        .bind(voter_id.to_string())
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| db::parse_column(row, "role"))
+        .collect()
+}
+
+/// Grants `role` in the organization to the voter registered with `email`,
+/// on behalf of nobody, making them a member first if need be; used from the
+/// command line to appoint the first admin.
+pub async fn grant_role_by_email(
+    pool: &Pool,
+    organization_id: Uuid,
+    email: &str,
+    role: Role,
+) -> Result<(), sqlx::Error> {
+    let row = sqlx::query("SELECT voter_id FROM voters WHERE email = $1")
+        .bind(email)
+        .fetch_one(pool)
+        .await?;
+    let voter_id: Uuid = db::parse_column(&row, "voter_id")?;
+
+    if !tenant::is_member(pool, organization_id, voter_id).await? {
+        events::record(pool, None, &Event::MemberAdded { organization_id, voter_id }).await?;
+    }
+    if !load_roles(pool, organization_id, voter_id).await?.contains(&role) {
+        let event = Event::RoleGranted {
+            organization_id,
+            voter_id,
+            role,
+        };
+        events::record(pool, None, &event).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use sqlx::any::{install_default_drivers, AnyArguments, AnyPoolOptions, AnyRow};
+use sqlx::migrate::Migrator;
+use sqlx::query::Query;
+use sqlx::{Any, Row};
+use time::format_description::well_known::{Iso8601, Rfc3339};
+use time::{Date, OffsetDateTime, UtcOffset};
+
+/// Connection pool shared by every handler, whichever backend it talks to.
+///
+/// Queries go through sqlx's `Any` driver, so they are written once with
+/// `$1`-style placeholders (understood by both SQLite and PostgreSQL) and
+/// only bind strings and integers.
+pub type Pool = sqlx::AnyPool;
+
+static SQLITE_MIGRATIONS: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[cfg(feature = "postgres")]
+static POSTGRES_MIGRATIONS: Migrator = sqlx::migrate!("./migrations/postgres");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    /// Picks the backend from the `DATABASE_URL` scheme.
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        match database_url.split(':').next()? {
+            "sqlite" => Some(Backend::Sqlite),
+            "postgres" | "postgresql" => Some(Backend::Postgres),
+            _ => None,
+        }
+    }
+
+    fn migrator(self) -> Result<&'static Migrator, sqlx::Error> {
+        match self {
+            Backend::Sqlite => Ok(&SQLITE_MIGRATIONS),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => Ok(&POSTGRES_MIGRATIONS),
+            #[cfg(not(feature = "postgres"))]
+            Backend::Postgres => Err(sqlx::Error::Configuration(
+                "DATABASE_URL points to PostgreSQL but vote_server was built without the `postgres` feature".into(),
+            )),
+        }
+    }
+}
+
+/// Opens the pool for `database_url` and brings its schema up to date.
+pub async fn connect(database_url: &str) -> Result<Pool, sqlx::Error> {
+    install_default_drivers();
+
+    let backend = Backend::from_url(database_url).ok_or_else(|| {
+        sqlx::Error::Configuration(format!("unsupported DATABASE_URL: {database_url}").into())
+    })?;
+    let migrator = backend.migrator()?;
+
+    let pool = AnyPoolOptions::new().connect(database_url).await?;
+    migrator.run(&pool).await?;
+
+    Ok(pool)
+}
+
+/// SQL assembled from a variable set of clauses.
+///
+/// sqlx's own `QueryBuilder` emits `?` placeholders for the `Any` driver,
+/// which PostgreSQL rejects, so this numbers them `$1`, `$2`, ... instead.
+/// Only text is bound; numbers such as limits are formatted into the SQL.
+#[derive(Default)]
+pub struct SqlBuilder {
+    sql: String,
+    binds: Vec<String>,
+}
+
+impl SqlBuilder {
+    pub fn new(sql: &str) -> Self {
+        SqlBuilder {
+            sql: sql.to_owned(),
+            binds: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    pub fn push_bind(&mut self, value: impl Into<String>) -> &mut Self {
+        self.binds.push(value.into());
+        write!(self.sql, "${}", self.binds.len()).unwrap();
+        self
+    }
+
+    /// Appends `(value1, value2, ...)` for use with `IN`.
+    pub fn push_bind_list<I, T>(&mut self, values: I) -> &mut Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.push("(");
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            self.push_bind(value);
+        }
+        self.push(")")
+    }
+
+    pub fn query(&self) -> Query<'_, Any, AnyArguments<'_>> {
+        self.binds
+            .iter()
+            .fold(sqlx::query(&self.sql), |query, value| query.bind(value.as_str()))
+    }
+}
+
+/// Reads a column holding a value stored as text, such as a UUID or an enum.
+pub fn parse_column<T>(row: &AnyRow, column: &str) -> Result<T, sqlx::Error>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let text: String = row.try_get(column)?;
+    text.parse().map_err(|e: T::Err| sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(e),
+    })
+}
+
+/// Reads a nullable column holding a value stored as text.
+pub fn optional_parse_column<T>(row: &AnyRow, column: &str) -> Result<Option<T>, sqlx::Error>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let text: Option<String> = row.try_get(column)?;
+    text.map(|text| {
+        text.parse().map_err(|e: T::Err| sqlx::Error::ColumnDecode {
+            index: column.to_owned(),
+            source: Box::new(e),
+        })
+    })
+    .transpose()
+}
+
+/// Reads a column holding a JSON document.
+pub fn json_column<T: DeserializeOwned>(row: &AnyRow, column: &str) -> Result<T, sqlx::Error> {
+    let text: String = row.try_get(column)?;
+    serde_json::from_str(&text).map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(e),
+    })
+}
+
+/// Timestamps are stored as UTC RFC 3339 text, to the second, so that they
+/// all have the same length and compare as strings on every backend.
+pub fn format_timestamp(at: OffsetDateTime) -> String {
+    at.to_offset(UtcOffset::UTC)
+        .replace_nanosecond(0)
+        .expect("0 is a valid nanosecond")
+        .format(&Rfc3339)
+        .expect("Failed to format timestamp")
+}
+
+pub fn timestamp_column(row: &AnyRow, column: &str) -> Result<OffsetDateTime, sqlx::Error> {
+    let text: String = row.try_get(column)?;
+    parse_timestamp(&text, column)
+}
+
+/// Reads a nullable column written by [`format_timestamp`].
+pub fn optional_timestamp_column(
+    row: &AnyRow,
+    column: &str,
+) -> Result<Option<OffsetDateTime>, sqlx::Error> {
+    let text: Option<String> = row.try_get(column)?;
+    text.map(|text| parse_timestamp(&text, column)).transpose()
+}
+
+fn parse_timestamp(text: &str, column: &str) -> Result<OffsetDateTime, sqlx::Error> {
+    OffsetDateTime::parse(text, &Rfc3339).map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(e),
+    })
+}
+
+/// Dates are stored as `YYYY-MM-DD`, which is how `time::Date` displays.
+pub fn date_column(row: &AnyRow, column: &str) -> Result<Date, sqlx::Error> {
+    let text: String = row.try_get(column)?;
+    Date::parse(&text, &Iso8601::DATE).map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(e),
+    })
+}
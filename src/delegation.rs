@@ -0,0 +1,149 @@
+//! Which delegations carry a voter's say on a question.
+//!
+//! A delegation covers the topics it names and every topic nested under them.
+//! For each topic of a question, the delegation naming the topic closest to
+//! it wins: a delegation on Nuclear beats one on Energy for a question about
+//! Nuclear. Between delegations naming the same topic, the one ending last
+//! wins.
+//!
+//! A question about several topics may lead to different delegates, one per
+//! topic. The organization's [`ConflictPolicy`] then says whether the
+//! delegate for the first topic votes, each delegate carries an equal share,
+//! or the voter abstains.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::any::AnyRow;
+use time::UtcOffset;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db::{self, Pool};
+use crate::taxonomy;
+use crate::{ConflictPolicy, Delegation, Poll, PollQuestion};
+
+/// The delegations that carry one voter's say on a question.
+#[derive(Serialize, Clone, ToSchema)]
+pub struct AppliedDelegation {
+    pub question_id: Uuid,
+    /// Empty when no delegation covers the question's topics, or when the
+    /// voter abstains to settle a conflict.
+    pub shares: Vec<DelegationShare>,
+    /// Whether the question's topics lead to different delegates.
+    pub conflict: bool,
+    /// How the conflict was settled, if there was one.
+    pub settled_by: Option<ConflictPolicy>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+pub struct DelegationShare {
+    pub delegation: Delegation,
+    /// Topic of the question the delegation applies to.
+    pub topic: Uuid,
+    /// Topic the delegation names: `topic` itself, or the nearest broader
+    /// topic.
+    pub via_topic: Uuid,
+    /// Part of the voter's say the delegate carries, 1 unless split.
+    pub weight: f64,
+}
+
+/// Picks, among `delegations` given by one voter, those that apply to
+/// `question`. `parents` is the topic hierarchy, see [`taxonomy::parents`].
+pub fn resolve(
+    parents: &HashMap<Uuid, Option<Uuid>>,
+    delegations: &[Delegation],
+    question: &PollQuestion,
+    policy: ConflictPolicy,
+) -> AppliedDelegation {
+    // The most specific delegation for each topic, in the question's order,
+    // keeping the first topic leading to each delegate.
+    let mut found: Vec<(Uuid, &Delegation, Uuid)> = Vec::new();
+    for &topic in &question.question_topics {
+        let Some((delegation, via_topic)) = most_specific(parents, delegations, topic) else {
+            continue;
+        };
+        if !found.iter().any(|(_, other, _)| other.to_delegate == delegation.to_delegate) {
+            found.push((topic, delegation, via_topic));
+        }
+    }
+
+    let conflict = found.len() > 1;
+    let found = match policy {
+        _ if !conflict => found,
+        ConflictPolicy::Priority => found.into_iter().take(1).collect(),
+        ConflictPolicy::Split => found,
+        ConflictPolicy::Abstain => Vec::new(),
+    };
+    let weight = 1.0 / found.len().max(1) as f64;
+
+    AppliedDelegation {
+        question_id: question.question_id,
+        shares: found
+            .into_iter()
+            .map(|(topic, delegation, via_topic)| DelegationShare {
+                delegation: delegation.clone(),
+                topic,
+                via_topic,
+                weight,
+            })
+            .collect(),
+        conflict,
+        settled_by: conflict.then_some(policy),
+    }
+}
+
+/// The delegation naming `topic` or its nearest ancestor, and that topic.
+fn most_specific<'a>(
+    parents: &HashMap<Uuid, Option<Uuid>>,
+    delegations: &'a [Delegation],
+    topic: Uuid,
+) -> Option<(&'a Delegation, Uuid)> {
+    taxonomy::ancestry(parents, topic).into_iter().find_map(|topic_id| {
+        delegations
+            .iter()
+            .filter(|delegation| delegation.topics.contains(&topic_id))
+            .max_by_key(|delegation| (delegation.end_date, delegation.delegation_id))
+            .map(|delegation| (delegation, topic_id))
+    })
+}
+
+/// Delegations given in the organization of `poll` that are still in force
+/// on the day it opens, by principal; only those of `principal` if given.
+pub async fn in_force(
+    pool: &Pool,
+    poll: &Poll,
+    principal: Option<Uuid>,
+) -> Result<HashMap<Uuid, Vec<Delegation>>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT delegation_id, organization_id, from_principal, to_delegate, topics, end_date
+        FROM delegations
+        WHERE organization_id = $1 AND end_date >= $2 AND ($3 IS NULL OR from_principal = $3)
+        "#,
+    )
+    .bind(poll.organization_id.to_string())
+    .bind(poll.poll_opening_time.to_offset(UtcOffset::UTC).date().to_string())
+    .bind(principal.map(|id| id.to_string()))
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_principal: HashMap<Uuid, Vec<Delegation>> = HashMap::new();
+    for row in &rows {
+        let delegation = delegation_from_row(row)?;
+        by_principal.entry(delegation.from_principal).or_default().push(delegation);
+    }
+
+    Ok(by_principal)
+}
+
+pub fn delegation_from_row(row: &AnyRow) -> Result<Delegation, sqlx::Error> {
+    Ok(Delegation {
+        delegation_id: db::parse_column(row, "delegation_id")?,
+        organization_id: db::parse_column(row, "organization_id")?,
+        from_principal: db::parse_column(row, "from_principal")?,
+        to_delegate: db::parse_column(row, "to_delegate")?,
+        topics: db::json_column(row, "topics")?,
+        end_date: db::date_column(row, "end_date")?,
+    })
+}
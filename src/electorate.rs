@@ -0,0 +1,229 @@
+//! Poll electorates.
+//!
+//! The [`Eligibility`] rules of a poll are evaluated once, when it opens, and
+//! the voters they admit are recorded in the log as
+//! [`Event::ElectorateMaterialized`], along with the weight each has that
+//! day. Who may vote and how much their ballot weighs then no longer change
+//! with birthdays, group changes, new registrations or new weights, and
+//! turnout has an exact denominator. [`run`] materializes electorates as polls open;
+//! [`ensure`] does it on demand, for a ballot cast right at the opening.
+
+use std::collections::HashMap;
+use std::time::Duration as StdDuration;
+
+use sqlx::Row;
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+use uuid::Uuid;
+
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+use crate::events::{self, Event};
+use crate::weights;
+use crate::{Eligibility, Poll};
+
+/// How often [`run`] looks for polls that just opened.
+const TICK: StdDuration = StdDuration::from_secs(30);
+
+/// What the rules look at in a voter.
+struct Candidate {
+    voter_id: Uuid,
+    birth_date: Date,
+    email_verified: bool,
+    registered_at: Option<OffsetDateTime>,
+    groups: Vec<String>,
+}
+
+impl Eligibility {
+    fn admits(&self, candidate: &Candidate, opening: OffsetDateTime) -> bool {
+        if self.exclude.contains(&candidate.voter_id) {
+            return false;
+        }
+        if self.include.contains(&candidate.voter_id) {
+            return true;
+        }
+
+        self.min_age
+            .is_none_or(|min_age| age_on(candidate.birth_date, opening.date()) >= i32::from(min_age))
+            && (!self.verified_email || candidate.email_verified)
+            && self.min_account_age_days.is_none_or(|days| {
+                candidate
+                    .registered_at
+                    .is_some_and(|registered_at| registered_at + Duration::days(days.into()) <= opening)
+            })
+            && (self.groups.is_empty() || self.groups.iter().any(|group| candidate.groups.contains(group)))
+    }
+}
+
+/// Age in whole years on `day` of someone born on `birth_date`.
+fn age_on(birth_date: Date, day: Date) -> i32 {
+    let had_birthday = (day.month() as u8, day.day()) >= (birth_date.month() as u8, birth_date.day());
+    day.year() - birth_date.year() - i32::from(!had_birthday)
+}
+
+/// Materializes the electorate of every poll that has opened, forever.
+pub async fn run(pool: Pool) {
+    let mut ticks = tokio::time::interval(TICK);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = materialize_due(&pool).await {
+            eprintln!("Failed to materialize electorates: {e:?}");
+        }
+    }
+}
+
+async fn materialize_due(pool: &Pool) -> Result<(), ApiError> {
+    let due = sqlx::query(
+        r#"
+        SELECT poll_id, organization_id, poll_opening_time, eligibility
+        FROM polls
+        WHERE electorate_size IS NULL AND poll_opening_time <= $1
+        "#,
+    )
+    .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+    .fetch_all(pool)
+    .await?;
+
+    for row in &due {
+        materialize(
+            pool,
+            db::parse_column(row, "poll_id")?,
+            db::parse_column(row, "organization_id")?,
+            db::timestamp_column(row, "poll_opening_time")?,
+            &db::json_column::<Eligibility>(row, "eligibility")?,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Materializes the electorate of `poll` if it has opened and nobody did yet.
+pub async fn ensure(pool: &Pool, poll: &Poll) -> Result<(), ApiError> {
+    if OffsetDateTime::now_utc() < poll.poll_opening_time || size(pool, poll.poll_id).await?.is_some() {
+        return Ok(());
+    }
+
+    materialize(
+        pool,
+        poll.poll_id,
+        poll.organization_id,
+        poll.poll_opening_time,
+        &poll.eligibility,
+    )
+    .await
+}
+
+async fn materialize(
+    pool: &Pool,
+    poll_id: Uuid,
+    organization_id: Uuid,
+    opening: OffsetDateTime,
+    eligibility: &Eligibility,
+) -> Result<(), ApiError> {
+    let voters = candidates(pool, organization_id)
+        .await?
+        .iter()
+        .filter(|candidate| eligibility.admits(candidate, opening))
+        .map(|candidate| candidate.voter_id)
+        .collect::<Vec<_>>();
+    let in_force = weights::on(pool, organization_id, opening.to_offset(UtcOffset::UTC).date()).await?;
+    let weights = voters
+        .iter()
+        .map(|voter_id| in_force.get(voter_id).copied().unwrap_or_else(weights::unit))
+        .collect();
+
+    let event = Event::ElectorateMaterialized {
+        poll_id,
+        voters,
+        weights,
+    };
+    match events::record(pool, None, &event).await {
+        Ok(()) => Ok(()),
+        // Another request got there first.
+        Err(_) if size(pool, poll_id).await?.is_some() => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Members who may vote at all: those with the `Voter` role in the
+/// organization, except its auditors.
+async fn candidates(pool: &Pool, organization_id: Uuid) -> Result<Vec<Candidate>, sqlx::Error> {
+    let mut groups: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for row in sqlx::query("SELECT group_name, voter_id FROM group_members WHERE organization_id = $1")
+        .bind(organization_id.to_string())
+        .fetch_all(pool)
+        .await?
+    {
+        groups
+            .entry(db::parse_column(&row, "voter_id")?)
+            .or_default()
+            .push(row.try_get("group_name")?);
+    }
+
+    // Voters are registered when their VoterCreated event is logged.
+    sqlx::query(
+        r#"
+        SELECT voters.voter_id, voters.birth_date, voters.email_verified_at,
+               (SELECT MIN(events.occurred_at)
+                FROM events
+                WHERE events.event_type = 'VoterCreated' AND events.voter_id = voters.voter_id) AS registered_at
+        FROM voters
+        JOIN voter_roles ON voter_roles.voter_id = voters.voter_id
+                        AND voter_roles.organization_id = $1
+                        AND voter_roles.role = 'Voter'
+        WHERE NOT EXISTS (
+            SELECT 1 FROM voter_roles AS auditors
+            WHERE auditors.voter_id = voters.voter_id
+              AND auditors.organization_id = $1
+              AND auditors.role = 'Auditor'
+        )
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        let voter_id = db::parse_column(row, "voter_id")?;
+        Ok(Candidate {
+            voter_id,
+            birth_date: db::date_column(row, "birth_date")?,
+            email_verified: db::optional_timestamp_column(row, "email_verified_at")?.is_some(),
+            registered_at: db::optional_timestamp_column(row, "registered_at")?,
+            groups: groups.remove(&voter_id).unwrap_or_default(),
+        })
+    })
+    .collect()
+}
+
+/// Number of voters in the electorate, once materialized.
+pub async fn size(pool: &Pool, poll_id: Uuid) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query("SELECT electorate_size FROM polls WHERE poll_id = $1")
+        .bind(poll_id.to_string())
+        .fetch_one(pool)
+        .await?
+        .try_get("electorate_size")
+}
+
+pub async fn includes(pool: &Pool, poll_id: Uuid, voter_id: Uuid) -> Result<bool, sqlx::Error> {
+    Ok(sqlx::query("SELECT 1 FROM electorates WHERE poll_id = $1 AND voter_id = $2")
+        .bind(poll_id.to_string())
+        .bind(voter_id.to_string())
+        .fetch_optional(pool)
+        .await?
+        .is_some())
+}
+
+/// Weight of each voter of the electorate, once materialized.
+pub async fn weights(pool: &Pool, poll_id: Uuid) -> Result<HashMap<Uuid, f64>, sqlx::Error> {
+    let mut weights = HashMap::new();
+    for row in sqlx::query("SELECT voter_id, weight FROM electorates WHERE poll_id = $1")
+        .bind(poll_id.to_string())
+        .fetch_all(pool)
+        .await?
+    {
+        weights.insert(db::parse_column(&row, "voter_id")?, row.try_get("weight")?);
+    }
+
+    Ok(weights)
+}
@@ -0,0 +1,192 @@
+use axum::{
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Error returned by every handler.
+///
+/// It is rendered as an RFC 7807 `application/problem+json` body whose `code`
+/// member is stable and meant for clients to match on.
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    Unauthorized(String),
+    SecondFactorRequired(String),
+    Conflict(String),
+    Validation(String),
+    Forbidden(String),
+    PollClosed(Uuid),
+    Internal(String),
+}
+
+impl ApiError {
+    /// Maps `RowNotFound` to a [`ApiError::NotFound`] naming `resource`, and
+    /// any other error as usual.
+    pub fn missing(resource: String) -> impl FnOnce(sqlx::Error) -> ApiError {
+        move |error| match error {
+            sqlx::Error::RowNotFound => ApiError::NotFound(format!("{resource} does not exist")),
+            error => error.into(),
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::SecondFactorRequired(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::PollClosed(_) => StatusCode::CONFLICT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::SecondFactorRequired(_) => "second_factor_required",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::PollClosed(_) => "poll_closed",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "Resource not found",
+            ApiError::Unauthorized(_) => "Authentication required",
+            ApiError::SecondFactorRequired(_) => "Second factor required",
+            ApiError::Conflict(_) => "Conflicting resource",
+            ApiError::Validation(_) => "Invalid request",
+            ApiError::Forbidden(_) => "Forbidden",
+            ApiError::PollClosed(_) => "Poll is not open",
+            ApiError::Internal(_) => "Internal server error",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            ApiError::NotFound(detail)
+            | ApiError::Unauthorized(detail)
+            | ApiError::SecondFactorRequired(detail)
+            | ApiError::Conflict(detail)
+            | ApiError::Validation(detail)
+            | ApiError::Forbidden(detail) => detail.clone(),
+            ApiError::PollClosed(poll_id) => format!("Poll {poll_id} is not accepting ballots"),
+            // The cause is logged, not leaked to the client.
+            ApiError::Internal(_) => "The server failed to process the request".to_owned(),
+        }
+    }
+}
+
+/// RFC 7807 problem details, as rendered for an [`ApiError`].
+#[derive(Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    #[schema(example = "/problems/not-found")]
+    problem_type: String,
+    title: String,
+    status: u16,
+    detail: String,
+    /// Stable identifier of the error, such as `not_found` or `poll_closed`.
+    #[schema(example = "not_found")]
+    code: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Internal(cause) = &self {
+            eprintln!("Internal error: {cause}");
+        }
+
+        let status = self.status();
+        let problem = Problem {
+            problem_type: format!("/problems/{}", self.code().replace('_', "-")),
+            title: self.title().to_owned(),
+            status: status.as_u16(),
+            detail: self.detail(),
+            code: self.code().to_owned(),
+        };
+
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            serde_json::to_string(&problem).unwrap(),
+        )
+            .into_response()
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::RowNotFound => ApiError::NotFound("No such resource".to_owned()),
+            sqlx::Error::Database(db_error) if db_error.is_unique_violation() => {
+                ApiError::Conflict("A resource with the same unique fields already exists".to_owned())
+            }
+            sqlx::Error::Database(db_error) if db_error.is_foreign_key_violation() => {
+                ApiError::Validation("The request references a resource that does not exist".to_owned())
+            }
+            sqlx::Error::Database(db_error) if db_error.is_check_violation() => {
+                ApiError::Validation(db_error.message().to_owned())
+            }
+            _ => ApiError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(error: serde_json::Error) -> Self {
+        ApiError::Internal(error.to_string())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::Validation(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::Validation(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::Validation(rejection.body_text())
+    }
+}
+
+/// `axum::Json` whose rejection is an [`ApiError`].
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// `axum::extract::Path` whose rejection is an [`ApiError`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct Path<T>(pub T);
+
+/// `axum::extract::Query` whose rejection is an [`ApiError`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct Query<T>(pub T);
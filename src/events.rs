@@ -0,0 +1,959 @@
+//! Append-only log of every state change.
+//!
+//! Handlers never touch the `organizations`, `voters`, `delegations`,
+//! `polls`, `poll_questions`, `vote_history` and `votes` tables directly:
+//! they [`record`] an [`Event`], which is appended to `events` and applied
+//! to those tables in the same transaction. The tables are therefore
+//! projections and can be rebuilt from the log with [`rebuild_projections`].
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{any::AnyRow, AnyConnection, Row};
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::auth::{Authorized, ReadAuditLog};
+use crate::db::{self, Pool};
+use crate::error::Problem;
+use crate::tenant::{self, Tenant};
+use crate::{
+    AppState, Delegation, Group, Organization, Poll, PollQuestion, QuestionResult, RankedChoiceVote,
+    ResultsVisibility, Role, Topic, Voter, VoterStatus, VotingWeight,
+};
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "type", content = "data")]
+pub enum Event {
+    OrganizationCreated(Organization),
+    /// New name or settings; the slug never changes.
+    OrganizationUpdated(Organization),
+    TopicCreated(Topic),
+    /// New name or description, or the topic was archived or restored.
+    TopicUpdated(Topic),
+    VoterCreated(Voter),
+    MemberAdded {
+        organization_id: Uuid,
+        voter_id: Uuid,
+    },
+    /// The voter left the organization, along with their roles, groups and
+    /// delegations there.
+    MemberRemoved {
+        organization_id: Uuid,
+        voter_id: Uuid,
+    },
+    VoterStatusChanged {
+        voter_id: Uuid,
+        status: VoterStatus,
+    },
+    EmailVerified {
+        voter_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        verified_at: OffsetDateTime,
+    },
+    RoleGranted {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        voter_id: Uuid,
+        role: Role,
+    },
+    RoleRevoked {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        voter_id: Uuid,
+        role: Role,
+    },
+    GroupCreated(Group),
+    /// New description; groups are never renamed.
+    GroupUpdated(Group),
+    /// The group was deleted, and its members left it.
+    GroupDeleted {
+        organization_id: Uuid,
+        group: String,
+    },
+    GroupJoined {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        voter_id: Uuid,
+        group: String,
+    },
+    GroupLeft {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        voter_id: Uuid,
+        group: String,
+    },
+    /// The voter weighs this much from `effective_from` on, replacing any
+    /// weight given from the same day.
+    VotingWeightSet(VotingWeight),
+    DelegationAdded(Delegation),
+    DelegationRemoved {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        delegation_id: Uuid,
+        from: Uuid,
+        to: Uuid,
+    },
+    VoteHistoryAdded {
+        #[serde(default = "tenant::default_organization")]
+        organization_id: Uuid,
+        voter_id: Uuid,
+        vote: String,
+        score: u32,
+    },
+    QuestionCreated(PollQuestion),
+    PollCreated(Poll),
+    PollRescheduled {
+        poll_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        poll_opening_time: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        poll_closing_time: OffsetDateTime,
+    },
+    PollDeleted {
+        poll_id: Uuid,
+    },
+    /// The poll was closed ahead of its scheduled closing time.
+    PollClosed {
+        poll_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        closed_at: OffsetDateTime,
+    },
+    /// The poll opened with these voters as its electorate.
+    ElectorateMaterialized {
+        poll_id: Uuid,
+        voters: Vec<Uuid>,
+        /// Weight of each of `voters` on the day the poll opened, in the
+        /// same order. Older logs have none.
+        #[serde(default)]
+        weights: Vec<f64>,
+    },
+    BallotCast(RankedChoiceVote),
+    /// The poll closed and was counted, delegations included; see
+    /// [`crate::tally`].
+    PollTallied {
+        poll_id: Uuid,
+        results: Vec<QuestionResult>,
+    },
+}
+
+impl Event {
+    fn event_type(&self) -> &'static str {
+        match self {
+            Event::OrganizationCreated(_) => "OrganizationCreated",
+            Event::OrganizationUpdated(_) => "OrganizationUpdated",
+            Event::TopicCreated(_) => "TopicCreated",
+            Event::TopicUpdated(_) => "TopicUpdated",
+            Event::VoterCreated(_) => "VoterCreated",
+            Event::MemberAdded { .. } => "MemberAdded",
+            Event::MemberRemoved { .. } => "MemberRemoved",
+            Event::VoterStatusChanged { .. } => "VoterStatusChanged",
+            Event::EmailVerified { .. } => "EmailVerified",
+            Event::RoleGranted { .. } => "RoleGranted",
+            Event::RoleRevoked { .. } => "RoleRevoked",
+            Event::GroupCreated(_) => "GroupCreated",
+            Event::GroupUpdated(_) => "GroupUpdated",
+            Event::GroupDeleted { .. } => "GroupDeleted",
+            Event::GroupJoined { .. } => "GroupJoined",
+            Event::GroupLeft { .. } => "GroupLeft",
+            Event::VotingWeightSet(_) => "VotingWeightSet",
+            Event::DelegationAdded(_) => "DelegationAdded",
+            Event::DelegationRemoved { .. } => "DelegationRemoved",
+            Event::VoteHistoryAdded { .. } => "VoteHistoryAdded",
+            Event::QuestionCreated(_) => "QuestionCreated",
+            Event::PollCreated(_) => "PollCreated",
+            Event::PollRescheduled { .. } => "PollRescheduled",
+            Event::PollDeleted { .. } => "PollDeleted",
+            Event::PollClosed { .. } => "PollClosed",
+            Event::ElectorateMaterialized { .. } => "ElectorateMaterialized",
+            Event::BallotCast(_) => "BallotCast",
+            Event::PollTallied { .. } => "PollTallied",
+        }
+    }
+
+    /// The organization the event happened in, when the payload says so;
+    /// events about a poll belong to the organization of the poll, and those
+    /// about an account alone to none.
+    fn organization_id(&self) -> Option<Uuid> {
+        match self {
+            Event::OrganizationCreated(organization) | Event::OrganizationUpdated(organization) => {
+                Some(organization.organization_id)
+            }
+            Event::TopicCreated(topic) | Event::TopicUpdated(topic) => Some(topic.organization_id),
+            Event::GroupCreated(group) | Event::GroupUpdated(group) => Some(group.organization_id),
+            Event::MemberAdded { organization_id, .. }
+            | Event::MemberRemoved { organization_id, .. }
+            | Event::RoleGranted { organization_id, .. }
+            | Event::RoleRevoked { organization_id, .. }
+            | Event::GroupDeleted { organization_id, .. }
+            | Event::GroupJoined { organization_id, .. }
+            | Event::GroupLeft { organization_id, .. }
+            | Event::DelegationRemoved { organization_id, .. }
+            | Event::VoteHistoryAdded { organization_id, .. } => Some(*organization_id),
+            Event::VotingWeightSet(weight) => Some(weight.organization_id),
+            Event::DelegationAdded(delegation) => Some(delegation.organization_id),
+            Event::PollCreated(poll) => Some(poll.organization_id),
+            _ => None,
+        }
+    }
+
+    fn poll_id(&self) -> Option<Uuid> {
+        match self {
+            Event::QuestionCreated(question) => Some(question.poll_id),
+            Event::PollCreated(poll) => Some(poll.poll_id),
+            Event::PollRescheduled { poll_id, .. }
+            | Event::PollDeleted { poll_id }
+            | Event::PollClosed { poll_id, .. }
+            | Event::ElectorateMaterialized { poll_id, .. }
+            | Event::PollTallied { poll_id, .. } => Some(*poll_id),
+            Event::BallotCast(ballot) => Some(ballot.poll_id),
+            _ => None,
+        }
+    }
+
+    fn voter_id(&self) -> Option<Uuid> {
+        match self {
+            Event::VoterCreated(voter) => Some(voter.voter_id),
+            Event::MemberAdded { voter_id, .. }
+            | Event::MemberRemoved { voter_id, .. }
+            | Event::VoterStatusChanged { voter_id, .. }
+            | Event::EmailVerified { voter_id, .. }
+            | Event::RoleGranted { voter_id, .. }
+            | Event::RoleRevoked { voter_id, .. }
+            | Event::GroupJoined { voter_id, .. }
+            | Event::GroupLeft { voter_id, .. } => Some(*voter_id),
+            Event::VotingWeightSet(weight) => Some(weight.voter_id),
+            Event::DelegationAdded(delegation) => Some(delegation.from_principal),
+            Event::DelegationRemoved { from, .. } => Some(*from),
+            Event::VoteHistoryAdded { voter_id, .. } => Some(*voter_id),
+            Event::PollCreated(poll) => Some(poll.creator_user_id),
+            Event::BallotCast(ballot) => Some(ballot.user_id),
+            Event::OrganizationCreated(_)
+            | Event::OrganizationUpdated(_)
+            | Event::TopicCreated(_)
+            | Event::TopicUpdated(_)
+            | Event::GroupCreated(_)
+            | Event::GroupUpdated(_)
+            | Event::GroupDeleted { .. }
+            | Event::QuestionCreated(_)
+            | Event::PollRescheduled { .. }
+            | Event::PollDeleted { .. }
+            | Event::PollClosed { .. }
+            | Event::ElectorateMaterialized { .. }
+            | Event::PollTallied { .. } => None,
+        }
+    }
+
+    fn delegate_id(&self) -> Option<Uuid> {
+        match self {
+            Event::DelegationAdded(delegation) => Some(delegation.to_delegate),
+            Event::DelegationRemoved { to, .. } => Some(*to),
+            _ => None,
+        }
+    }
+}
+
+/// An event as read back from the log.
+#[derive(Serialize, ToSchema)]
+pub struct RecordedEvent {
+    event_id: i64,
+    occurred_at: String,
+    actor_id: Option<Uuid>,
+    #[serde(flatten)]
+    event: Event,
+}
+
+/// Appends `event` to the log on behalf of `actor_id` and updates the
+/// projections accordingly.
+pub async fn record(pool: &Pool, actor_id: Option<Uuid>, event: &Event) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    record_in(&mut tx, actor_id, event).await?;
+    tx.commit().await
+}
+
+/// Same as [`record`], within a transaction the caller also writes to.
+pub async fn record_in(
+    conn: &mut AnyConnection,
+    actor_id: Option<Uuid>,
+    event: &Event,
+) -> Result<(), sqlx::Error> {
+    append(conn, actor_id, event).await?;
+    apply(conn, event).await
+}
+
+async fn append(
+    conn: &mut AnyConnection,
+    actor_id: Option<Uuid>,
+    event: &Event,
+) -> Result<(), sqlx::Error> {
+    // Appended before being applied, so a deleted poll is still there.
+    sqlx::query(
+        r#"
+        INSERT INTO events (occurred_at, actor_id, event_type, organization_id, poll_id, voter_id, delegate_id, payload)
+        VALUES ($1, $2, $3, COALESCE($4, (SELECT organization_id FROM polls WHERE poll_id = $5)), $5, $6, $7, $8)
+        "#,
+    )
+    .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+    .bind(actor_id.map(|id| id.to_string()))
+    .bind(event.event_type())
+    .bind(event.organization_id().map(|id| id.to_string()))
+    .bind(event.poll_id().map(|id| id.to_string()))
+    .bind(event.voter_id().map(|id| id.to_string()))
+    .bind(event.delegate_id().map(|id| id.to_string()))
+    .bind(serde_json::to_string(event).unwrap())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Applies `event` to the projection tables.
+async fn apply(conn: &mut AnyConnection, event: &Event) -> Result<(), sqlx::Error> {
+    match event {
+        Event::OrganizationCreated(organization) => {
+            sqlx::query("INSERT INTO organizations (organization_id, slug, name, settings) VALUES ($1, $2, $3, $4)")
+                .bind(organization.organization_id.to_string())
+                .bind(&organization.slug)
+                .bind(&organization.name)
+                .bind(serde_json::to_string(&organization.settings).unwrap())
+                .execute(conn)
+                .await?;
+        }
+        Event::OrganizationUpdated(organization) => {
+            let updated = sqlx::query("UPDATE organizations SET name = $1, settings = $2 WHERE organization_id = $3")
+                .bind(&organization.name)
+                .bind(serde_json::to_string(&organization.settings).unwrap())
+                .bind(organization.organization_id.to_string())
+                .execute(conn)
+                .await?;
+
+            if updated.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::TopicCreated(topic) => {
+            sqlx::query(
+                r#"
+                INSERT INTO topics (topic_id, organization_id, parent_id, name, description, archived_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(topic.topic_id.to_string())
+            .bind(topic.organization_id.to_string())
+            .bind(topic.parent_id.map(|id| id.to_string()))
+            .bind(&topic.name)
+            .bind(&topic.description)
+            .bind(topic.archived_at.map(db::format_timestamp))
+            .execute(conn)
+            .await?;
+        }
+        Event::TopicUpdated(topic) => {
+            let updated = sqlx::query(
+                r#"
+                UPDATE topics SET parent_id = $1, name = $2, description = $3, archived_at = $4
+                WHERE topic_id = $5 AND organization_id = $6
+                "#,
+            )
+            .bind(topic.parent_id.map(|id| id.to_string()))
+            .bind(&topic.name)
+            .bind(&topic.description)
+            .bind(topic.archived_at.map(db::format_timestamp))
+            .bind(topic.topic_id.to_string())
+            .bind(topic.organization_id.to_string())
+            .execute(conn)
+            .await?;
+
+            if updated.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::MemberAdded { organization_id, voter_id } => {
+            sqlx::query("INSERT INTO organization_members (organization_id, voter_id) VALUES ($1, $2)")
+                .bind(organization_id.to_string())
+                .bind(voter_id.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::MemberRemoved { organization_id, voter_id } => {
+            let deleted = sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND voter_id = $2")
+                .bind(organization_id.to_string())
+                .bind(voter_id.to_string())
+                .execute(&mut *conn)
+                .await?;
+            if deleted.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+
+            for sql in [
+                "DELETE FROM voter_roles WHERE organization_id = $1 AND voter_id = $2",
+                "DELETE FROM group_members WHERE organization_id = $1 AND voter_id = $2",
+                "DELETE FROM delegations WHERE organization_id = $1 AND (from_principal = $2 OR to_delegate = $2)",
+            ] {
+                sqlx::query(sql)
+                    .bind(organization_id.to_string())
+                    .bind(voter_id.to_string())
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+        Event::VoterCreated(user) => {
+            sqlx::query(
+                r#"
+                INSERT INTO voters (voter_id, first_name, last_name, email, birth_date, status)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(user.voter_id.to_string())
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(&user.email)
+            .bind(user.birth_date.to_string())
+            .bind(user.status.to_string())
+            .execute(conn)
+            .await?;
+        }
+        Event::VoterStatusChanged { voter_id, status } => {
+            let updated = sqlx::query(
+                r#"
+                UPDATE voters
+                SET status = $1
+                WHERE voter_id = $2
+                "#,
+            )
+            .bind(status.to_string())
+            .bind(voter_id.to_string())
+            .execute(conn)
+            .await?;
+
+            if updated.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::EmailVerified { voter_id, verified_at } => {
+            sqlx::query("UPDATE voters SET email_verified_at = $1 WHERE voter_id = $2")
+                .bind(db::format_timestamp(*verified_at))
+                .bind(voter_id.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::RoleGranted {
+            organization_id,
+            voter_id,
+            role,
+        } => {
+            sqlx::query("INSERT INTO voter_roles (organization_id, voter_id, role) VALUES ($1, $2, $3)")
+                .bind(organization_id.to_string())
+                .bind(voter_id.to_string())
+                .bind(role.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::RoleRevoked {
+            organization_id,
+            voter_id,
+            role,
+        } => {
+            let deleted = sqlx::query(
+                "DELETE FROM voter_roles WHERE organization_id = $1 AND voter_id = $2 AND role = $3",
+            )
+            .bind(organization_id.to_string())
+            .bind(voter_id.to_string())
+            .bind(role.to_string())
+            .execute(conn)
+            .await?;
+
+            if deleted.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::GroupCreated(group) => {
+            sqlx::query("INSERT INTO voter_groups (organization_id, name, description) VALUES ($1, $2, $3)")
+                .bind(group.organization_id.to_string())
+                .bind(&group.name)
+                .bind(&group.description)
+                .execute(conn)
+                .await?;
+        }
+        Event::GroupUpdated(group) => {
+            let updated = sqlx::query(
+                "UPDATE voter_groups SET description = $1 WHERE organization_id = $2 AND name = $3",
+            )
+            .bind(&group.description)
+            .bind(group.organization_id.to_string())
+            .bind(&group.name)
+            .execute(conn)
+            .await?;
+
+            if updated.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::GroupDeleted { organization_id, group } => {
+            sqlx::query("DELETE FROM group_members WHERE organization_id = $1 AND group_name = $2")
+                .bind(organization_id.to_string())
+                .bind(group)
+                .execute(&mut *conn)
+                .await?;
+            let deleted = sqlx::query("DELETE FROM voter_groups WHERE organization_id = $1 AND name = $2")
+                .bind(organization_id.to_string())
+                .bind(group)
+                .execute(&mut *conn)
+                .await?;
+
+            if deleted.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::GroupJoined {
+            organization_id,
+            voter_id,
+            group,
+        } => {
+            sqlx::query("INSERT INTO group_members (organization_id, group_name, voter_id) VALUES ($1, $2, $3)")
+                .bind(organization_id.to_string())
+                .bind(group)
+                .bind(voter_id.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::GroupLeft {
+            organization_id,
+            voter_id,
+            group,
+        } => {
+            let deleted = sqlx::query(
+                "DELETE FROM group_members WHERE organization_id = $1 AND group_name = $2 AND voter_id = $3",
+            )
+            .bind(organization_id.to_string())
+            .bind(group)
+            .bind(voter_id.to_string())
+            .execute(conn)
+            .await?;
+
+            if deleted.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::VotingWeightSet(weight) => {
+            sqlx::query(
+                "DELETE FROM voting_weights WHERE organization_id = $1 AND voter_id = $2 AND effective_from = $3",
+            )
+            .bind(weight.organization_id.to_string())
+            .bind(weight.voter_id.to_string())
+            .bind(weight.effective_from.to_string())
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query(
+                r#"
+                INSERT INTO voting_weights (organization_id, voter_id, weight, effective_from)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(weight.organization_id.to_string())
+            .bind(weight.voter_id.to_string())
+            .bind(weight.weight)
+            .bind(weight.effective_from.to_string())
+            .execute(conn)
+            .await?;
+        }
+        Event::DelegationAdded(delegation) => {
+            sqlx::query(
+                r#"
+                INSERT INTO delegations (delegation_id, organization_id, from_principal, to_delegate, topics, end_date)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(delegation.delegation_id.to_string())
+            .bind(delegation.organization_id.to_string())
+            .bind(delegation.from_principal.to_string())
+            .bind(delegation.to_delegate.to_string())
+            .bind(serde_json::to_string(&delegation.topics).unwrap())
+            .bind(delegation.end_date.to_string())
+            .execute(conn)
+            .await?;
+        }
+        Event::DelegationRemoved { delegation_id, .. } => {
+            let deleted = sqlx::query(
+                r#"
+                DELETE FROM delegations
+                WHERE delegation_id = $1
+                "#,
+            )
+            .bind(delegation_id.to_string())
+            .execute(conn)
+            .await?;
+
+            if deleted.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+        Event::VoteHistoryAdded {
+            organization_id,
+            voter_id,
+            vote,
+            score,
+        } => {
+            sqlx::query(
+                r#"
+                INSERT INTO vote_history (organization_id, voter_id, vote, score)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(organization_id.to_string())
+            .bind(voter_id.to_string())
+            .bind(vote)
+            .bind(i64::from(*score))
+            .execute(conn)
+            .await?;
+        }
+        Event::QuestionCreated(question) => insert_question(conn, question).await?,
+        Event::PollCreated(poll) => {
+            sqlx::query(
+                r#"
+                INSERT INTO polls (creator_user_id, poll_id, organization_id, poll_opening_time, poll_closing_time, results_visibility, eligibility, breakdown, credits, questions)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#,
+            )
+            .bind(poll.creator_user_id.to_string())
+            .bind(poll.poll_id.to_string())
+            .bind(poll.organization_id.to_string())
+            .bind(db::format_timestamp(poll.poll_opening_time))
+            .bind(db::format_timestamp(poll.poll_closing_time))
+            .bind(poll.results_visibility.to_string())
+            .bind(serde_json::to_string(&poll.eligibility).unwrap())
+            .bind(serde_json::to_string(&poll.breakdown).unwrap())
+            .bind(poll.credits.map(i64::from))
+            .bind(serde_json::to_string(&poll.questions).unwrap())
+            .execute(&mut *conn)
+            .await?;
+
+            for question in &poll.questions {
+                insert_question(conn, question).await?;
+            }
+        }
+        Event::PollRescheduled {
+            poll_id,
+            poll_opening_time,
+            poll_closing_time,
+        } => {
+            sqlx::query(
+                r#"
+                UPDATE polls
+                SET poll_opening_time = $1, poll_closing_time = $2
+                WHERE poll_id = $3
+                "#,
+            )
+            .bind(db::format_timestamp(*poll_opening_time))
+            .bind(db::format_timestamp(*poll_closing_time))
+            .bind(poll_id.to_string())
+            .execute(conn)
+            .await?;
+        }
+        Event::PollDeleted { poll_id } => {
+            sqlx::query("DELETE FROM electorates WHERE poll_id = $1")
+                .bind(poll_id.to_string())
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("DELETE FROM poll_questions WHERE poll_id = $1")
+                .bind(poll_id.to_string())
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("DELETE FROM polls WHERE poll_id = $1")
+                .bind(poll_id.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::PollClosed { poll_id, closed_at } => {
+            sqlx::query("UPDATE polls SET poll_closing_time = $1 WHERE poll_id = $2")
+                .bind(db::format_timestamp(*closed_at))
+                .bind(poll_id.to_string())
+                .execute(conn)
+                .await?;
+        }
+        Event::ElectorateMaterialized { poll_id, voters, weights } => {
+            // Only once per poll, even if two requests race to open it.
+            let updated = sqlx::query(
+                "UPDATE polls SET electorate_size = $1 WHERE poll_id = $2 AND electorate_size IS NULL",
+            )
+            .bind(voters.len() as i64)
+            .bind(poll_id.to_string())
+            .execute(&mut *conn)
+            .await?;
+            if updated.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+
+            for (index, voter_id) in voters.iter().enumerate() {
+                match weights.get(index) {
+                    Some(weight) => {
+                        sqlx::query("INSERT INTO electorates (poll_id, voter_id, weight) VALUES ($1, $2, $3)")
+                            .bind(poll_id.to_string())
+                            .bind(voter_id.to_string())
+                            .bind(weight)
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    // Older logs did not record weights: take the one in
+                    // force on the opening day, as migration 0026 did.
+                    None => {
+                        sqlx::query(
+                            r#"
+                            INSERT INTO electorates (poll_id, voter_id, weight)
+                            SELECT $1, $2, COALESCE((
+                                SELECT voting_weights.weight
+                                FROM voting_weights
+                                JOIN polls ON polls.organization_id = voting_weights.organization_id
+                                WHERE polls.poll_id = $1
+                                  AND voting_weights.voter_id = $2
+                                  AND voting_weights.effective_from <= substr(polls.poll_opening_time, 1, 10)
+                                ORDER BY voting_weights.effective_from DESC
+                                LIMIT 1
+                            ), 1)
+                            "#,
+                        )
+                        .bind(poll_id.to_string())
+                        .bind(voter_id.to_string())
+                        .execute(&mut *conn)
+                        .await?;
+                    }
+                }
+            }
+        }
+        Event::BallotCast(ballot) => {
+            // Logs from before ballots were unique may repeat a voter; their
+            // first ballot counts.
+            sqlx::query(
+                r#"
+                INSERT INTO votes (poll_id, user_id, vote)
+                SELECT $1, $2, $3
+                WHERE NOT EXISTS (SELECT 1 FROM votes WHERE poll_id = $1 AND user_id = $2)
+                "#,
+            )
+            .bind(ballot.poll_id.to_string())
+            .bind(ballot.user_id.to_string())
+            .bind(serde_json::to_string(&ballot.vote).unwrap())
+            .execute(conn)
+            .await?;
+        }
+        Event::PollTallied { poll_id, results } => {
+            // Once per poll: `poll_results` is unique by poll.
+            sqlx::query(
+                r#"
+                INSERT INTO poll_results (creator_user_id, poll_id, results)
+                SELECT creator_user_id, poll_id, $1 FROM polls WHERE poll_id = $2
+                "#,
+            )
+            .bind(serde_json::to_string(results).unwrap())
+            .bind(poll_id.to_string())
+            .execute(&mut *conn)
+            .await?;
+
+            for result in results {
+                sqlx::query(
+                    r#"
+                    INSERT INTO question_results (poll_id, question_id, vote_results, delegated_results, raw_vote_results, raw_delegated_results, approval_rates, stv, seat_allocation, quadratic, majority_judgment, report, group_results, college_results)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                    "#,
+                )
+                .bind(result.poll_id.to_string())
+                .bind(result.question_id.to_string())
+                .bind(serde_json::to_string(&result.vote_results).unwrap())
+                .bind(serde_json::to_string(&result.delegated_results).unwrap())
+                .bind(serde_json::to_string(&result.raw_vote_results).unwrap())
+                .bind(serde_json::to_string(&result.raw_delegated_results).unwrap())
+                .bind(serde_json::to_string(&result.approval_rates).unwrap())
+                .bind(serde_json::to_string(&result.stv).unwrap())
+                .bind(serde_json::to_string(&result.seat_allocation).unwrap())
+                .bind(serde_json::to_string(&result.quadratic).unwrap())
+                .bind(serde_json::to_string(&result.majority_judgment).unwrap())
+                .bind(serde_json::to_string(&result.report).unwrap())
+                .bind(serde_json::to_string(&result.groups).unwrap())
+                .bind(serde_json::to_string(&result.college_results).unwrap())
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn insert_question(conn: &mut AnyConnection, question: &PollQuestion) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO poll_questions (poll_id, question_id, question_type, question_topics, question_description, vote_options, votes, min_approvals, max_approvals, seats, surplus_transfer, apportionment, threshold, grades)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        "#,
+    )
+    .bind(question.poll_id.to_string())
+    .bind(question.question_id.to_string())
+    .bind(question.question_type.to_string())
+    .bind(serde_json::to_string(&question.question_topics).unwrap())
+    .bind(&question.question_description)
+    .bind(serde_json::to_string(&question.vote_options).unwrap())
+    .bind(serde_json::to_string(&question.votes).unwrap())
+    .bind(question.min_approvals.map(i64::from))
+    .bind(question.max_approvals.map(i64::from))
+    .bind(question.seats.map(i64::from))
+    .bind(question.surplus_transfer.map(|method| method.to_string()))
+    .bind(question.apportionment.map(|method| method.to_string()))
+    .bind(question.threshold)
+    .bind(serde_json::to_string(&question.grades).unwrap())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Projection tables, children first so foreign keys hold while clearing.
+const PROJECTIONS: &[&str] = &[
+    "question_results",
+    "poll_results",
+    "votes",
+    "electorates",
+    "vote_history",
+    "voting_weights",
+    "poll_questions",
+    "delegations",
+    "polls",
+    "voter_roles",
+    "group_members",
+    "organization_members",
+    "voters",
+    "voter_groups",
+    "topics",
+    "organizations",
+];
+
+/// Empties every projection table and replays the whole log into them.
+pub async fn rebuild_projections(pool: &Pool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for table in PROJECTIONS {
+        sqlx::query(&format!("DELETE FROM {table}"))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let rows = sqlx::query("SELECT payload FROM events ORDER BY event_id")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for row in &rows {
+        let event: Event = serde_json::from_str(&row.get::<String, _>("payload"))
+            .map_err(|e| sqlx::Error::Decode(e.into()))?;
+        apply(&mut tx, &event).await?;
+    }
+
+    tx.commit().await?;
+    Ok(rows.len() as u64)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventFilter {
+    poll_id: Option<Uuid>,
+    voter_id: Option<Uuid>,
+    /// Only return events after this `event_id`, to resume a previous read.
+    after: Option<i64>,
+}
+
+/// Streams the log of the organization as newline-delimited JSON, oldest
+/// first. Account events, which belong to no organization, are included for
+/// its members. Ballots cast in a poll with secret results show an empty
+/// `vote` until the poll closes, and organizations never show the client
+/// secret of their identity provider.
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "audit",
+    params(EventFilter),
+    responses(
+        (status = 200, description = "One event per line", body = RecordedEvent, content_type = "application/x-ndjson"),
+        (status = 401, description = "Not logged in", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not an auditor or admin", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn stream_events(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    _session: Authorized<ReadAuditLog>,
+    Query(filter): Query<EventFilter>,
+) -> impl IntoResponse {
+    let (tx, rx) = mpsc::channel::<Result<String, sqlx::Error>>(64);
+
+    tokio::spawn(async move {
+        let mut rows = sqlx::query(
+            r#"
+            SELECT event_id, occurred_at, actor_id, payload,
+                (SELECT results_visibility FROM polls WHERE polls.poll_id = events.poll_id) AS results_visibility,
+                (SELECT poll_closing_time FROM polls WHERE polls.poll_id = events.poll_id) AS poll_closing_time
+            FROM events
+            WHERE ($1 IS NULL OR poll_id = $1)
+              AND ($2 IS NULL OR voter_id = $2 OR delegate_id = $2)
+              AND event_id > $3
+              AND (organization_id = $4
+                   OR (organization_id IS NULL
+                       AND voter_id IN (SELECT voter_id FROM organization_members WHERE organization_id = $4)))
+            ORDER BY event_id
+            "#,
+        )
+        .bind(filter.poll_id.map(|id| id.to_string()))
+        .bind(filter.voter_id.map(|id| id.to_string()))
+        .bind(filter.after.unwrap_or(0))
+        .bind(tenant.organization_id.to_string())
+        .fetch(&state.pool);
+
+        while let Some(row) = rows.next().await {
+            let line = row.and_then(|row| {
+                let mut event = RecordedEvent {
+                    event_id: row.get("event_id"),
+                    occurred_at: row.get("occurred_at"),
+                    actor_id: row
+                        .get::<Option<String>, _>("actor_id")
+                        .and_then(|id| id.parse().ok()),
+                    event: serde_json::from_str(&row.get::<String, _>("payload"))
+                        .map_err(|e| sqlx::Error::Decode(e.into()))?,
+                };
+                match &mut event.event {
+                    Event::BallotCast(ballot) if sealed(&row)? => ballot.vote.clear(),
+                    Event::OrganizationCreated(organization) | Event::OrganizationUpdated(organization) => {
+                        *organization = organization.clone().redacted();
+                    }
+                    _ => {}
+                }
+                Ok(serde_json::to_string(&event).unwrap() + "\n")
+            });
+
+            if tx.send(line).await.is_err() {
+                // The client went away.
+                break;
+            }
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+}
+
+/// Whether the ballots of the poll of an event row are still secret: the
+/// poll keeps its results secret and has not closed, or is gone.
+fn sealed(row: &AnyRow) -> Result<bool, sqlx::Error> {
+    let visibility = db::optional_parse_column::<ResultsVisibility>(row, "results_visibility")?;
+    let closing = db::optional_timestamp_column(row, "poll_closing_time")?;
+
+    Ok(match (visibility, closing) {
+        (Some(ResultsVisibility::Public), _) => false,
+        (Some(ResultsVisibility::Secret), Some(closing)) => OffsetDateTime::now_utc() < closing,
+        _ => true,
+    })
+}
@@ -1,61 +1,213 @@
-use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
-    Router,
-};
-use leptos::*;
+use axum::{http::StatusCode, routing::get};
+use leptos::prelude::*;
 use serde::{Deserialize, Serialize};
-use sqlx::{SqlitePool, Sqlite};
 use dotenv::dotenv;
-use std::{env, fmt::Display};
-use std::net::SocketAddr;
-use tokio::runtime::Runtime;
+use std::env;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+use tower::Layer;
+use utoipa::ToSchema;
 use uuid::Uuid;
-use time::{Date, Time};
+use time::{Date, OffsetDateTime, Time};
 
-#[derive(Serialize, Deserialize, Clone)]
+mod api;
+mod api_keys;
+mod apportionment;
+mod auth;
+mod db;
+mod delegation;
+mod electorate;
+mod error;
+mod events;
+mod mail;
+mod majority_judgment;
+mod oidc;
+mod quadratic;
+mod stv;
+mod tally;
+mod taxonomy;
+mod tenant;
+mod totp;
+mod weights;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 enum PollType {
     Referendum,
     OptionalRankedChoice,
     ForcedRankedChoice,
     QuantifiedAnswers,
+    /// Voters approve any number of options, scoring each 1 or 0; see
+    /// `min_approvals` and `max_approvals`.
+    Approval,
+    /// Voters rank options, 1 for their first preference, to elect `seats`
+    /// of them; see [`stv`].
+    SingleTransferableVote,
+    /// Voters choose one list, scoring it 1, and the lists share `seats` in
+    /// proportion to their votes; see [`apportionment`].
+    PartyList,
+    /// Voters cast votes for or against options, k votes costing k² of the
+    /// poll's `credits`; see [`quadratic`].
+    QuadraticVoting,
+    /// Voters grade every option on the question's scale of `grades`; see
+    /// [`majority_judgment`].
+    MajorityJudgment,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-enum ReferendumOption {
-    Yes,
-    No,
+/// How the surplus of an option elected by single transferable vote moves
+/// on; see [`stv`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, ToSchema)]
+enum SurplusTransfer {
+    #[default]
+    Gregory,
+    Meek,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// How the seats of a party-list question are shared between lists; see
+/// [`apportionment`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, ToSchema)]
+enum ApportionmentMethod {
+    #[default]
+    DHondt,
+    SainteLague,
+    /// Hare quota and largest remainders.
+    LargestRemainder,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 enum VoterStatus {
     Public,
     Private,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-enum Topic {
-    Geopolitics,
-    Defense,
-    Work,
-    Industry,
-    Family,
-    Finances,
-    Education,
-    Research,
-    Judicial,
-    LawEnforcement,
-    Environment,
-    Energy,
-    Medical,
-    Culture,
-    Technology,
-    Sports
-}
-
-// #[derive(Serialize, Deserialize, Clone)]
+/// What delegations and questions are about, defined by each organization;
+/// see [`taxonomy`].
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Topic {
+    topic_id: Uuid,
+    organization_id: Uuid,
+    /// The broader topic this one belongs to, such as Energy for Nuclear.
+    #[serde(default)]
+    parent_id: Option<Uuid>,
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// From then on, new delegations and questions cannot use the topic.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    archived_at: Option<OffsetDateTime>,
+}
+
+/// A named set of members of the organization, such as a committee, a
+/// local chapter or an employee college, known by its name.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Group {
+    organization_id: Uuid,
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// What a voter's account may do, on top of acting for themselves.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+enum Role {
+    /// Manages voters and roles, and may edit any poll.
+    Admin,
+    /// Creates polls and edits their own.
+    PollCreator,
+    /// Casts ballots and manages their own delegations.
+    Voter,
+    /// Reads the event log and voter records; never votes.
+    Auditor,
+}
+
+/// Whether provisional results are published while a poll is open.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, ToSchema)]
+enum ResultsVisibility {
+    /// Only participation is shown until the poll closes.
+    #[default]
+    Secret,
+    /// Running tallies are shown as ballots arrive.
+    Public,
+}
+
+/// Enums are stored in TEXT columns under their variant name.
+macro_rules! text_enum {
+    ($($name:ident),*) => {$(
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(self, f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = serde_json::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                serde_json::from_value(serde_json::Value::String(s.to_owned()))
+            }
+        }
+    )*};
+}
+
+text_enum!(PollType, SurplusTransfer, ApportionmentMethod, VoterStatus, ResultsVisibility, Role);
+
+/// An association, party or assembly using the server, with its own
+/// voters, polls and settings; see [`tenant`].
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Organization {
+    organization_id: Uuid,
+    /// Names the organization in its subdomain or `/o/{slug}` path prefix.
+    slug: String,
+    name: String,
+    #[serde(default)]
+    settings: OrganizationSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+struct OrganizationSettings {
+    /// Whether members must enroll in TOTP before voting or delegating.
+    #[serde(default)]
+    require_totp: bool,
+    /// What happens to a voter who delegated the topics of a question to
+    /// different people.
+    #[serde(default)]
+    delegation_conflicts: ConflictPolicy,
+    /// Identity provider voters may log in through, if any.
+    #[serde(default)]
+    sso: Option<oidc::Settings>,
+}
+
+/// How a question spanning several topics is settled for a voter whose
+/// delegations on those topics go to different delegates.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, ToSchema)]
+enum ConflictPolicy {
+    /// The delegate for the first of the question's topics votes for them.
+    #[default]
+    Priority,
+    /// Each delegate carries an equal share of their vote.
+    Split,
+    /// Their vote is not counted.
+    Abstain,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Delegation {
+    delegation_id: Uuid,
+    /// Delegations only apply within the organization they were given in.
+    #[serde(default = "tenant::default_organization")]
+    organization_id: Uuid,
+    from_principal: Uuid,
+    to_delegate: Uuid,
+    /// Ids of the topics the delegation covers.
+    #[serde(deserialize_with = "taxonomy::deserialize_ids")]
+    topics: Vec<Uuid>,
+    end_date: Date,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 struct Voter {
     voter_id: Uuid,
     first_name: String,
@@ -63,40 +215,293 @@ struct Voter {
     email: String,
     birth_date: Date,
     status: VoterStatus,
+    /// When the voter proved they own `email`; unverified voters cannot vote.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    email_verified_at: Option<OffsetDateTime>,
+    /// Weight of the voter's ballots in the organization today, see
+    /// [`weights`].
+    #[serde(default = "weights::unit")]
+    voting_weight: f64,
+    delegations_received: Vec<Delegation>,
+    delegations_given: Vec<Delegation>,
+}
+
+/// The weight of a member's ballots from a given day on, until the next
+/// change; shares held in a co-op, or the weight of a membership class.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct VotingWeight {
+    organization_id: Uuid,
+    voter_id: Uuid,
+    weight: f64,
+    effective_from: Date,
 }
 
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct VoteHistory {
+    vote: String,
+    score: u32,
+}
 
-async fn create_user(
-    State(state): State<AppState>,
-    Json(payload): Json<CreateUserPayload>,
-) -> impl IntoResponse {
-    let user = Voter {
-        voter_id: Uuid::new_v4(),
-        first_name: payload.first_name,
-        last_name: payload.last_name,
-        email: payload.email,
-        birth_date: payload.birth_date,
-        status: payload.status,
-    };
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct VoteOption {
+    vote: String,
+    vote_description: String,
+}
+
+/// Options named `names`, without descriptions, for tests.
+#[cfg(test)]
+fn vote_options(names: &[&str]) -> Vec<VoteOption> {
+    names
+        .iter()
+        .map(|name| VoteOption {
+            vote: name.to_string(),
+            vote_description: String::new(),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct ScoredVote {
+    /// The question answered. Ballots may leave it out for options no other
+    /// question of the poll has, and it is filled in when they are cast;
+    /// ballots cast before it existed match questions by option alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    question_id: Option<Uuid>,
+    vote: String,
+    vote_description: String,
+    score: u32,
+    /// On `QuadraticVoting` questions, the votes go against the option.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    against: bool,
+}
 
-    // Save user to the database
-    sqlx::query!(
-        r#"
-        INSERT INTO voters (voter_id, first_name, last_name, email, birth_date, status)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-        "#,
-        user.voter_id.to_string(),
-        user.first_name,
-        user.last_name,
-        user.email,
-        user.birth_date.to_string(),
-        user.status.to_string()
-    )
-    .execute(&state.pool)
-    .await
-    .expect("Failed to save user");
-
-    (StatusCode::OK, Json(user))
+impl ScoredVote {
+    /// Whether this answers `option` of the question `question_id`.
+    fn answers(&self, question_id: Uuid, option: &VoteOption) -> bool {
+        self.vote == option.vote && self.question_id.is_none_or(|id| id == question_id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct PollQuestion {
+    poll_id: Uuid,
+    question_id: Uuid,
+    question_type: PollType,
+    /// Ids of the topics the question is about, most important first. Reads
+    /// the single `question_topic` of questions created before.
+    #[serde(alias = "question_topic", deserialize_with = "taxonomy::deserialize_question_topics")]
+    question_topics: Vec<Uuid>,
+    question_description: String,
+    vote_options: Vec<VoteOption>,
+    votes: Vec<ScoredVote>,
+    /// Fewest options an `Approval` ballot may approve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_approvals: Option<u32>,
+    /// Most options an `Approval` ballot may approve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_approvals: Option<u32>,
+    /// Options a `SingleTransferableVote` question elects, or seats the
+    /// lists of a `PartyList` question share.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seats: Option<u32>,
+    /// For `SingleTransferableVote` questions, `Gregory` unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    surplus_transfer: Option<SurplusTransfer>,
+    /// For `PartyList` questions, `DHondt` unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    apportionment: Option<ApportionmentMethod>,
+    /// For `PartyList` questions, the percentage of the votes a list needs
+    /// to win seats; 0 unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threshold: Option<f64>,
+    /// For `MajorityJudgment` questions, the names of the grades, best
+    /// first; a ballot scores an option with the number of grades the one
+    /// it gives is above the last, so that 0 or nothing gives the last.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    grades: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Poll {
+    creator_user_id: Uuid,
+    poll_id: Uuid,
+    #[serde(default = "tenant::default_organization")]
+    organization_id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    poll_opening_time: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    poll_closing_time: OffsetDateTime,
+    #[serde(default)]
+    results_visibility: ResultsVisibility,
+    #[serde(default)]
+    eligibility: Eligibility,
+    #[serde(default)]
+    breakdown: Breakdown,
+    /// Credits each voter may spend on the poll's `QuadraticVoting`
+    /// questions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credits: Option<u32>,
+    questions: Vec<PollQuestion>,
+}
+
+/// How the results of a poll are broken down by group.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+struct Breakdown {
+    /// Groups whose members' votes are also counted apart.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<String>,
+    /// Groups that each count for a fixed share of the result, whatever
+    /// their size; the shares add up to 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    colleges: Vec<College>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+struct College {
+    group: String,
+    /// Share of the result, between 0 and 1.
+    share: f64,
+}
+
+/// Who may vote in a poll, on top of holding the `Voter` role. Every rule set
+/// must hold; `include` admits voters whatever the rules say, and `exclude`
+/// always turns them away.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+struct Eligibility {
+    /// Age the voter must have reached on the day the poll opens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_age: Option<u8>,
+    /// The voter must belong to one of these groups, if any are listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<String>,
+    /// The voter must have confirmed their email address by the opening.
+    #[serde(default)]
+    verified_email: bool,
+    /// Days the voter must have been registered for when the poll opens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_account_age_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct PollResult {
+    creator_user_id: Uuid,
+    poll_id: Uuid,
+    results: Vec<QuestionResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct QuestionResult {
+    poll_id: Uuid,
+    question_id: Uuid,
+    /// Scores ballots gave each option, in the order of the options, each
+    /// times the weight of the voter when the poll opened.
+    vote_results: Vec<f64>,
+    /// Scores added to each option by the ballots of delegates, on behalf of
+    /// voters who did not vote themselves, times the weight of those voters.
+    #[serde(default)]
+    delegated_results: Vec<f64>,
+    /// `vote_results` with every voter weighing 1; empty on results tallied
+    /// before voters had weights.
+    #[serde(default)]
+    raw_vote_results: Vec<u64>,
+    /// `delegated_results` with every voter weighing 1; empty on results
+    /// tallied before voters had weights.
+    #[serde(default)]
+    raw_delegated_results: Vec<f64>,
+    /// For `Approval` questions, the percentage of the weight of the voters
+    /// who took part, delegated votes included, approving each option.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    approval_rates: Vec<f64>,
+    /// For `SingleTransferableVote` questions, the options elected and the
+    /// count round by round.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stv: Option<stv::StvCount>,
+    /// For `PartyList` questions, the seats each list won and the quotient
+    /// table they were shared by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seat_allocation: Option<apportionment::SeatAllocation>,
+    /// For `QuadraticVoting` questions, the net votes of each option and
+    /// how voters spent their credits; `vote_results` and
+    /// `delegated_results` then only count votes for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quadratic: Option<quadratic::QuadraticResult>,
+    /// For `MajorityJudgment` questions, the merit profile and majority
+    /// grade of each option, and the options ranked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    majority_judgment: Option<majority_judgment::MajorityJudgment>,
+    /// How the result was reached; missing on results tallied before.
+    #[serde(default)]
+    report: Option<TallyReport>,
+    /// The votes of the members of each group of the poll's breakdown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupResult>,
+    /// Share of the result each option gets when each college counts for
+    /// its share, for polls with colleges: the sum, over colleges, of the
+    /// college's share times the option's share of the college's votes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    college_results: Option<Vec<f64>>,
+}
+
+/// Votes of the members of a group on a question, their delegated votes
+/// included.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct GroupResult {
+    group: String,
+    /// Members who cast a ballot.
+    ballots: u64,
+    vote_results: Vec<f64>,
+    delegated_results: Vec<f64>,
+    raw_vote_results: Vec<u64>,
+    raw_delegated_results: Vec<f64>,
+}
+
+/// How delegated votes were counted for a question.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+struct TallyReport {
+    /// Voters who cast a ballot.
+    ballots: u64,
+    /// Voters who did not vote but were represented by a delegate who did.
+    represented: u64,
+    /// Total weight of the voters who cast a ballot.
+    #[serde(default)]
+    ballots_weight: f64,
+    /// Total weight of the voters represented by a delegate.
+    #[serde(default)]
+    represented_weight: f64,
+    /// Voters whose delegations on the question's topics went to different
+    /// delegates.
+    conflicts: u64,
+    /// How those conflicts were settled.
+    conflict_policy: ConflictPolicy,
+    /// The above, in a sentence or two.
+    explanation: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct RankedChoiceVote {
+    user_id: Uuid,
+    poll_id: Uuid,
+    #[schema(value_type = String, example = "14:05:00")]
+    vote_time: Time,
+    vote: Vec<ScoredVote>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    pool: db::Pool,
+    live: api::LiveFeed,
+    mailer: Arc<dyn mail::Mailer>,
+    /// Base of the links sent by email, such as `https://vote.example.org`.
+    public_url: String,
+    /// Whether every voter must enroll in TOTP before voting or delegating,
+    /// whatever the settings of their organization.
+    require_totp: bool,
+    /// Domain whose subdomains name organizations, such as `vote.example.org`.
+    tenant_domain: Option<String>,
 }
 
 #[component]
@@ -120,32 +525,76 @@ fn App() -> impl IntoView {
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = SqlitePool::connect(&database_url)
+    let pool = db::connect(&database_url)
         .await
         .expect("Failed to create pool.");
 
-    let state = AppState { pool };
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("rebuild-projections") => {
+            let replayed = events::rebuild_projections(&pool)
+                .await
+                .expect("Failed to rebuild projections");
+            println!("Replayed {replayed} events");
+            return Ok(());
+        }
+        // Bootstraps the first admin, who can then grant roles over the API.
+        Some("grant-role") => {
+            let (Some(email), Some(role)) = (args.get(2), args.get(3)) else {
+                eprintln!("Usage: vote_server grant-role <email> <Admin|PollCreator|Voter|Auditor> [organization]");
+                std::process::exit(2);
+            };
+            let role = role.parse().expect("Unknown role");
+            let organization = match args.get(4) {
+                Some(slug) => tenant::find(&pool, slug).await,
+                None => tenant::load(&pool, tenant::DEFAULT_ORGANIZATION).await,
+            }
+            .expect("Unknown organization");
+            auth::grant_role_by_email(&pool, organization.organization_id, email, role)
+                .await
+                .expect("Failed to grant role");
+            println!("Granted {role} to {email} in {}", organization.slug);
+            return Ok(());
+        }
+        Some("create-organization") => {
+            let (Some(slug), Some(name)) = (args.get(2), args.get(3)) else {
+                eprintln!("Usage: vote_server create-organization <slug> <name>");
+                std::process::exit(2);
+            };
+            let organization = tenant::create(&pool, None, slug.clone(), name.clone())
+                .await
+                .expect("Failed to create the organization");
+            println!("Created organization {} ({})", organization.slug, organization.organization_id);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    if env::var("OIDC_ISSUER").is_ok() {
+        eprintln!("OIDC_ISSUER is ignored: each organization now sets its identity provider in its settings");
+    }
+
+    let public_url = env::var("PUBLIC_URL").unwrap_or_else(|_| "http://localhost:3000".to_owned());
+    let state = AppState {
+        live: api::LiveFeed::start(pool.clone()),
+        pool,
+        mailer: mail::from_env().expect("Invalid MAILER"),
+        public_url,
+        require_totp: env::var("REQUIRE_TOTP").is_ok_and(|value| value == "true" || value == "1"),
+        tenant_domain: env::var("TENANT_DOMAIN").ok(),
+    };
+
+    tokio::spawn(electorate::run(state.pool.clone()));
+    tokio::spawn(tally::run(state.pool.clone()));
 
-    let app = Router::new()
-        .route("/create_user", post(create_user))
-        .route("/", get(|| async {
-            let app = create_app(App);
-            let html = app.render().await;
-            (StatusCode::OK, html)
-        }))
-        .with_state(state);
+    let app = api::router()
+        .route("/", get(|| async { (StatusCode::OK, axum::response::Html(view! { <App/> }.to_html())) }))
+        .with_state(state.clone());
+    // Picks the organization before routing, as it may strip a path prefix.
+    let app = axum::middleware::map_request_with_state(state, tenant::select).layer(app);
 
         let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-        axum::serve(listener, app).await.unwrap();
+        axum::serve(listener, axum::ServiceExt::<axum::extract::Request>::into_make_service(app)).await.unwrap();
 
     Ok(())
 }
-
-// #[derive(Serialize, Deserialize)]
-struct CreateUserPayload {
-    first_name: String,
-    last_name: String,
-    email: String,
-    birth_date: Date,
-    status: VoterStatus,
-}
@@ -0,0 +1,246 @@
+//! Majority judgment.
+//!
+//! Voters grade every option on the question's scale of named grades, such
+//! as Excellent, Very good, Good, Fair, Poor, Reject; a ballot leaving an
+//! option ungraded gives it the lowest grade. The merit profile of an option
+//! is the weight of the ballots giving it each grade, delegated ones
+//! included, so that every option is graded by the same total weight.
+//!
+//! An option's majority grade is the best grade that more than half of that
+//! weight gives it or better: the lower of the two middle grades when the
+//! weight splits evenly. Options rank by majority grade. Options with the
+//! same one are told apart the standard way, as if the ballots giving them
+//! that grade were set aside one by one until their majority grades differ,
+//! which amounts to comparing their grades ever further from the middle,
+//! the lower side first. The first sign of it is the majority gauge: an
+//! option graded above its majority grade by more of the weight than below
+//! ranks before one graded below by more, the more above the better and the
+//! more below the worse. Options whose profiles are the same share a rank.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::VoteOption;
+
+/// Shares of the weight this close are the same; weighted votes are sums of
+/// floats.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct MajorityJudgment {
+    /// The scale, best grade first.
+    pub grades: Vec<String>,
+    /// The merit profile of each option, in the order of the options.
+    pub profiles: Vec<MeritProfile>,
+    /// Options from first to last.
+    pub ranking: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct MeritProfile {
+    pub option: String,
+    /// Weight of the ballots giving the option each grade, best grade first.
+    pub distribution: Vec<f64>,
+    /// `distribution` as percentages of the weight, adding up to 100: the
+    /// segments of a stacked bar, best grade first.
+    pub shares: Vec<f64>,
+    pub majority_grade: String,
+    /// Percentage of the weight grading the option above its majority grade.
+    pub above: f64,
+    /// Percentage of the weight grading the option below its majority grade.
+    pub below: f64,
+    /// 1 for the first option; options with the same profile share a rank.
+    pub rank: u32,
+}
+
+/// Ranks `options` on the scale `grades`, from the weight of the ballots
+/// giving each option each grade, best grade first.
+pub fn count(options: &[VoteOption], grades: &[String], distributions: Vec<Vec<f64>>) -> MajorityJudgment {
+    let cumulatives = distributions.iter().map(|distribution| cumulative(distribution)).collect::<Vec<_>>();
+
+    let mut order = (0..options.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| compare(&cumulatives[a], &cumulatives[b]));
+    let mut ranks = vec![0; options.len()];
+    for (position, &option) in order.iter().enumerate() {
+        let tied = position > 0 && compare(&cumulatives[order[position - 1]], &cumulatives[option]).is_eq();
+        ranks[option] = if tied { ranks[order[position - 1]] } else { position as u32 + 1 };
+    }
+
+    let profiles = options
+        .iter()
+        .zip(distributions)
+        .zip(&cumulatives)
+        .zip(&ranks)
+        .map(|(((option, distribution), cumulative), &rank)| {
+            let total = distribution.iter().sum::<f64>();
+            let shares = distribution
+                .iter()
+                .map(|weight| if total > 0.0 { 100.0 * weight / total } else { 0.0 })
+                .collect::<Vec<_>>();
+            let majority = quantile(cumulative, 0.5);
+            MeritProfile {
+                option: option.vote.clone(),
+                majority_grade: grades[majority].clone(),
+                above: shares[..majority].iter().sum(),
+                below: shares[majority + 1..].iter().sum(),
+                distribution,
+                shares,
+                rank,
+            }
+        })
+        .collect();
+
+    MajorityJudgment {
+        grades: grades.to_vec(),
+        profiles,
+        ranking: order.into_iter().map(|option| options[option].vote.clone()).collect(),
+    }
+}
+
+/// Share of the weight giving each grade or better; without any weight,
+/// every grade is the lowest.
+fn cumulative(distribution: &[f64]) -> Vec<f64> {
+    let total = distribution.iter().sum::<f64>();
+    let mut sum = 0.0;
+    distribution
+        .iter()
+        .enumerate()
+        .map(|(grade, weight)| {
+            sum += weight;
+            if total > 0.0 {
+                sum / total
+            } else if grade + 1 == distribution.len() {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// The grade at `t`, between 0 and 1, of the grades sorted best first.
+fn quantile(cumulative: &[f64], t: f64) -> usize {
+    cumulative
+        .iter()
+        .position(|&share| share > t + TOLERANCE)
+        .unwrap_or(cumulative.len() - 1)
+}
+
+/// Orders two options by their grades, best first: at the middle of their
+/// grades, then ever further from it, the lower side first at each
+/// distance, until their grades differ.
+fn compare(a: &[f64], b: &[f64]) -> Ordering {
+    let mut bounds = a.iter().chain(b).copied().chain([0.0, 0.5, 1.0]).collect::<Vec<_>>();
+    bounds.sort_by(f64::total_cmp);
+    bounds.dedup_by(|later, earlier| *later - *earlier <= TOLERANCE);
+
+    // Both options keep the same grade between two bounds. Those above the
+    // middle hold the lower grades.
+    let mut spans = bounds.windows(2).map(|span| (span[0], span[1])).collect::<Vec<_>>();
+    let distance = |&(start, end): &(f64, f64)| {
+        if start >= 0.5 - TOLERANCE {
+            (start - 0.5, 0)
+        } else {
+            (0.5 - end, 1)
+        }
+    };
+    spans.sort_by(|x, y| {
+        let (x, y) = (distance(x), distance(y));
+        x.0.total_cmp(&y.0).then(x.1.cmp(&y.1))
+    });
+
+    spans
+        .into_iter()
+        .map(|(start, end)| {
+            let t = (start + end) / 2.0;
+            quantile(a, t).cmp(&quantile(b, t))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vote_options;
+
+    fn grades(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// The Tennessee capital example of the Wikipedia article on majority
+    /// judgment, won by Nashville.
+    #[test]
+    fn tennessee_picks_nashville() {
+        let cities = vote_options(&["Memphis", "Nashville", "Chattanooga", "Knoxville"]);
+        let scale = grades(&["Excellent", "Very good", "Good", "Fair", "Poor"]);
+        let judgment = count(
+            &cities,
+            &scale,
+            vec![
+                vec![42.0, 0.0, 0.0, 0.0, 58.0],
+                vec![26.0, 42.0, 32.0, 0.0, 0.0],
+                vec![15.0, 43.0, 0.0, 42.0, 0.0],
+                vec![17.0, 15.0, 26.0, 42.0, 0.0],
+            ],
+        );
+
+        let majority_grades = judgment
+            .profiles
+            .iter()
+            .map(|profile| profile.majority_grade.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(majority_grades, ["Poor", "Very good", "Very good", "Good"]);
+        // Nashville and Chattanooga are both Very good, but more of the
+        // weight grades Chattanooga below it.
+        let gauges = judgment
+            .profiles
+            .iter()
+            .map(|profile| (profile.above.round(), profile.below.round()))
+            .collect::<Vec<_>>();
+        assert_eq!(gauges, [(42.0, 0.0), (26.0, 32.0), (15.0, 42.0), (32.0, 42.0)]);
+        assert_eq!(judgment.ranking, ["Nashville", "Chattanooga", "Knoxville", "Memphis"]);
+        let ranks = judgment.profiles.iter().map(|profile| profile.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, [4, 1, 2, 3]);
+    }
+
+    #[test]
+    fn an_even_split_takes_the_lower_middle_grade() {
+        let judgment = count(
+            &vote_options(&["Parks", "Roads"]),
+            &grades(&["Good", "Fair", "Poor"]),
+            vec![vec![1.0, 0.0, 1.0], vec![0.0, 0.0, 0.0]],
+        );
+
+        assert_eq!(judgment.profiles[0].majority_grade, "Poor");
+        assert_eq!(judgment.profiles[0].above, 50.0);
+        // Ungraded by anyone, an option has the lowest grade.
+        assert_eq!(judgment.profiles[1].majority_grade, "Poor");
+        assert_eq!(judgment.profiles[1].shares, [0.0, 0.0, 0.0]);
+        assert_eq!(judgment.ranking, ["Parks", "Roads"]);
+    }
+
+    #[test]
+    fn ties_are_settled_further_from_the_middle() {
+        let scale = grades(&["A", "B", "C", "D", "E"]);
+        // Both are C, with a third of the weight above and below; set the
+        // C grades aside and Steady's D beats Polarizing's E.
+        let judgment = count(
+            &vote_options(&["Polarizing", "Steady", "Twin"]),
+            &scale,
+            vec![
+                vec![1.0, 0.0, 1.0, 0.0, 1.0],
+                vec![0.0, 1.0, 1.0, 1.0, 0.0],
+                vec![0.0, 2.0, 2.0, 2.0, 0.0],
+            ],
+        );
+
+        assert!(judgment.profiles.iter().all(|profile| profile.majority_grade == "C"));
+        assert_eq!(judgment.ranking, ["Steady", "Twin", "Polarizing"]);
+        // The same profile, twice the weight: the same rank.
+        let ranks = judgment.profiles.iter().map(|profile| profile.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, [3, 1, 1]);
+    }
+}
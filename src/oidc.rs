@@ -0,0 +1,268 @@
+//! OpenID Connect single sign-on with the organization's identity provider.
+//!
+//! Login follows the authorization code flow with PKCE: [`start_login`]
+//! sends the browser to the provider, which sends it back with a code that
+//! [`finish_login`] exchanges for a verified ID token. Each organization
+//! configures its own provider in its settings, see [`Settings`], and
+//! single sign-on is off in those that configure none.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use openidconnect::core::{CoreAuthenticationFlow, CoreClient, CoreProviderMetadata};
+use openidconnect::reqwest::async_http_client;
+use openidconnect::{
+    AuthorizationCode, ClientId, ClientSecret, CsrfToken, IssuerUrl, Nonce, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use time::format_description::well_known::Iso8601;
+use time::{Date, Duration, OffsetDateTime};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth;
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+use crate::Role;
+
+/// Time the voter has to log in at the provider.
+pub const LOGIN_LIFETIME: Duration = Duration::minutes(10);
+
+/// The identity provider of an organization, part of its settings.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct Settings {
+    /// Issuer URL. Its discovery document is fetched at each login, so any
+    /// compliant provider works, including a local mock one.
+    pub issuer: String,
+    pub client_id: String,
+    /// Secret of a confidential client. Responses and the audit log leave
+    /// it out, and an update without one keeps the current one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    /// Whether voters join the organization at their first login there,
+    /// registered from their claims if no voter has their verified address.
+    #[serde(default)]
+    pub auto_provision: bool,
+    /// Claim listing the groups of the voter at the provider.
+    #[serde(default = "default_groups_claim")]
+    pub groups_claim: String,
+    /// Roles granted to members of provider groups, and revoked from the
+    /// others, at each login.
+    #[serde(default)]
+    pub role_groups: Vec<RoleGroup>,
+}
+
+/// Members of `group` at the provider have `role` in the organization.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct RoleGroup {
+    pub group: String,
+    pub role: Role,
+}
+
+fn default_groups_claim() -> String {
+    "groups".to_owned()
+}
+
+/// An organization's provider, ready to log voters in.
+pub struct Config {
+    issuer: IssuerUrl,
+    client_id: ClientId,
+    client_secret: Option<ClientSecret>,
+    redirect_url: RedirectUrl,
+    pub auto_provision: bool,
+    groups_claim: String,
+    role_groups: Vec<RoleGroup>,
+}
+
+impl Config {
+    /// Checks `settings`; the provider sends voters back to `public_url`.
+    pub fn new(settings: &Settings, public_url: &str) -> Result<Self, ApiError> {
+        let issuer = IssuerUrl::new(settings.issuer.clone())
+            .map_err(|e| ApiError::Validation(format!("Invalid issuer {:?}: {e}", settings.issuer)))?;
+        let redirect_url = RedirectUrl::new(format!("{public_url}/api/v1/session/oidc/callback"))
+            .map_err(|e| ApiError::Internal(format!("PUBLIC_URL: {e}")))?;
+
+        Ok(Config {
+            issuer,
+            client_id: ClientId::new(settings.client_id.clone()),
+            client_secret: settings.client_secret.clone().map(ClientSecret::new),
+            redirect_url,
+            auto_provision: settings.auto_provision,
+            groups_claim: settings.groups_claim.clone(),
+            role_groups: settings.role_groups.clone(),
+        })
+    }
+
+    pub fn issuer(&self) -> &str {
+        self.issuer.as_str()
+    }
+
+    /// For each role the group mapping manages, whether `groups` confers it.
+    /// Roles no group maps to are left alone.
+    pub fn roles_from_groups(&self, groups: &[String]) -> Vec<(Role, bool)> {
+        let mut roles: Vec<(Role, bool)> = Vec::new();
+        for RoleGroup { group, role } in &self.role_groups {
+            let member = groups.contains(group);
+            match roles.iter_mut().find(|(managed, _)| managed == role) {
+                Some((_, granted)) => *granted |= member,
+                None => roles.push((*role, member)),
+            }
+        }
+        roles
+    }
+
+    async fn client(&self) -> Result<CoreClient, ApiError> {
+        let metadata = CoreProviderMetadata::discover_async(self.issuer.clone(), async_http_client)
+            .await
+            .map_err(|e| ApiError::Internal(format!("OIDC discovery failed: {e}")))?;
+
+        Ok(CoreClient::from_provider_metadata(
+            metadata,
+            self.client_id.clone(),
+            self.client_secret.clone(),
+        )
+        .set_redirect_uri(self.redirect_url.clone()))
+    }
+}
+
+/// What the identity provider vouches for.
+pub struct Identity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub birth_date: Option<Date>,
+    pub groups: Vec<String>,
+}
+
+/// Remembers a new login attempt to the organization and returns the
+/// provider URL to send the browser to, with the `state` the callback must
+/// bring back.
+pub async fn start_login(
+    pool: &Pool,
+    config: &Config,
+    organization_id: Uuid,
+) -> Result<(String, String), ApiError> {
+    let client = config.client().await?;
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let (url, state, nonce) = client
+        .authorize_url(
+            CoreAuthenticationFlow::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        )
+        .add_scope(Scope::new("email".to_owned()))
+        .add_scope(Scope::new("profile".to_owned()))
+        .set_pkce_challenge(pkce_challenge)
+        .url();
+
+    sqlx::query(
+        r#"
+        INSERT INTO oidc_logins (state_hash, pkce_verifier, nonce, organization_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(auth::digest(state.secret()))
+    .bind(pkce_verifier.secret())
+    .bind(nonce.secret())
+    .bind(organization_id.to_string())
+    .bind(db::format_timestamp(OffsetDateTime::now_utc() + LOGIN_LIFETIME))
+    .execute(pool)
+    .await?;
+
+    Ok((url.to_string(), state.secret().clone()))
+}
+
+/// A login attempt the provider sent the browser back from.
+pub struct Login {
+    /// The organization the login was started in.
+    pub organization_id: Uuid,
+    pkce_verifier: String,
+    nonce: String,
+}
+
+/// Consumes the login attempt `state`, which can only be used once.
+pub async fn take_login(pool: &Pool, state: &str) -> Result<Login, ApiError> {
+    let expired = || ApiError::Unauthorized("Single sign-on failed: the login expired, try again".to_owned());
+    let state_hash = auth::digest(state);
+
+    let row = sqlx::query(
+        r#"
+        SELECT pkce_verifier, nonce, organization_id
+        FROM oidc_logins
+        WHERE state_hash = $1 AND expires_at > $2
+        "#,
+    )
+    .bind(&state_hash)
+    .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(expired)?;
+
+    // Whoever deletes the attempt first gets to use it.
+    let deleted = sqlx::query("DELETE FROM oidc_logins WHERE state_hash = $1")
+        .bind(&state_hash)
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(expired());
+    }
+
+    Ok(Login {
+        organization_id: db::parse_column(&row, "organization_id")?,
+        pkce_verifier: row.try_get("pkce_verifier")?,
+        nonce: row.try_get("nonce")?,
+    })
+}
+
+/// Redeems `code` at the provider of the organization `login` was started
+/// in for the identity of the voter.
+pub async fn finish_login(config: &Config, login: Login, code: String) -> Result<Identity, ApiError> {
+    let failed = |cause: String| ApiError::Unauthorized(format!("Single sign-on failed: {cause}"));
+
+    let client = config.client().await?;
+    let token = client
+        .exchange_code(AuthorizationCode::new(code))
+        .set_pkce_verifier(PkceCodeVerifier::new(login.pkce_verifier))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| failed(e.to_string()))?;
+
+    let id_token = token
+        .id_token()
+        .ok_or_else(|| failed("the provider sent no ID token".to_owned()))?;
+    let claims = id_token
+        .claims(&client.id_token_verifier(), &Nonce::new(login.nonce))
+        .map_err(|e| failed(e.to_string()))?;
+
+    Ok(Identity {
+        subject: claims.subject().as_str().to_owned(),
+        email: claims.email().map(|email| email.as_str().to_owned()),
+        email_verified: claims.email_verified().unwrap_or(false),
+        first_name: claims
+            .given_name()
+            .and_then(|name| name.get(None))
+            .map(|name| name.as_str().to_owned()),
+        last_name: claims
+            .family_name()
+            .and_then(|name| name.get(None))
+            .map(|name| name.as_str().to_owned()),
+        birth_date: claims
+            .birthdate()
+            .and_then(|date| Date::parse(date.as_str(), &Iso8601::DATE).ok()),
+        groups: groups(&id_token.to_string(), &config.groups_claim),
+    })
+}
+
+/// Reads the groups claim, which has no standard name, from the payload of
+/// an ID token whose signature was already checked.
+fn groups(id_token: &str, claim: &str) -> Vec<String> {
+    id_token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|mut payload| serde_json::from_value(payload[claim].take()).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,190 @@
+//! Quadratic voting.
+//!
+//! Each voter has a budget of credits for the whole poll, set on the poll.
+//! Casting k votes on an option, for or against it, costs k² credits, so
+//! voters can weigh in strongly on what matters most to them, but at a
+//! rising price. A ballot spending more than the budget on the poll's
+//! quadratic questions is refused.
+//!
+//! An option's net votes are its votes for less its votes against, each
+//! ballot weighing its voter's weight, delegated ballots included. Results
+//! also give the credits ballots spent on each option and how much of their
+//! budget voters spent on the question.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{PollQuestion, PollType, ScoredVote, VoteOption};
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct QuadraticResult {
+    /// Credits each voter had for the poll.
+    pub credits: u32,
+    /// Votes for each option less votes against it, weighted, in the order
+    /// of the options.
+    pub net_votes: Vec<f64>,
+    /// Weighted votes against each option.
+    pub votes_against: Vec<f64>,
+    /// Credits the ballots cast spent on each option.
+    pub credits_spent: Vec<u64>,
+    /// How many voters spent how many credits on the question, fewest
+    /// credits first.
+    pub spending: Vec<CreditSpending>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct CreditSpending {
+    pub credits: u64,
+    pub voters: u64,
+}
+
+/// Credits `votes` votes on an option cost; the square of a `u32` always
+/// fits in a `u64`.
+pub fn cost(votes: u32) -> u64 {
+    u64::from(votes) * u64::from(votes)
+}
+
+/// Credits `vote` spends on the quadratic questions of `questions`, or
+/// `None` when that overflows, which no budget covers.
+pub fn spent(questions: &[PollQuestion], vote: &[ScoredVote]) -> Option<u64> {
+    questions
+        .iter()
+        .filter(|question| matches!(question.question_type, PollType::QuadraticVoting))
+        .try_fold(0u64, |total, question| {
+            total.checked_add(spent_on(question.question_id, &question.vote_options, vote)?)
+        })
+}
+
+/// Results of question `question_id` from the weighted votes for and
+/// against each option and the ballots cast.
+pub fn count<'a>(
+    question_id: Uuid,
+    options: &[VoteOption],
+    votes_for: Vec<f64>,
+    votes_against: Vec<f64>,
+    ballots: impl IntoIterator<Item = &'a [ScoredVote]>,
+    credits: u32,
+) -> QuadraticResult {
+    // Ballots were held to the budget when cast, so only the sums over
+    // ballots could overflow, with billions of voters.
+    let mut credits_spent = vec![0u64; options.len()];
+    let mut spending = BTreeMap::new();
+    for ballot in ballots {
+        for (spent, option) in credits_spent.iter_mut().zip(options) {
+            *spent = spent.saturating_add(spent_on(question_id, std::slice::from_ref(option), ballot).unwrap_or(u64::MAX));
+        }
+        *spending.entry(spent_on(question_id, options, ballot).unwrap_or(u64::MAX)).or_insert(0) += 1;
+    }
+
+    QuadraticResult {
+        credits,
+        net_votes: votes_for.iter().zip(&votes_against).map(|(votes_for, against)| votes_for - against).collect(),
+        votes_against,
+        credits_spent,
+        spending: spending
+            .into_iter()
+            .map(|(credits, voters)| CreditSpending { credits, voters })
+            .collect(),
+    }
+}
+
+fn spent_on(question_id: Uuid, options: &[VoteOption], vote: &[ScoredVote]) -> Option<u64> {
+    vote.iter()
+        .filter(|vote| options.iter().any(|option| vote.answers(question_id, option)))
+        .try_fold(0u64, |total, vote| total.checked_add(cost(vote.score)))
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn question(question_type: PollType, options: &[&str]) -> PollQuestion {
+        PollQuestion {
+            poll_id: Uuid::nil(),
+            question_id: Uuid::new_v4(),
+            question_type,
+            question_topics: Vec::new(),
+            question_description: String::new(),
+            vote_options: options
+                .iter()
+                .map(|option| VoteOption {
+                    vote: option.to_string(),
+                    vote_description: String::new(),
+                })
+                .collect(),
+            votes: Vec::new(),
+            min_approvals: None,
+            max_approvals: None,
+            seats: None,
+            surplus_transfer: None,
+            apportionment: None,
+            threshold: None,
+            grades: Vec::new(),
+        }
+    }
+
+    fn answer(option: &str, score: u32, against: bool) -> ScoredVote {
+        ScoredVote {
+            question_id: None,
+            vote: option.to_owned(),
+            vote_description: String::new(),
+            score,
+            against,
+        }
+    }
+
+    #[test]
+    fn votes_cost_their_square() {
+        assert_eq!(cost(0), 0);
+        assert_eq!(cost(1), 1);
+        assert_eq!(cost(7), 49);
+        assert_eq!(cost(u32::MAX), 18_446_744_065_119_617_025);
+    }
+
+    #[test]
+    fn ballots_spend_on_quadratic_questions_only() {
+        let questions = [
+            question(PollType::QuadraticVoting, &["Parks", "Roads"]),
+            question(PollType::Referendum, &["Yes", "No"]),
+            question(PollType::QuadraticVoting, &["Library"]),
+        ];
+        let vote = [
+            answer("Parks", 3, false),
+            answer("Roads", 2, true),
+            answer("Yes", 5, false),
+            answer("Library", 1, false),
+        ];
+
+        assert_eq!(spent(&questions, &vote), Some(9 + 4 + 1));
+        assert_eq!(spent(&questions, &[]), Some(0));
+    }
+
+    #[test]
+    fn answers_only_spend_on_their_question() {
+        let questions = [
+            question(PollType::QuadraticVoting, &["Yes", "No"]),
+            question(PollType::QuadraticVoting, &["Yes", "No"]),
+        ];
+        let answer_to = |question: &PollQuestion, score| ScoredVote {
+            question_id: Some(question.question_id),
+            ..answer("Yes", score, false)
+        };
+        let vote = [answer_to(&questions[0], 3), answer_to(&questions[1], 1)];
+
+        assert_eq!(spent(&questions, &vote), Some(9 + 1));
+    }
+
+    #[test]
+    fn spending_past_u64_is_none() {
+        let questions = [question(PollType::QuadraticVoting, &["Parks", "Roads"])];
+        let vote = [answer("Parks", u32::MAX, false), answer("Roads", u32::MAX, true)];
+
+        assert_eq!(spent(&questions, &vote[..1]), Some(cost(u32::MAX)));
+        assert_eq!(spent(&questions, &vote), None);
+    }
+}
@@ -0,0 +1,532 @@
+//! Single transferable vote, for questions electing several options.
+//!
+//! Each ballot ranks options, and is worth its voter's weight, or the part
+//! of a principal's weight a delegate carries. An option is elected once
+//! its votes exceed the Droop quota, votes / (seats + 1). Its surplus then
+//! moves to the next options ranked on its ballots, by one of two rules:
+//!
+//! - `Gregory`: every ballot of the elected option moves on at the same
+//!   transfer value, surplus / votes, and the rest stays behind;
+//! - `Meek`: each elected option keeps a fraction of any ballot reaching it,
+//!   adjusted until it keeps exactly the quota, and passes the rest on; the
+//!   quota shrinks as ballots run out of preferences.
+//!
+//! When nobody reaches the quota, the option with the fewest votes is
+//! excluded and its ballots move on whole. A tie for the fewest votes is
+//! settled by the latest earlier round in which the tied options had
+//! different votes, then by the order of the options: the one listed last
+//! goes. Every round is recorded so that the count can be followed by hand.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{SurplusTransfer, VoteOption};
+
+/// Meek's keep values are adjusted until every elected option is this close
+/// to the quota.
+const TOLERANCE: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 10_000;
+
+/// A ranked ballot: indices of options, first preference first, and what it
+/// is worth.
+pub struct Ballot {
+    pub ranking: Vec<usize>,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct StvCount {
+    pub seats: u32,
+    pub surplus_transfer: SurplusTransfer,
+    /// Options elected, in the order they were.
+    pub elected: Vec<String>,
+    pub rounds: Vec<StvRound>,
+    pub tie_breaks: Vec<TieBreak>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct StvRound {
+    pub round: u32,
+    /// Votes of each option during the round, in the order of the options.
+    pub votes: Vec<f64>,
+    /// Value of the ballots with no option left to go to.
+    pub exhausted: f64,
+    pub quota: f64,
+    /// Where each option stands at the end of the round.
+    pub status: Vec<CandidateStatus>,
+    /// With `Meek`, the fraction of a ballot each option keeps.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keep_values: Vec<f64>,
+    /// Votes moved at the end of the round, with `Gregory`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transfers: Vec<StvTransfer>,
+    /// What happened, in a sentence or two.
+    pub summary: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum CandidateStatus {
+    Hopeful,
+    Elected,
+    Excluded,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct StvTransfer {
+    /// Option the votes left.
+    pub from: String,
+    /// Surplus of an elected option, or all the votes of an excluded one.
+    pub votes: f64,
+    /// Fraction of the value of each of its ballots that moved.
+    pub transfer_value: f64,
+    /// Votes each option received, in the order of the options.
+    pub received: Vec<f64>,
+    /// Votes of ballots with no option left to go to.
+    pub exhausted: f64,
+}
+
+/// Options tied for the fewest votes, and which was excluded.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct TieBreak {
+    pub round: u32,
+    pub between: Vec<String>,
+    pub excluded: String,
+    /// Earlier round whose votes settled the tie; none when the order of the
+    /// options did.
+    pub settled_by_round: Option<u32>,
+}
+
+/// A ballot's part still held by an option, with `Gregory`.
+struct Parcel {
+    ranking: usize,
+    position: usize,
+    value: f64,
+}
+
+/// Elects `seats` of `options` from `ballots`.
+pub fn count(
+    options: &[VoteOption],
+    ballots: &[Ballot],
+    seats: u32,
+    surplus_transfer: SurplusTransfer,
+) -> StvCount {
+    let mut count = Count {
+        options,
+        ballots,
+        seats: seats as usize,
+        status: vec![CandidateStatus::Hopeful; options.len()],
+        elected: Vec::new(),
+        rounds: Vec::new(),
+        tie_breaks: Vec::new(),
+    };
+    match surplus_transfer {
+        SurplusTransfer::Gregory => count.gregory(),
+        SurplusTransfer::Meek => count.meek(),
+    }
+
+    StvCount {
+        seats,
+        surplus_transfer,
+        elected: count.elected.iter().map(|&option| options[option].vote.clone()).collect(),
+        rounds: count.rounds,
+        tie_breaks: count.tie_breaks,
+    }
+}
+
+struct Count<'a> {
+    options: &'a [VoteOption],
+    ballots: &'a [Ballot],
+    seats: usize,
+    status: Vec<CandidateStatus>,
+    elected: Vec<usize>,
+    rounds: Vec<StvRound>,
+    tie_breaks: Vec<TieBreak>,
+}
+
+impl Count<'_> {
+    fn gregory(&mut self) {
+        let mut parcels = Vec::new();
+        let mut exhausted = 0.0;
+        for (ranking, ballot) in self.ballots.iter().enumerate() {
+            match self.next_hopeful(ranking, 0) {
+                Some(position) => parcels.push(Parcel {
+                    ranking,
+                    position,
+                    value: ballot.value,
+                }),
+                None => exhausted += ballot.value,
+            }
+        }
+        let total = parcels.iter().map(|parcel| parcel.value).sum::<f64>();
+        let quota = total / (self.seats + 1) as f64;
+
+        while !self.done() {
+            let mut votes = vec![0.0; self.options.len()];
+            for parcel in &parcels {
+                votes[self.holder(parcel)] += parcel.value;
+            }
+
+            let (summary, transfers) = if let Some(summary) = self.elect_remaining(&votes) {
+                (summary, Vec::new())
+            } else {
+                let reached = self.reaching(&votes, quota);
+                if reached.is_empty() {
+                    let excluded = self.exclude_lowest(&votes);
+                    let transfer = self.move_parcels(&mut parcels, &mut exhausted, excluded, 1.0);
+                    let summary = format!(
+                        "Nobody reached the quota; {} had the fewest votes and was excluded.",
+                        self.options[excluded].vote
+                    );
+                    (summary, vec![transfer])
+                } else {
+                    let mut transfers = Vec::new();
+                    for &option in &reached {
+                        self.elect(option);
+                    }
+                    if !self.done() {
+                        for &option in &reached {
+                            let transfer_value = (votes[option] - quota) / votes[option];
+                            let transfer =
+                                self.move_parcels(&mut parcels, &mut exhausted, option, transfer_value);
+                            transfers.push(transfer);
+                        }
+                    }
+                    let summary = format!("Elected, having exceeded the quota: {}.", self.names(&reached));
+                    (summary, transfers)
+                }
+            };
+
+            let exhausted_before = exhausted - transfers.iter().map(|transfer| transfer.exhausted).sum::<f64>();
+            self.record(votes, exhausted_before, quota, Vec::new(), transfers, summary);
+        }
+    }
+
+    fn meek(&mut self) {
+        let mut keep = vec![1.0; self.options.len()];
+        while !self.done() {
+            let (votes, exhausted, quota) = self.converge(&mut keep);
+
+            let summary = if let Some(summary) = self.elect_remaining(&votes) {
+                summary
+            } else {
+                let reached = self.reaching(&votes, quota);
+                if reached.is_empty() {
+                    let excluded = self.exclude_lowest(&votes);
+                    keep[excluded] = 0.0;
+                    format!(
+                        "Nobody reached the quota; {} had the fewest votes and was excluded.",
+                        self.options[excluded].vote
+                    )
+                } else {
+                    for &option in &reached {
+                        self.elect(option);
+                    }
+                    format!("Elected, having exceeded the quota: {}.", self.names(&reached))
+                }
+            };
+
+            self.record(votes, exhausted, quota, keep.clone(), Vec::new(), summary);
+        }
+    }
+
+    /// Adjusts the keep values of elected options until each keeps the
+    /// quota, and returns the votes, the exhausted value and the quota.
+    fn converge(&self, keep: &mut [f64]) -> (Vec<f64>, f64, f64) {
+        let mut iterations = 0;
+        loop {
+            let mut votes = vec![0.0; self.options.len()];
+            let mut exhausted = 0.0;
+            for ballot in self.ballots {
+                let mut remaining = ballot.value;
+                for &option in &ballot.ranking {
+                    let kept = remaining * keep[option];
+                    votes[option] += kept;
+                    remaining -= kept;
+                }
+                exhausted += remaining;
+            }
+            let total = votes.iter().sum::<f64>();
+            let quota = total / (self.seats + 1) as f64;
+
+            iterations += 1;
+            let converged = self
+                .elected
+                .iter()
+                .all(|&option| (votes[option] - quota).abs() <= TOLERANCE * total.max(1.0));
+            if converged || iterations >= MAX_ITERATIONS {
+                return (votes, exhausted, quota);
+            }
+            for &option in &self.elected {
+                if votes[option] > 0.0 {
+                    keep[option] *= quota / votes[option];
+                }
+            }
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.elected.len() >= self.seats || !self.status.contains(&CandidateStatus::Hopeful)
+    }
+
+    /// Elects every hopeful option, by votes, when there are no more of them
+    /// than seats left.
+    fn elect_remaining(&mut self, votes: &[f64]) -> Option<String> {
+        let mut hopeful = self.hopeful();
+        if hopeful.len() > self.seats - self.elected.len() {
+            return None;
+        }
+        hopeful.sort_by(|&a, &b| votes[b].total_cmp(&votes[a]).then(a.cmp(&b)));
+        for &option in &hopeful {
+            self.elect(option);
+        }
+
+        Some(format!(
+            "Elected, as no more options were left than seats: {}.",
+            self.names(&hopeful)
+        ))
+    }
+
+    /// Hopeful options whose votes exceed `quota`, most votes first.
+    fn reaching(&self, votes: &[f64], quota: f64) -> Vec<usize> {
+        let mut reached = self
+            .hopeful()
+            .into_iter()
+            .filter(|&option| votes[option] > quota)
+            .collect::<Vec<_>>();
+        reached.sort_by(|&a, &b| votes[b].total_cmp(&votes[a]).then(a.cmp(&b)));
+        reached.truncate(self.seats - self.elected.len());
+        reached
+    }
+
+    fn elect(&mut self, option: usize) {
+        self.status[option] = CandidateStatus::Elected;
+        self.elected.push(option);
+    }
+
+    /// Excludes the hopeful option with the fewest votes, breaking ties.
+    fn exclude_lowest(&mut self, votes: &[f64]) -> usize {
+        let hopeful = self.hopeful();
+        let fewest = hopeful.iter().map(|&option| votes[option]).fold(f64::INFINITY, f64::min);
+        let mut tied = hopeful
+            .into_iter()
+            .filter(|&option| votes[option] == fewest)
+            .collect::<Vec<_>>();
+
+        let excluded = if tied.len() == 1 {
+            tied[0]
+        } else {
+            let between = tied
+                .iter()
+                .map(|&option| self.options[option].vote.clone())
+                .collect();
+            let mut settled_by_round = None;
+            for earlier in self.rounds.iter().rev() {
+                let fewest = tied.iter().map(|&option| earlier.votes[option]).fold(f64::INFINITY, f64::min);
+                let still = tied
+                    .iter()
+                    .copied()
+                    .filter(|&option| earlier.votes[option] == fewest)
+                    .collect::<Vec<_>>();
+                if still.len() < tied.len() {
+                    settled_by_round = Some(earlier.round);
+                    tied = still;
+                    if tied.len() == 1 {
+                        break;
+                    }
+                }
+            }
+            if tied.len() > 1 {
+                settled_by_round = None;
+            }
+            let excluded = *tied.iter().max().unwrap();
+            self.tie_breaks.push(TieBreak {
+                round: self.rounds.len() as u32 + 1,
+                between,
+                excluded: self.options[excluded].vote.clone(),
+                settled_by_round,
+            });
+            excluded
+        };
+
+        self.status[excluded] = CandidateStatus::Excluded;
+        excluded
+    }
+
+    /// Moves `transfer_value` of the parcels `from` holds to the next hopeful
+    /// option on their ballots.
+    fn move_parcels(
+        &self,
+        parcels: &mut Vec<Parcel>,
+        exhausted: &mut f64,
+        from: usize,
+        transfer_value: f64,
+    ) -> StvTransfer {
+        let mut transfer = StvTransfer {
+            from: self.options[from].vote.clone(),
+            votes: 0.0,
+            transfer_value,
+            received: vec![0.0; self.options.len()],
+            exhausted: 0.0,
+        };
+
+        let mut moved = Vec::new();
+        for parcel in parcels.iter_mut().filter(|parcel| self.holder(parcel) == from) {
+            let value = parcel.value * transfer_value;
+            parcel.value -= value;
+            transfer.votes += value;
+            match self.next_hopeful(parcel.ranking, parcel.position + 1) {
+                Some(position) => {
+                    transfer.received[self.ballots[parcel.ranking].ranking[position]] += value;
+                    moved.push(Parcel {
+                        ranking: parcel.ranking,
+                        position,
+                        value,
+                    });
+                }
+                None => transfer.exhausted += value,
+            }
+        }
+        parcels.retain(|parcel| parcel.value > 0.0);
+        parcels.extend(moved);
+        *exhausted += transfer.exhausted;
+
+        transfer
+    }
+
+    fn holder(&self, parcel: &Parcel) -> usize {
+        self.ballots[parcel.ranking].ranking[parcel.position]
+    }
+
+    /// Position of the first hopeful option on a ballot, from `position` on.
+    fn next_hopeful(&self, ballot: usize, position: usize) -> Option<usize> {
+        let ranking = &self.ballots[ballot].ranking;
+        (position..ranking.len()).find(|&position| self.status[ranking[position]] == CandidateStatus::Hopeful)
+    }
+
+    fn hopeful(&self) -> Vec<usize> {
+        (0..self.options.len())
+            .filter(|&option| self.status[option] == CandidateStatus::Hopeful)
+            .collect()
+    }
+
+    fn names(&self, options: &[usize]) -> String {
+        options
+            .iter()
+            .map(|&option| self.options[option].vote.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn record(
+        &mut self,
+        votes: Vec<f64>,
+        exhausted: f64,
+        quota: f64,
+        keep_values: Vec<f64>,
+        transfers: Vec<StvTransfer>,
+        summary: String,
+    ) {
+        self.rounds.push(StvRound {
+            round: self.rounds.len() as u32 + 1,
+            votes,
+            exhausted,
+            quota,
+            status: self.status.clone(),
+            keep_values,
+            transfers,
+            summary,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vote_options;
+
+    fn assert_near(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    /// The food election of the Wikipedia article on STV: 20 voters, 3
+    /// seats, won by Chocolate, Orange and Strawberry.
+    fn food_election() -> (Vec<VoteOption>, Vec<Ballot>) {
+        let options = vote_options(&["Orange", "Pear", "Chocolate", "Strawberry", "Sweets"]);
+        let ballots = [
+            (4, vec![0]),
+            (2, vec![1, 0]),
+            (8, vec![2, 3]),
+            (4, vec![2, 4]),
+            (1, vec![3]),
+            (1, vec![4]),
+        ]
+        .into_iter()
+        .flat_map(|(voters, ranking)| {
+            (0..voters).map(move |_| Ballot {
+                ranking: ranking.clone(),
+                value: 1.0,
+            })
+        })
+        .collect();
+        (options, ballots)
+    }
+
+    #[test]
+    fn gregory_elects_the_food_election_winners() {
+        let (options, ballots) = food_election();
+        let count = count(&options, &ballots, 3, SurplusTransfer::Gregory);
+
+        assert_eq!(count.elected, ["Chocolate", "Strawberry", "Orange"]);
+        assert!(count.tie_breaks.is_empty());
+        assert_eq!(count.rounds.len(), 4);
+        assert!(count.rounds.iter().all(|round| round.quota == 5.0));
+
+        // Chocolate's surplus of 7 moves on at 7/12.
+        let first = &count.rounds[0];
+        assert_near(&first.votes, &[4.0, 2.0, 12.0, 1.0, 1.0]);
+        assert_eq!(first.transfers[0].from, "Chocolate");
+        assert_near(&[first.transfers[0].transfer_value], &[7.0 / 12.0]);
+        assert_near(&first.transfers[0].received, &[0.0, 0.0, 0.0, 56.0 / 12.0, 28.0 / 12.0]);
+
+        // Chocolate keeps the quota; Strawberry's surplus has nowhere to go.
+        let second = &count.rounds[1];
+        assert_near(&second.votes, &[4.0, 2.0, 5.0, 1.0 + 56.0 / 12.0, 1.0 + 28.0 / 12.0]);
+        assert_near(&[second.transfers[0].exhausted], &[2.0 / 3.0]);
+
+        let third = &count.rounds[2];
+        assert_eq!(third.status[1], CandidateStatus::Excluded);
+        assert_near(&third.transfers[0].received, &[2.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_near(&count.rounds[3].votes, &[6.0, 0.0, 5.0, 5.0, 1.0 + 28.0 / 12.0]);
+        assert_near(&[count.rounds[3].exhausted], &[2.0 / 3.0]);
+    }
+
+    #[test]
+    fn meek_elects_the_food_election_winners() {
+        let (options, ballots) = food_election();
+        let count = count(&options, &ballots, 3, SurplusTransfer::Meek);
+
+        assert_eq!(count.elected, ["Chocolate", "Strawberry", "Orange"]);
+        assert_eq!(count.rounds.len(), 4);
+
+        // Chocolate keeps 5/12 of each ballot, the quota of 20 / 4.
+        let second = &count.rounds[1];
+        assert_near(&[second.quota], &[5.0]);
+        assert_near(&second.keep_values, &[1.0, 1.0, 5.0 / 12.0, 1.0, 1.0]);
+        assert_near(&second.votes, &[4.0, 2.0, 5.0, 1.0 + 56.0 / 12.0, 1.0 + 28.0 / 12.0]);
+
+        // Once Strawberry keeps part of its ballots too, those no one else
+        // is ranked on exhaust and the quota falls to 33/7, so Chocolate
+        // keeps 11/28 and Strawberry 33/41.
+        let third = &count.rounds[2];
+        assert_near(&[third.quota], &[33.0 / 7.0]);
+        assert_near(&[third.exhausted], &[8.0 / 7.0]);
+        assert_near(&third.keep_values, &[1.0, 0.0, 11.0 / 28.0, 33.0 / 41.0, 1.0]);
+        assert_near(&third.votes, &[4.0, 2.0, 33.0 / 7.0, 33.0 / 7.0, 24.0 / 7.0]);
+        assert_eq!(third.status[1], CandidateStatus::Excluded);
+
+        assert_near(&count.rounds[3].votes, &[6.0, 0.0, 33.0 / 7.0, 33.0 / 7.0, 24.0 / 7.0]);
+    }
+}
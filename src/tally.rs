@@ -0,0 +1,564 @@
+//! Counting polls.
+//!
+//! Each option of a question gets the scores ballots gave it. Voters in the
+//! electorate who did not vote are represented by the delegates their
+//! delegations on the question's topics lead to, see [`crate::delegation`]:
+//! a delegate's ballot counts once more, scaled by the share of the voter's
+//! say they carry. Delegations are followed one step only, so a delegate who
+//! did not vote represents nobody.
+//!
+//! Each ballot counts times the weight its voter had on the day the poll
+//! opened, as its electorate recorded it, see [`crate::electorate`], and a
+//! delegated vote times the weight of the voter who delegated; results also give the raw counts, every voter
+//! weighing 1.
+//!
+//! Polls with a [`Breakdown`] also count the votes of the members of each
+//! of its groups apart, delegated votes going to the groups of the voter who
+//! delegated. Each college then counts for its share of the result: an
+//! option gets the college's share times its share of the college's votes.
+//!
+//! Single transferable vote questions are also counted round by round, see
+//! [`crate::stv`], from the rankings of ballots and of the delegates who
+//! represent a voter, each worth the weight it would carry above. Party-list
+//! questions share their seats between lists by their weighted votes,
+//! delegated ones included, see [`crate::apportionment`], and quadratic
+//! voting questions also count votes against, see [`crate::quadratic`].
+//! Majority judgment questions rank options by the grades ballots and
+//! delegates gave them, see [`crate::majority_judgment`].
+//!
+//! Open polls with public results are counted on every update; once a poll
+//! closes, [`run`] counts it one last time and records the results in the
+//! log as [`Event::PollTallied`], with a [`TallyReport`] for each question.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use sqlx::Row;
+use time::{OffsetDateTime, UtcOffset};
+use uuid::Uuid;
+
+use crate::apportionment;
+use crate::db::{self, Pool};
+use crate::delegation;
+use crate::electorate;
+use crate::error::ApiError;
+use crate::events::{self, Event};
+use crate::majority_judgment;
+use crate::quadratic;
+use crate::stv;
+use crate::taxonomy;
+use crate::tenant;
+use crate::weights;
+use crate::{
+    Breakdown, ConflictPolicy, Delegation, GroupResult, Poll, PollQuestion, PollType, QuestionResult, ScoredVote,
+    TallyReport, VoteOption,
+};
+
+/// How often [`run`] looks for polls that just closed.
+const TICK: Duration = Duration::from_secs(30);
+
+/// Tallies every poll that has closed, forever.
+pub async fn run(pool: Pool) {
+    let mut ticks = tokio::time::interval(TICK);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = tally_due(&pool).await {
+            eprintln!("Failed to tally closed polls: {e:?}");
+        }
+    }
+}
+
+async fn tally_due(pool: &Pool) -> Result<(), ApiError> {
+    let due = sqlx::query(
+        r#"
+        SELECT poll_id, organization_id
+        FROM polls
+        WHERE poll_closing_time <= $1
+            AND NOT EXISTS (SELECT 1 FROM poll_results WHERE poll_results.poll_id = polls.poll_id)
+        "#,
+    )
+    .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+    .fetch_all(pool)
+    .await?;
+
+    for row in &due {
+        let poll = crate::api::load_poll(
+            pool,
+            db::parse_column(row, "organization_id")?,
+            db::parse_column(row, "poll_id")?,
+        )
+        .await?;
+        tally(pool, &poll).await?;
+    }
+
+    Ok(())
+}
+
+/// Tallies `poll` if it has closed and nobody did yet.
+pub async fn ensure(pool: &Pool, poll: &Poll) -> Result<(), ApiError> {
+    if OffsetDateTime::now_utc() < poll.poll_closing_time || tallied(pool, poll.poll_id).await? {
+        return Ok(());
+    }
+
+    tally(pool, poll).await
+}
+
+async fn tally(pool: &Pool, poll: &Poll) -> Result<(), ApiError> {
+    let results = compute(pool, poll).await?;
+    let event = Event::PollTallied {
+        poll_id: poll.poll_id,
+        results,
+    };
+
+    match events::record(pool, None, &event).await {
+        Ok(()) => Ok(()),
+        // Another request got there first.
+        Err(_) if tallied(pool, poll.poll_id).await? => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn tallied(pool: &Pool, poll_id: Uuid) -> Result<bool, sqlx::Error> {
+    Ok(sqlx::query("SELECT 1 FROM poll_results WHERE poll_id = $1")
+        .bind(poll_id.to_string())
+        .fetch_optional(pool)
+        .await?
+        .is_some())
+}
+
+/// Counts the ballots cast so far in `poll`, delegations included.
+pub async fn compute(pool: &Pool, poll: &Poll) -> Result<Vec<QuestionResult>, ApiError> {
+    let mut ballots: HashMap<Uuid, Vec<ScoredVote>> = HashMap::new();
+    for row in sqlx::query("SELECT user_id, vote FROM votes WHERE poll_id = $1")
+        .bind(poll.poll_id.to_string())
+        .fetch_all(pool)
+        .await?
+    {
+        ballots.insert(db::parse_column(&row, "user_id")?, db::json_column(&row, "vote")?);
+    }
+
+    let mut principals = delegation::in_force(pool, poll, None).await?;
+    // Only voters who could have voted are represented, with the weight
+    // they had when the poll opened.
+    let weights = if electorate::size(pool, poll.poll_id).await?.is_some() {
+        let electorate = electorate::weights(pool, poll.poll_id).await?;
+        principals.retain(|principal, _| electorate.contains_key(principal));
+        electorate
+    } else {
+        let opening = poll.poll_opening_time.to_offset(UtcOffset::UTC).date();
+        weights::on(pool, poll.organization_id, opening).await?
+    };
+    principals.retain(|principal, _| !ballots.contains_key(principal));
+
+    let parents = taxonomy::parents(pool, poll.organization_id).await?;
+    let policy = tenant::load(pool, poll.organization_id)
+        .await?
+        .settings
+        .delegation_conflicts;
+
+    let mut groups: HashMap<Uuid, Vec<String>> = HashMap::new();
+    if !poll.breakdown.groups.is_empty() || !poll.breakdown.colleges.is_empty() {
+        for row in sqlx::query("SELECT group_name, voter_id FROM group_members WHERE organization_id = $1")
+            .bind(poll.organization_id.to_string())
+            .fetch_all(pool)
+            .await?
+        {
+            groups
+                .entry(db::parse_column(&row, "voter_id")?)
+                .or_default()
+                .push(row.try_get("group_name")?);
+        }
+    }
+
+    Ok(count(poll, &ballots, &principals, &groups, &weights, &parents, policy))
+}
+
+/// Counts each question of `poll` from the ballots by voter and the
+/// delegations of the voters who did not vote, by principal. `groups` gives
+/// the groups of each voter, for the breakdown, and `weights` their weights
+/// when the poll opened.
+fn count(
+    poll: &Poll,
+    ballots: &HashMap<Uuid, Vec<ScoredVote>>,
+    principals: &HashMap<Uuid, Vec<Delegation>>,
+    groups: &HashMap<Uuid, Vec<String>>,
+    weights: &HashMap<Uuid, f64>,
+    parents: &HashMap<Uuid, Option<Uuid>>,
+    policy: ConflictPolicy,
+) -> Vec<QuestionResult> {
+    // Sorted, so that shares add up in the same order on every count.
+    let mut principals = principals.iter().collect::<Vec<_>>();
+    principals.sort_by_key(|(principal, _)| **principal);
+    let mut ballots = ballots.iter().collect::<Vec<_>>();
+    ballots.sort_by_key(|(voter_id, _)| **voter_id);
+    let weight = |voter_id: &Uuid| weights.get(voter_id).copied().unwrap_or_else(weights::unit);
+
+    poll.questions
+        .iter()
+        .map(|question| {
+            let options = &question.vote_options;
+
+            let mut delegated_by = Vec::new();
+            let mut conflicts = 0;
+            for (principal, given) in &principals {
+                let applied = delegation::resolve(parents, given, question, policy);
+                if applied.conflict {
+                    conflicts += 1;
+                }
+
+                let mut represented = Represented {
+                    principal: **principal,
+                    added: vec![0.0; options.len()],
+                    delegates: Vec::new(),
+                };
+                for share in &applied.shares {
+                    let Some(votes) = ballots_of(&ballots, share.delegation.to_delegate) else {
+                        continue;
+                    };
+                    for (added, option) in represented.added.iter_mut().zip(options) {
+                        *added += share.weight * score(votes, question.question_id, option) as f64;
+                    }
+                    represented.delegates.push((votes, share.weight));
+                }
+                if !represented.delegates.is_empty() {
+                    delegated_by.push(represented);
+                }
+            }
+
+            let (vote_results, raw_vote_results) = direct(question, &ballots, weight);
+            let (delegated_results, raw_delegated_results) = delegated(options, &delegated_by, weight);
+
+            let group_results = breakdown(&poll.breakdown, |group| {
+                let in_group = |voter_id: &Uuid| {
+                    groups.get(voter_id).is_some_and(|of| of.iter().any(|name| name == group))
+                };
+                let members = ballots
+                    .iter()
+                    .copied()
+                    .filter(|(voter_id, _)| in_group(voter_id))
+                    .collect::<Vec<_>>();
+                let represented = delegated_by
+                    .iter()
+                    .filter(|represented| in_group(&represented.principal))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let (vote_results, raw_vote_results) = direct(question, &members, weight);
+                let (delegated_results, raw_delegated_results) = delegated(options, &represented, weight);
+
+                GroupResult {
+                    group: group.to_owned(),
+                    ballots: members.len() as u64,
+                    vote_results,
+                    delegated_results,
+                    raw_vote_results,
+                    raw_delegated_results,
+                }
+            });
+            let college_results = (!poll.breakdown.colleges.is_empty())
+                .then(|| weigh_colleges(&poll.breakdown, &group_results, options.len()));
+
+            let cast = ballots.len() as u64;
+            let represented = delegated_by.len() as u64;
+            let ballots_weight = ballots.iter().map(|(voter_id, _)| weight(voter_id)).sum::<f64>();
+            let represented_weight = delegated_by
+                .iter()
+                .map(|represented| weight(&represented.principal))
+                .sum::<f64>();
+            let approval_rates = match question.question_type {
+                PollType::Approval => {
+                    approval_rates(&vote_results, &delegated_results, ballots_weight + represented_weight)
+                }
+                _ => Vec::new(),
+            };
+            let stv = match question.question_type {
+                PollType::SingleTransferableVote => {
+                    let mut rankings = ballots
+                        .iter()
+                        .map(|(voter_id, votes)| stv::Ballot {
+                            ranking: ranking(question, votes),
+                            value: weight(voter_id),
+                        })
+                        .collect::<Vec<_>>();
+                    for represented in &delegated_by {
+                        for (votes, share) in &represented.delegates {
+                            rankings.push(stv::Ballot {
+                                ranking: ranking(question, votes),
+                                value: share * weight(&represented.principal),
+                            });
+                        }
+                    }
+                    Some(stv::count(
+                        options,
+                        &rankings,
+                        question.seats.unwrap_or(1),
+                        question.surplus_transfer.unwrap_or_default(),
+                    ))
+                }
+                _ => None,
+            };
+            let seat_allocation = match question.question_type {
+                PollType::PartyList => Some(apportionment::allocate(
+                    options,
+                    vote_results.iter().zip(&delegated_results).map(|(direct, delegated)| direct + delegated).collect(),
+                    question.seats.unwrap_or(1),
+                    question.apportionment.unwrap_or_default(),
+                    question.threshold.unwrap_or(0.0),
+                )),
+                _ => None,
+            };
+            let quadratic = match question.question_type {
+                PollType::QuadraticVoting => {
+                    let mut against = vec![0.0; options.len()];
+                    for (voter_id, votes) in &ballots {
+                        add_against(&mut against, question, votes, weight(voter_id));
+                    }
+                    for represented in &delegated_by {
+                        for (votes, share) in &represented.delegates {
+                            add_against(&mut against, question, votes, share * weight(&represented.principal));
+                        }
+                    }
+                    Some(quadratic::count(
+                        question.question_id,
+                        options,
+                        vote_results.iter().zip(&delegated_results).map(|(direct, delegated)| direct + delegated).collect(),
+                        against,
+                        ballots.iter().map(|(_, votes)| votes.as_slice()),
+                        poll.credits.unwrap_or(0),
+                    ))
+                }
+                _ => None,
+            };
+            let majority_judgment = match question.question_type {
+                PollType::MajorityJudgment => {
+                    let mut distributions = vec![vec![0.0; question.grades.len()]; options.len()];
+                    for (voter_id, votes) in &ballots {
+                        add_grades(&mut distributions, question, votes, weight(voter_id));
+                    }
+                    for represented in &delegated_by {
+                        for (votes, share) in &represented.delegates {
+                            add_grades(&mut distributions, question, votes, share * weight(&represented.principal));
+                        }
+                    }
+                    Some(majority_judgment::count(options, &question.grades, distributions))
+                }
+                _ => None,
+            };
+            QuestionResult {
+                poll_id: poll.poll_id,
+                question_id: question.question_id,
+                vote_results,
+                delegated_results,
+                raw_vote_results,
+                raw_delegated_results,
+                approval_rates,
+                stv,
+                seat_allocation,
+                quadratic,
+                majority_judgment,
+                report: Some(TallyReport {
+                    ballots: cast,
+                    represented,
+                    ballots_weight,
+                    represented_weight,
+                    conflicts,
+                    conflict_policy: policy,
+                    explanation: explain(cast, represented, conflicts, policy),
+                }),
+                groups: group_results,
+                college_results,
+            }
+        })
+        .collect()
+}
+
+/// A voter who did not vote, represented on a question by delegates who did.
+#[derive(Clone)]
+struct Represented<'a> {
+    principal: Uuid,
+    /// Scores the delegates gave each option, times the share of the
+    /// principal's say each carries, before weighing.
+    added: Vec<f64>,
+    /// The delegates' ballots, with those shares.
+    delegates: Vec<(&'a [ScoredVote], f64)>,
+}
+
+/// Adds `weight` to the grade `votes` gave each option of `question`, in
+/// `distributions` of grades best first; options left out get the last.
+fn add_grades(distributions: &mut [Vec<f64>], question: &PollQuestion, votes: &[ScoredVote], weight: f64) {
+    for (distribution, option) in distributions.iter_mut().zip(&question.vote_options) {
+        let Some(last) = distribution.len().checked_sub(1) else {
+            continue;
+        };
+        let above_last = score(votes, question.question_id, option).min(last as u64) as usize;
+        distribution[last - above_last] += weight;
+    }
+}
+
+/// The options of `question` `votes` ranks, first preference first.
+fn ranking(question: &PollQuestion, votes: &[ScoredVote]) -> Vec<usize> {
+    let mut ranked = question
+        .vote_options
+        .iter()
+        .enumerate()
+        .filter_map(|(index, option)| {
+            let rank = votes.iter().find(|vote| vote.answers(question.question_id, option))?.score;
+            (rank > 0).then_some((rank, index))
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_unstable();
+    ranked.into_iter().map(|(_, index)| index).collect()
+}
+
+/// Percentage of `turnout`, the weight of the voters who took part, that
+/// approved each option.
+fn approval_rates(vote_results: &[f64], delegated_results: &[f64], turnout: f64) -> Vec<f64> {
+    vote_results
+        .iter()
+        .zip(delegated_results)
+        .map(|(direct, delegated)| {
+            if turnout > 0.0 {
+                100.0 * (direct + delegated) / turnout
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+fn ballots_of<'a>(ballots: &[(&Uuid, &'a Vec<ScoredVote>)], voter_id: Uuid) -> Option<&'a [ScoredVote]> {
+    ballots
+        .binary_search_by_key(&voter_id, |(id, _)| **id)
+        .ok()
+        .map(|index| ballots[index].1.as_slice())
+}
+
+/// Scores `ballots` gave each option of `question`, weighted then raw.
+fn direct(
+    question: &PollQuestion,
+    ballots: &[(&Uuid, &Vec<ScoredVote>)],
+    weight: impl Fn(&Uuid) -> f64,
+) -> (Vec<f64>, Vec<u64>) {
+    let options = &question.vote_options;
+    let mut weighted = vec![0.0; options.len()];
+    let mut raw = vec![0; options.len()];
+    for (voter_id, votes) in ballots {
+        let weight = weight(voter_id);
+        for ((weighted, raw), option) in weighted.iter_mut().zip(raw.iter_mut()).zip(options) {
+            let score = score(votes, question.question_id, option);
+            *weighted += weight * score as f64;
+            *raw += score;
+        }
+    }
+
+    (weighted, raw)
+}
+
+/// Scores delegates added to each option for the principals of
+/// `delegated_by`, weighted by the principal's weight then raw.
+fn delegated(
+    options: &[VoteOption],
+    delegated_by: &[Represented],
+    weight: impl Fn(&Uuid) -> f64,
+) -> (Vec<f64>, Vec<f64>) {
+    let mut weighted = vec![0.0; options.len()];
+    let mut raw = vec![0.0; options.len()];
+    for represented in delegated_by {
+        let weight = weight(&represented.principal);
+        for ((weighted, raw), added) in weighted.iter_mut().zip(raw.iter_mut()).zip(&represented.added) {
+            *weighted += weight * added;
+            *raw += added;
+        }
+    }
+
+    (weighted, raw)
+}
+
+/// Counts each group of `breakdown`, the breakdown's own groups then the
+/// colleges, once each.
+fn breakdown(
+    breakdown: &Breakdown,
+    mut count_group: impl FnMut(&str) -> GroupResult,
+) -> Vec<GroupResult> {
+    let mut results: Vec<GroupResult> = Vec::new();
+    let names = breakdown
+        .groups
+        .iter()
+        .chain(breakdown.colleges.iter().map(|college| &college.group));
+    for name in names {
+        if !results.iter().any(|result| &result.group == name) {
+            results.push(count_group(name));
+        }
+    }
+
+    results
+}
+
+/// Share of the result each of `options` options gets when each college of
+/// `breakdown` counts for its share; a college nobody in voted for adds
+/// nothing.
+fn weigh_colleges(breakdown: &Breakdown, group_results: &[GroupResult], options: usize) -> Vec<f64> {
+    let mut weighted = vec![0.0; options];
+    for college in &breakdown.colleges {
+        let Some(result) = group_results.iter().find(|result| result.group == college.group) else {
+            continue;
+        };
+        let totals = result
+            .vote_results
+            .iter()
+            .zip(&result.delegated_results)
+            .map(|(direct, delegated)| direct + delegated)
+            .collect::<Vec<_>>();
+        let sum = totals.iter().sum::<f64>();
+        if sum > 0.0 {
+            for (weighted, total) in weighted.iter_mut().zip(totals) {
+                *weighted += college.share * total / sum;
+            }
+        }
+    }
+
+    weighted
+}
+
+/// Sum of the scores `votes` gave `option` of the question `question_id`,
+/// votes against left out.
+fn score(votes: &[ScoredVote], question_id: Uuid, option: &VoteOption) -> u64 {
+    votes
+        .iter()
+        .filter(|vote| vote.answers(question_id, option) && !vote.against)
+        .map(|vote| u64::from(vote.score))
+        .sum()
+}
+
+/// Adds the votes `votes` cast against each option of `question`, times
+/// `weight`.
+fn add_against(against: &mut [f64], question: &PollQuestion, votes: &[ScoredVote], weight: f64) {
+    for (against, option) in against.iter_mut().zip(&question.vote_options) {
+        *against += weight
+            * votes
+                .iter()
+                .filter(|vote| vote.answers(question.question_id, option) && vote.against)
+                .map(|vote| f64::from(vote.score))
+                .sum::<f64>();
+    }
+}
+
+fn explain(ballots: u64, represented: u64, conflicts: u64, policy: ConflictPolicy) -> String {
+    let mut explanation = format!(
+        "Ballots cast: {ballots}. Voters who did not vote but were represented by a delegate \
+         who did: {represented}."
+    );
+    if conflicts > 0 {
+        explanation += &format!(
+            " Voters who delegated the question's topics to different people: {conflicts}; {}",
+            match policy {
+                ConflictPolicy::Priority => {
+                    "for each, only the delegate for the first of its topics they delegated counted."
+                }
+                ConflictPolicy::Split => "each of their delegates carried an equal share of their vote.",
+                ConflictPolicy::Abstain => "their votes were not counted.",
+            }
+        );
+    }
+
+    explanation
+}
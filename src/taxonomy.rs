@@ -0,0 +1,194 @@
+//! Topics, which each organization defines for itself. Voters delegate on
+//! topics and a question can be about several; an archived topic stays on
+//! the delegations and questions that use it, but new ones cannot.
+//!
+//! Topics nest, such as Energy > Nuclear: a delegation on a topic also
+//! covers the topics below it, see [`crate::delegation`].
+//!
+//! Topics used to be a fixed enum. Its variants were seeded as topics of the
+//! default organization, with the ids of [`LEGACY_TOPICS`], and events and
+//! payloads naming a variant still read as the matching topic.
+
+use std::collections::HashMap;
+
+use serde::{de, Deserialize, Deserializer};
+use sqlx::{any::AnyRow, Row};
+use uuid::Uuid;
+
+use crate::db::{self, Pool, SqlBuilder};
+use crate::error::ApiError;
+use crate::Topic;
+
+/// Variants of the former enum; the nth has the id `n + 1` in the second
+/// half of the id, such as `00000000-0000-0001-0000-000000000001`.
+const LEGACY_TOPICS: [&str; 16] = [
+    "Geopolitics",
+    "Defense",
+    "Work",
+    "Industry",
+    "Family",
+    "Finances",
+    "Education",
+    "Research",
+    "Judicial",
+    "LawEnforcement",
+    "Environment",
+    "Energy",
+    "Medical",
+    "Culture",
+    "Technology",
+    "Sports",
+];
+
+/// Id of the topic seeded for a variant of the former enum.
+fn legacy_id(name: &str) -> Option<Uuid> {
+    let index = LEGACY_TOPICS.iter().position(|legacy| *legacy == name)?;
+
+    Some(Uuid::from_u128(1 << 64 | (index as u128 + 1)))
+}
+
+fn parse(value: &str) -> Result<Uuid, String> {
+    match legacy_id(value) {
+        Some(topic_id) => Ok(topic_id),
+        None => value
+            .parse()
+            .map_err(|_| format!("{value:?} is neither a topic id nor a former topic name")),
+    }
+}
+
+/// Reads topic ids, or names of variants of the former enum.
+pub fn deserialize_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Uuid>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| parse(value).map_err(de::Error::custom))
+        .collect()
+}
+
+/// Reads the topics of a question: a list of topic ids, or the single topic
+/// id or former topic name questions had before they could span several.
+pub fn deserialize_question_topics<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Uuid>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Topics {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let topics = match Topics::deserialize(deserializer)? {
+        Topics::One(value) => vec![value],
+        Topics::Many(values) => values,
+    };
+    topics.iter().map(|value| parse(value).map_err(de::Error::custom)).collect()
+}
+
+pub async fn load(pool: &Pool, organization_id: Uuid, topic_id: Uuid) -> Result<Topic, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT topic_id, organization_id, parent_id, name, description, archived_at
+        FROM topics
+        WHERE topic_id = $1 AND organization_id = $2
+        "#,
+    )
+    .bind(topic_id.to_string())
+    .bind(organization_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(ApiError::missing(format!("Topic {topic_id}")))?;
+
+    Ok(topic_from_row(&row)?)
+}
+
+/// Topics of the organization by name, archived ones only if asked for.
+pub async fn list(pool: &Pool, organization_id: Uuid, archived: bool) -> Result<Vec<Topic>, sqlx::Error> {
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT topic_id, organization_id, parent_id, name, description, archived_at
+        FROM topics
+        WHERE organization_id = "#,
+    );
+    sql.push_bind(organization_id.to_string());
+    if !archived {
+        sql.push(" AND archived_at IS NULL");
+    }
+    sql.push(" ORDER BY name, topic_id");
+
+    sql.query()
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(topic_from_row)
+        .collect()
+}
+
+/// Parent of every topic of the organization, to walk up the hierarchy.
+pub async fn parents(pool: &Pool, organization_id: Uuid) -> Result<HashMap<Uuid, Option<Uuid>>, sqlx::Error> {
+    sqlx::query("SELECT topic_id, parent_id FROM topics WHERE organization_id = $1")
+        .bind(organization_id.to_string())
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok((
+                db::parse_column(row, "topic_id")?,
+                db::optional_parse_column(row, "parent_id")?,
+            ))
+        })
+        .collect()
+}
+
+/// `topic_id`, then its parent, and so on up to a topic without one.
+pub fn ancestry(parents: &HashMap<Uuid, Option<Uuid>>, topic_id: Uuid) -> Vec<Uuid> {
+    let mut ancestry = vec![topic_id];
+    while let Some(&Some(parent_id)) = parents.get(ancestry.last().unwrap()) {
+        // Cycles are refused when topics are saved; this only guards reads.
+        if ancestry.contains(&parent_id) {
+            break;
+        }
+        ancestry.push(parent_id);
+    }
+
+    ancestry
+}
+
+/// Rejects ids that do not name a topic of the organization that is still
+/// in use.
+pub async fn ensure_usable(pool: &Pool, organization_id: Uuid, topic_ids: &[Uuid]) -> Result<(), ApiError> {
+    if topic_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut sql = SqlBuilder::new(
+        r#"
+        SELECT topic_id
+        FROM topics
+        WHERE archived_at IS NULL AND organization_id = "#,
+    );
+    sql.push_bind(organization_id.to_string())
+        .push(" AND topic_id IN ")
+        .push_bind_list(topic_ids.iter().map(Uuid::to_string));
+    let usable = sql
+        .query()
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| db::parse_column(row, "topic_id"))
+        .collect::<Result<Vec<Uuid>, _>>()?;
+
+    match topic_ids.iter().find(|topic_id| !usable.contains(topic_id)) {
+        Some(topic_id) => Err(ApiError::Validation(format!(
+            "Topic {topic_id} does not exist in this organization or is archived"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn topic_from_row(row: &AnyRow) -> Result<Topic, sqlx::Error> {
+    Ok(Topic {
+        topic_id: db::parse_column(row, "topic_id")?,
+        organization_id: db::parse_column(row, "organization_id")?,
+        parent_id: db::optional_parse_column(row, "parent_id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        archived_at: db::optional_timestamp_column(row, "archived_at")?,
+    })
+}
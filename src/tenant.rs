@@ -0,0 +1,237 @@
+//! Organizations sharing the server.
+//!
+//! Every request acts within one [`Organization`], its tenant, picked by
+//! [`select`] before routing: from the subdomain of `TENANT_DOMAIN` the
+//! request was sent to, such as `assoc.vote.example.org`, or from an
+//! `/o/{slug}` path prefix, which is stripped. Requests naming neither go to
+//! the default organization, which owns everything recorded before there
+//! were several. Handlers take the [`Tenant`] extractor and filter every
+//! query by its id.
+//!
+//! Accounts belong to people: credentials, sessions and second factors work
+//! in every organization. Membership, roles, groups, delegations, polls and
+//! vote history belong to one organization, so a voter who is a member of
+//! several has separate delegations in each.
+
+use std::ops::Deref;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, Uri},
+};
+use sqlx::{any::AnyRow, Row};
+use uuid::Uuid;
+
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+use crate::events::{self, Event};
+use crate::{AppState, Organization, OrganizationSettings, Role};
+
+/// The organization that owns data recorded before there were several.
+pub const DEFAULT_ORGANIZATION: Uuid = Uuid::from_u128(1);
+
+/// For events and payloads recorded before organizations existed.
+pub fn default_organization() -> Uuid {
+    DEFAULT_ORGANIZATION
+}
+
+/// Slug named by a request, set by [`select`].
+#[derive(Clone)]
+struct Selected(String);
+
+/// Reads the slug of the organization a request is for and, for an `/o/`
+/// path prefix, strips it so that the request is routed like any other.
+pub async fn select(State(state): State<AppState>, mut request: Request) -> Request {
+    let path = request.uri().path().to_owned();
+    if let Some(rest) = path.strip_prefix("/o/") {
+        let (slug, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("/{rest}?{query}"),
+            None => format!("/{rest}"),
+        };
+        let mut uri = request.uri().clone().into_parts();
+        uri.path_and_query = path_and_query.parse().ok();
+        if let Ok(uri) = Uri::from_parts(uri) {
+            *request.uri_mut() = uri;
+        }
+        request.extensions_mut().insert(Selected(slug.to_owned()));
+    } else if let Some(slug) = state
+        .tenant_domain
+        .as_deref()
+        .and_then(|domain| subdomain(request.headers(), domain))
+    {
+        request.extensions_mut().insert(Selected(slug));
+    }
+
+    request
+}
+
+/// `assoc` for a request to `assoc.vote.example.org:3000` when `domain` is
+/// `vote.example.org`.
+fn subdomain(headers: &HeaderMap, domain: &str) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let host = host.split_once(':').map_or(host, |(host, _port)| host);
+    let slug = host.strip_suffix(domain)?.strip_suffix('.')?;
+
+    (!slug.is_empty() && !slug.contains('.')).then(|| slug.to_ascii_lowercase())
+}
+
+impl Organization {
+    /// The organization as responses and the audit log show it, without the
+    /// client secret of its identity provider.
+    pub fn redacted(mut self) -> Organization {
+        if let Some(sso) = &mut self.settings.sso {
+            sso.client_secret = None;
+        }
+        self
+    }
+}
+
+/// The organization a request acts within; unknown slugs get a 404.
+pub(crate) struct Tenant(pub(crate) Organization);
+
+impl Deref for Tenant {
+    type Target = Organization;
+
+    fn deref(&self) -> &Organization {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Tenant {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Handlers often extract both a session and the tenant.
+        if let Some(organization) = parts.extensions.get::<Organization>() {
+            return Ok(Tenant(organization.clone()));
+        }
+
+        let organization = match parts.extensions.get::<Selected>() {
+            Some(Selected(slug)) => find(&state.pool, slug).await?,
+            None => load(&state.pool, DEFAULT_ORGANIZATION).await?,
+        };
+        parts.extensions.insert(organization.clone());
+
+        Ok(Tenant(organization))
+    }
+}
+
+pub async fn load(pool: &Pool, organization_id: Uuid) -> Result<Organization, ApiError> {
+    let row = sqlx::query("SELECT organization_id, slug, name, settings FROM organizations WHERE organization_id = $1")
+        .bind(organization_id.to_string())
+        .fetch_one(pool)
+        .await
+        .map_err(ApiError::missing(format!("Organization {organization_id}")))?;
+
+    Ok(organization_from_row(&row)?)
+}
+
+pub async fn find(pool: &Pool, slug: &str) -> Result<Organization, ApiError> {
+    let row = sqlx::query("SELECT organization_id, slug, name, settings FROM organizations WHERE slug = $1")
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .map_err(ApiError::missing(format!("Organization {slug:?}")))?;
+
+    Ok(organization_from_row(&row)?)
+}
+
+/// Organizations `voter_id` is a member of, by name.
+pub async fn memberships(pool: &Pool, voter_id: Uuid) -> Result<Vec<Organization>, sqlx::Error> {
+    sqlx::query(
+        r#"
+        SELECT organizations.organization_id, organizations.slug, organizations.name, organizations.settings
+        FROM organizations
+        JOIN organization_members ON organization_members.organization_id = organizations.organization_id
+        WHERE organization_members.voter_id = $1
+        ORDER BY organizations.name, organizations.organization_id
+        "#,
+    )
+    .bind(voter_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(organization_from_row)
+    .collect()
+}
+
+pub async fn is_member(pool: &Pool, organization_id: Uuid, voter_id: Uuid) -> Result<bool, sqlx::Error> {
+    Ok(sqlx::query("SELECT 1 FROM organization_members WHERE organization_id = $1 AND voter_id = $2")
+        .bind(organization_id.to_string())
+        .bind(voter_id.to_string())
+        .fetch_optional(pool)
+        .await?
+        .is_some())
+}
+
+/// Creates an organization. Its `founder`, if any, becomes a member with the
+/// `Admin` and `Voter` roles; otherwise grant roles with `grant-role`.
+pub async fn create(
+    pool: &Pool,
+    founder: Option<Uuid>,
+    slug: String,
+    name: String,
+) -> Result<Organization, ApiError> {
+    if !is_valid_slug(&slug) {
+        return Err(ApiError::Validation(format!(
+            "{slug:?} is not a valid slug: use 1 to 63 lowercase letters, digits and inner hyphens"
+        )));
+    }
+    if name.trim().is_empty() {
+        return Err(ApiError::Validation("The organization needs a name".to_owned()));
+    }
+
+    let organization = Organization {
+        organization_id: Uuid::new_v4(),
+        slug,
+        name,
+        settings: OrganizationSettings::default(),
+    };
+    let organization_id = organization.organization_id;
+
+    let mut tx = pool.begin().await?;
+    events::record_in(&mut tx, founder, &Event::OrganizationCreated(organization.clone()))
+        .await
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Conflict(_) => {
+                ApiError::Conflict(format!("The slug {:?} is already taken", organization.slug))
+            }
+            e => e,
+        })?;
+    if let Some(voter_id) = founder {
+        events::record_in(&mut tx, founder, &Event::MemberAdded { organization_id, voter_id }).await?;
+        for role in [Role::Admin, Role::Voter] {
+            let event = Event::RoleGranted {
+                organization_id,
+                voter_id,
+                role,
+            };
+            events::record_in(&mut tx, founder, &event).await?;
+        }
+    }
+    tx.commit().await?;
+
+    Ok(organization)
+}
+
+/// Slugs end up in host names, hence the DNS label rules.
+fn is_valid_slug(slug: &str) -> bool {
+    (1..=63).contains(&slug.len())
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+}
+
+fn organization_from_row(row: &AnyRow) -> Result<Organization, sqlx::Error> {
+    Ok(Organization {
+        organization_id: db::parse_column(row, "organization_id")?,
+        slug: row.try_get("slug")?,
+        name: row.try_get("name")?,
+        settings: db::json_column(row, "settings")?,
+    })
+}
@@ -0,0 +1,111 @@
+use axum::http::{header, StatusCode};
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+
+use super::{Account, TestApp};
+use crate::db;
+
+/// Makes every emailed link issued so far expire.
+async fn expire_links(app: &TestApp) {
+    sqlx::query("UPDATE email_tokens SET expires_at = $1")
+        .bind(db::format_timestamp(OffsetDateTime::now_utc() - time::Duration::minutes(1)))
+        .execute(&app.state.pool)
+        .await
+        .unwrap();
+}
+
+async fn session(app: &TestApp, account: &Account) -> Value {
+    let response = app.get("/api/v1/session", Some(account)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body
+}
+
+#[tokio::test]
+async fn the_confirmation_link_verifies_the_address_once() {
+    let app = TestApp::new().await;
+    let voter = app.register("Grace").await;
+    assert_eq!(app.mailbox.sent_to(&voter.email).len(), 1);
+    assert_eq!(session(&app, &voter).await["email_verified_at"], Value::Null);
+
+    let link = app.last_link(&voter.email).unwrap();
+    assert!(link.starts_with("/api/v1/accounts/verify?token="), "{link}");
+    let confirmed = app.get(&link, None).await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    assert!(session(&app, &voter).await["email_verified_at"].is_string());
+
+    let again = app.get(&link, None).await;
+    assert_eq!(again.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn an_expired_confirmation_link_is_refused_and_can_be_resent() {
+    let app = TestApp::new().await;
+    let voter = app.register("Grace").await;
+    let expired = app.last_link(&voter.email).unwrap();
+    expire_links(&app).await;
+
+    let refused = app.get(&expired, None).await;
+    assert_eq!(refused.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(session(&app, &voter).await["email_verified_at"], Value::Null);
+
+    let resent = app.post("/api/v1/accounts/verification", Some(&voter), json!({})).await;
+    assert_eq!(resent.status, StatusCode::ACCEPTED, "{}", resent.body);
+    let link = app.last_link(&voter.email).unwrap();
+    assert_ne!(link, expired);
+    assert_eq!(app.get(&link, None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn a_magic_link_logs_in_once() {
+    let app = TestApp::new().await;
+    let voter = app.register("Grace").await;
+
+    let requested = app
+        .post("/api/v1/session/magic-link", None, json!({ "email": voter.email }))
+        .await;
+    assert_eq!(requested.status, StatusCode::ACCEPTED);
+    let link = app.last_link(&voter.email).unwrap();
+    assert!(link.starts_with("/api/v1/session/magic?token="), "{link}");
+
+    let followed = app.get(&link, None).await;
+    assert_eq!(followed.status, StatusCode::SEE_OTHER);
+    assert_eq!(followed.headers[header::LOCATION], "/");
+    let logged_in = Account {
+        cookie: followed.session_cookie().expect("No session cookie"),
+        ..voter
+    };
+    let session = session(&app, &logged_in).await;
+    assert_eq!(session["voter_id"], json!(logged_in.voter_id));
+    // Following the link proves the address too.
+    assert!(session["email_verified_at"].is_string());
+
+    let again = app.get(&link, None).await;
+    assert_eq!(again.status, StatusCode::UNAUTHORIZED);
+    assert!(again.session_cookie().is_none());
+}
+
+#[tokio::test]
+async fn an_expired_magic_link_is_refused() {
+    let app = TestApp::new().await;
+    let voter = app.register("Grace").await;
+    app.post("/api/v1/session/magic-link", None, json!({ "email": voter.email }))
+        .await;
+    let link = app.last_link(&voter.email).unwrap();
+    expire_links(&app).await;
+
+    let refused = app.get(&link, None).await;
+    assert_eq!(refused.status, StatusCode::UNAUTHORIZED);
+    assert!(refused.session_cookie().is_none());
+}
+
+#[tokio::test]
+async fn magic_links_do_not_tell_who_is_registered() {
+    let app = TestApp::new().await;
+
+    let requested = app
+        .post("/api/v1/session/magic-link", None, json!({ "email": "nobody@example.org" }))
+        .await;
+    assert_eq!(requested.status, StatusCode::ACCEPTED);
+    assert!(app.mailbox.sent_to("nobody@example.org").is_empty());
+}
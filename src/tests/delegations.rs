@@ -0,0 +1,205 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use time::OffsetDateTime;
+
+use uuid::Uuid;
+
+use super::{answer, question, Account, TestApp};
+use crate::Role;
+
+#[tokio::test]
+async fn delegates_vote_for_principals_who_do_not() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let delegate = app.register_verified("Grace").await;
+    let principal = app.register_verified("Alan").await;
+    let dissenter = app.register_verified("Edsger").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+
+    let end_date = (OffsetDateTime::now_utc() + time::Duration::days(30)).date();
+    for from in [&principal, &dissenter] {
+        let response = app
+            .post(
+                &format!("/api/v1/voters/{}/delegations", from.voter_id),
+                Some(from),
+                json!({ "to": delegate.voter_id, "topics": [topic], "end_date": end_date }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    }
+    let forged = app
+        .post(
+            &format!("/api/v1/voters/{}/delegations", principal.voter_id),
+            Some(&dissenter),
+            json!({ "to": delegate.voter_id, "topics": [topic], "end_date": end_date }),
+        )
+        .await;
+    assert_eq!(forged.status, StatusCode::FORBIDDEN);
+
+    let referendum = question("Referendum", topic, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app.open_poll(&admin, json!({}), vec![referendum]).await;
+
+    // Voting yourself overrides your delegation.
+    for (voter, choice) in [(&delegate, "Yes"), (&dissenter, "No")] {
+        let ballot = app.vote(voter, poll_id, json!([answer(choice, 1)])).await;
+        assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    }
+
+    app.close(&admin, poll_id).await;
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([1.0, 1.0]));
+    assert_eq!(results["delegated_results"], json!([1.0, 0.0]));
+    assert_eq!(results["report"]["represented"], 1);
+}
+
+/// Delegates `from`'s vote on `topics` to `to` for a month.
+async fn delegate(app: &TestApp, from: &Account, to: &Account, topics: &[Uuid]) {
+    let end_date = (OffsetDateTime::now_utc() + time::Duration::days(30)).date();
+    let response = app
+        .post(
+            &format!("/api/v1/voters/{}/delegations", from.voter_id),
+            Some(from),
+            json!({ "to": to.voter_id, "topics": topics, "end_date": end_date }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
+
+#[tokio::test]
+async fn the_most_specific_delegation_applies_to_nested_topics() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let generalist = app.register_verified("Grace").await;
+    let specialist = app.register_verified("Alan").await;
+    let trusting = app.register_verified("Edsger").await;
+    let careful = app.register_verified("Barbara").await;
+    let transport = app.create_topic(&admin, "Transport").await;
+    let railways = app
+        .post("/api/v1/topics", Some(&admin), json!({ "name": "Railways", "parent_id": transport }))
+        .await;
+    assert_eq!(railways.status, StatusCode::CREATED, "{}", railways.body);
+    let railways: Uuid = railways.body["topic_id"].as_str().unwrap().parse().unwrap();
+
+    // A delegation on Transport covers Railways, unless one names Railways.
+    delegate(&app, &trusting, &generalist, &[transport]).await;
+    delegate(&app, &careful, &generalist, &[transport]).await;
+    delegate(&app, &careful, &specialist, &[railways]).await;
+
+    let referendum = question("Referendum", railways, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app.open_poll(&admin, json!({}), vec![referendum]).await;
+
+    let applied = app
+        .get(&format!("/api/v1/voters/{}/polls/{poll_id}/delegations", careful.voter_id), Some(&careful))
+        .await;
+    assert_eq!(applied.status, StatusCode::OK, "{}", applied.body);
+    let shares = &applied.body[0]["shares"];
+    assert_eq!(shares.as_array().unwrap().len(), 1, "{shares}");
+    assert_eq!(shares[0]["delegation"]["to_delegate"], json!(specialist.voter_id));
+    assert_eq!(shares[0]["via_topic"], json!(railways));
+    let applied = app
+        .get(&format!("/api/v1/voters/{}/polls/{poll_id}/delegations", trusting.voter_id), Some(&trusting))
+        .await;
+    assert_eq!(applied.body[0]["shares"][0]["delegation"]["to_delegate"], json!(generalist.voter_id));
+    assert_eq!(applied.body[0]["shares"][0]["topic"], json!(railways));
+    assert_eq!(applied.body[0]["shares"][0]["via_topic"], json!(transport));
+
+    for (voter, choice) in [(&generalist, "Yes"), (&specialist, "No")] {
+        let ballot = app.vote(voter, poll_id, json!([answer(choice, 1)])).await;
+        assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    }
+
+    app.close(&admin, poll_id).await;
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([1.0, 1.0]));
+    assert_eq!(results["delegated_results"], json!([1.0, 1.0]));
+    assert_eq!(results["report"]["represented"], 2);
+    assert_eq!(results["report"]["conflicts"], 0);
+}
+
+#[tokio::test]
+async fn the_organization_policy_settles_conflicting_delegations() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let builder = app.register_verified("Grace").await;
+    let treasurer = app.register_verified("Alan").await;
+    let principal = app.register_verified("Edsger").await;
+    let housing = app.create_topic(&admin, "Housing").await;
+    let taxes = app.create_topic(&admin, "Taxes").await;
+    delegate(&app, &principal, &builder, &[housing]).await;
+    delegate(&app, &principal, &treasurer, &[taxes]).await;
+
+    for (policy, delegated) in [
+        ("Priority", json!([1.0, 0.0])),
+        ("Split", json!([0.5, 0.5])),
+        ("Abstain", json!([0.0, 0.0])),
+    ] {
+        let updated = app
+            .request(
+                Method::PATCH,
+                "/api/v1/organization",
+                Some(&admin),
+                Some(json!({ "settings": { "delegation_conflicts": policy } })),
+            )
+            .await;
+        assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+
+        // Housing comes first, so it has priority.
+        let mut motion = question("Referendum", housing, &["Yes", "No"]);
+        motion["question_topics"] = json!([housing, taxes]);
+        let question_id = motion["question_id"].as_str().unwrap().parse().unwrap();
+        let poll_id = app.open_poll(&admin, json!({}), vec![motion]).await;
+        for (voter, choice) in [(&builder, "Yes"), (&treasurer, "No")] {
+            let ballot = app.vote(voter, poll_id, json!([answer(choice, 1)])).await;
+            assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+        }
+
+        app.close(&admin, poll_id).await;
+        let results = app.results(poll_id, question_id).await;
+        assert_eq!(results["vote_results"], json!([1.0, 1.0]), "{policy}");
+        assert_eq!(results["delegated_results"], delegated, "{policy}");
+        assert_eq!(results["report"]["conflicts"], 1, "{policy}");
+        assert_eq!(results["report"]["conflict_policy"], policy);
+    }
+}
+
+#[tokio::test]
+async fn delegations_are_listed_page_by_page_by_topic_and_delegate() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let principal = app.register_verified("Grace").await;
+    let first = app.register_verified("Alan").await;
+    let second = app.register_verified("Edsger").await;
+    let housing = app.create_topic(&admin, "Housing").await;
+    let taxes = app.create_topic(&admin, "Taxes").await;
+    let roads = app.create_topic(&admin, "Roads").await;
+    delegate(&app, &principal, &first, &[housing]).await;
+    delegate(&app, &principal, &second, &[taxes]).await;
+    delegate(&app, &principal, &second, &[roads]).await;
+    let delegations = format!("/api/v1/voters/{}/delegations", principal.voter_id);
+
+    let all = app.list_all(&format!("{delegations}?order=asc"), Some(&principal), 1).await;
+    assert_eq!(all.len(), 3);
+    assert_eq!(all, app.list_all(&format!("{delegations}?order=asc"), Some(&principal), 500).await);
+
+    let on_taxes = app.list_all(&format!("{delegations}?topic={taxes}"), Some(&principal), 50).await;
+    assert_eq!(on_taxes.len(), 1);
+    assert_eq!(on_taxes[0]["topics"], json!([taxes]));
+    let to_second = app
+        .list_all(&format!("{delegations}?delegate={}", second.voter_id), Some(&principal), 1)
+        .await;
+    assert_eq!(to_second.len(), 2);
+    assert!(to_second.iter().all(|delegation| delegation["to_delegate"] == json!(second.voter_id)));
+
+    // Delegates see the delegations they received.
+    let received = app
+        .list_all(&format!("/api/v1/voters/{}/delegations?order=asc", first.voter_id), Some(&first), 50)
+        .await;
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0]["from_principal"], json!(principal.voter_id));
+}
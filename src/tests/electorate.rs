@@ -0,0 +1,84 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use time::OffsetDateTime;
+
+use super::{answer, question, Account, TestApp};
+use crate::{db, Role};
+
+/// Makes `account` registered `days` ago.
+async fn backdate(app: &TestApp, account: &Account, days: i64) {
+    sqlx::query("UPDATE events SET occurred_at = $1 WHERE event_type = 'VoterCreated' AND voter_id = $2")
+        .bind(db::format_timestamp(OffsetDateTime::now_utc() - time::Duration::days(days)))
+        .bind(account.voter_id.to_string())
+        .execute(&app.state.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn only_the_electorate_votes_and_counts_in_the_turnout() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let member = app.register_verified("Grace").await;
+    let minor = app.register_verified("Alan").await;
+    let outsider = app.register_verified("Edsger").await;
+    let newcomer = app.register_verified("Barbara").await;
+    let excluded = app.register_verified("Donald").await;
+    for account in [&member, &minor, &outsider, &excluded] {
+        backdate(&app, account, 60).await;
+    }
+    let twelve_years_ago = (OffsetDateTime::now_utc() - time::Duration::days(12 * 365)).date();
+    sqlx::query("UPDATE voters SET birth_date = $1 WHERE voter_id = $2")
+        .bind(twelve_years_ago.to_string())
+        .bind(minor.voter_id.to_string())
+        .execute(&app.state.pool)
+        .await
+        .unwrap();
+
+    let group = app.post("/api/v1/groups", Some(&admin), json!({ "name": "board" })).await;
+    assert_eq!(group.status, StatusCode::CREATED, "{}", group.body);
+    let members = [&member, &minor, &newcomer, &excluded].map(|account| account.voter_id);
+    let imported = app
+        .post("/api/v1/groups/board/members", Some(&admin), json!({ "voter_ids": members }))
+        .await;
+    assert_eq!(imported.status, StatusCode::OK, "{}", imported.body);
+
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(
+            &admin,
+            json!({
+                "eligibility": {
+                    "min_age": 18,
+                    "groups": ["board"],
+                    "min_account_age_days": 30,
+                    "exclude": [excluded.voter_id],
+                },
+            }),
+            vec![question("Referendum", topic, &["Yes", "No"])],
+        )
+        .await;
+    let participation = app.get(&format!("/api/v1/polls/{poll_id}/participation"), None).await;
+    assert_eq!(participation.body["eligible"], 1, "{}", participation.body);
+
+    // Joining once the poll is open is too late.
+    let late = app.register_verified("Frances").await;
+    backdate(&app, &late, 60).await;
+    let imported = app
+        .post("/api/v1/groups/board/members", Some(&admin), json!({ "voter_ids": [late.voter_id] }))
+        .await;
+    assert_eq!(imported.status, StatusCode::OK, "{}", imported.body);
+
+    for voter in [&admin, &minor, &outsider, &newcomer, &excluded, &late] {
+        let refused = app.vote(voter, poll_id, json!([answer("No", 1)])).await;
+        assert_eq!(refused.status, StatusCode::FORBIDDEN, "{}", refused.body);
+    }
+    let ballot = app.vote(&member, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+
+    let participation = app.get(&format!("/api/v1/polls/{poll_id}/participation"), None).await;
+    assert_eq!(participation.body["ballots"], 1);
+    assert_eq!(participation.body["eligible"], 1);
+    assert_eq!(participation.body["turnout"], 1.0);
+}
@@ -0,0 +1,57 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{answer, question, Account, TestApp};
+use crate::Role;
+
+/// The ballots in the audit log of poll `poll_id`.
+async fn logged_ballots(app: &TestApp, auditor: &Account, poll_id: Uuid) -> Vec<Value> {
+    let response = app.get(&format!("/api/v1/events?poll_id={poll_id}"), Some(auditor)).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    // One event per line, which the harness cannot read as a single JSON.
+    let lines = match response.body {
+        Value::String(lines) => lines,
+        event => format!("{event}\n"),
+    };
+    lines
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|event| event["type"] == "BallotCast")
+        .collect()
+}
+
+#[tokio::test]
+async fn secret_ballots_stay_out_of_the_log_until_the_poll_closes() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let secret = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let public = app
+        .open_poll(
+            &admin,
+            json!({ "results_visibility": "Public" }),
+            vec![question("Referendum", topic, &["Yes", "No"])],
+        )
+        .await;
+    for poll_id in [secret, public] {
+        let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+        assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    }
+
+    let ballots = logged_ballots(&app, &admin, secret).await;
+    assert_eq!(ballots.len(), 1);
+    assert_eq!(ballots[0]["data"]["user_id"], json!(voter.voter_id));
+    assert_eq!(ballots[0]["data"]["vote"], json!([]));
+    let ballots = logged_ballots(&app, &admin, public).await;
+    assert_eq!(ballots[0]["data"]["vote"][0]["vote"], "Yes");
+
+    app.close(&admin, secret).await;
+    let ballots = logged_ballots(&app, &admin, secret).await;
+    assert_eq!(ballots[0]["data"]["vote"][0]["vote"], "Yes");
+}
@@ -0,0 +1,90 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use time::OffsetDateTime;
+
+use super::{answer, question, Account, TestApp};
+use crate::Role;
+
+async fn create_group(app: &TestApp, admin: &Account, name: &str, members: &[&Account]) {
+    let group = app.post("/api/v1/groups", Some(admin), json!({ "name": name })).await;
+    assert_eq!(group.status, StatusCode::CREATED, "{}", group.body);
+    let voter_ids = members.iter().map(|account| account.voter_id).collect::<Vec<_>>();
+    let imported = app
+        .post(&format!("/api/v1/groups/{name}/members"), Some(admin), json!({ "voter_ids": voter_ids }))
+        .await;
+    assert_eq!(imported.status, StatusCode::OK, "{}", imported.body);
+    assert_eq!(imported.body["added"], members.len());
+}
+
+#[tokio::test]
+async fn results_are_broken_down_by_group_and_weighed_by_college() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let workers = [
+        app.register_verified("Grace").await,
+        app.register_verified("Alan").await,
+        app.register_verified("Edsger").await,
+    ];
+    let manager = app.register_verified("Barbara").await;
+    create_group(&app, &admin, "workers", &workers.iter().collect::<Vec<_>>()).await;
+    create_group(&app, &admin, "managers", &[&manager]).await;
+    create_group(&app, &admin, "board", &[&workers[0], &manager]).await;
+
+    // A delegated vote counts in the groups of the voter who delegated.
+    let topic = app.create_topic(&admin, "Budget").await;
+    let end_date = (OffsetDateTime::now_utc() + time::Duration::days(30)).date();
+    let delegated = app
+        .post(
+            &format!("/api/v1/voters/{}/delegations", workers[2].voter_id),
+            Some(&workers[2]),
+            json!({ "to": manager.voter_id, "topics": [topic], "end_date": end_date }),
+        )
+        .await;
+    assert_eq!(delegated.status, StatusCode::CREATED, "{}", delegated.body);
+
+    let referendum = question("Referendum", topic, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app
+        .open_poll(
+            &admin,
+            json!({
+                "breakdown": {
+                    "groups": ["board"],
+                    "colleges": [
+                        { "group": "workers", "share": 0.5 },
+                        { "group": "managers", "share": 0.5 },
+                    ],
+                },
+            }),
+            vec![referendum],
+        )
+        .await;
+    for (voter, choice) in [(&workers[0], "Yes"), (&workers[1], "Yes"), (&manager, "No")] {
+        let ballot = app.vote(voter, poll_id, json!([answer(choice, 1)])).await;
+        assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    }
+
+    app.close(&admin, poll_id).await;
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([2.0, 1.0]));
+    assert_eq!(results["delegated_results"], json!([0.0, 1.0]));
+
+    let groups = results["groups"].as_array().unwrap();
+    let group = |name: &str| groups.iter().find(|group| group["group"] == name).unwrap();
+    assert_eq!(groups.len(), 3);
+    assert_eq!(group("board")["ballots"], 2);
+    assert_eq!(group("board")["vote_results"], json!([1.0, 1.0]));
+    assert_eq!(group("board")["delegated_results"], json!([0.0, 0.0]));
+    assert_eq!(group("workers")["ballots"], 2);
+    assert_eq!(group("workers")["vote_results"], json!([2.0, 0.0]));
+    assert_eq!(group("workers")["delegated_results"], json!([0.0, 1.0]));
+    assert_eq!(group("managers")["vote_results"], json!([0.0, 1.0]));
+
+    // Workers split 2 to 1 and managers 0 to 1, each college counting half.
+    let college_results = results["college_results"].as_array().unwrap();
+    assert_eq!(college_results.len(), 2);
+    for (share, expected) in college_results.iter().zip([1.0 / 3.0, 2.0 / 3.0]) {
+        assert!((share.as_f64().unwrap() - expected).abs() < 1e-9, "{college_results:?}");
+    }
+}
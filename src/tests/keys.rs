@@ -0,0 +1,102 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use time::{Date, Month};
+
+use super::{question, Account, TestApp};
+use crate::Role;
+
+/// Issues a key with `scopes` as `admin` in the default organization, and
+/// returns its id and secret.
+async fn issue(app: &TestApp, admin: &Account, scopes: &[&str]) -> (String, String) {
+    let response = app
+        .post("/api/v1/api-keys", Some(admin), json!({ "name": "Directory sync", "scopes": scopes }))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+
+    (
+        response.body["key_id"].as_str().unwrap().to_owned(),
+        response.body["secret"].as_str().unwrap().to_owned(),
+    )
+}
+
+fn new_voter(first_name: &str) -> Value {
+    json!({
+        "first_name": first_name,
+        "last_name": "Test",
+        "email": format!("{}-{}@example.org", first_name.to_lowercase(), uuid::Uuid::new_v4().simple()),
+        "birth_date": Date::from_calendar_date(1990, Month::January, 1).unwrap(),
+        "status": "Public",
+    })
+}
+
+#[tokio::test]
+async fn keys_only_do_what_their_scopes_allow() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let (_, secret) = issue(&app, &admin, &["voters:write"]).await;
+
+    let created = app
+        .request_with_key(Method::POST, "/api/v1/voters", &secret, Some(new_voter("Grace")))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+
+    for uri in [
+        format!("/api/v1/polls/{poll_id}/results"),
+        format!("/api/v1/polls/{poll_id}/participation"),
+    ] {
+        let refused = app.request_with_key(Method::GET, &uri, &secret, None).await;
+        assert_eq!(refused.status, StatusCode::FORBIDDEN, "{uri}: {}", refused.body);
+    }
+    // Without a key, results stay public.
+    let results = app.get(&format!("/api/v1/polls/{poll_id}/results"), None).await;
+    assert_eq!(results.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn revoked_keys_are_refused() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let (key_id, secret) = issue(&app, &admin, &["voters:write"]).await;
+    let voters = app.request_with_key(Method::GET, "/api/v1/voters", &secret, None).await;
+    assert_eq!(voters.status, StatusCode::OK, "{}", voters.body);
+
+    let revoked = app
+        .request(Method::DELETE, &format!("/api/v1/api-keys/{key_id}"), Some(&admin), None)
+        .await;
+    assert_eq!(revoked.status, StatusCode::NO_CONTENT, "{}", revoked.body);
+
+    let voters = app.request_with_key(Method::GET, "/api/v1/voters", &secret, None).await;
+    assert_eq!(voters.status, StatusCode::UNAUTHORIZED, "{}", voters.body);
+    let created = app
+        .request_with_key(Method::POST, "/api/v1/voters", &secret, Some(new_voter("Grace")))
+        .await;
+    assert_eq!(created.status, StatusCode::UNAUTHORIZED, "{}", created.body);
+}
+
+#[tokio::test]
+async fn keys_only_work_in_their_organization() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    app.create_organization(&admin, "assoc").await;
+    let (_, secret) = issue(&app, &admin, &["voters:write", "polls:read", "results:read"]).await;
+
+    for uri in ["/o/assoc/api/v1/voters", "/o/assoc/api/v1/polls"] {
+        let refused = app.request_with_key(Method::GET, uri, &secret, None).await;
+        assert_eq!(refused.status, StatusCode::UNAUTHORIZED, "{uri}: {}", refused.body);
+    }
+    let created = app
+        .request_with_key(Method::POST, "/o/assoc/api/v1/voters", &secret, Some(new_voter("Grace")))
+        .await;
+    assert_eq!(created.status, StatusCode::UNAUTHORIZED, "{}", created.body);
+
+    // The admin's own session still works there.
+    let voters = app.get("/o/assoc/api/v1/voters", Some(&admin)).await;
+    assert_eq!(voters.status, StatusCode::OK, "{}", voters.body);
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use axum::body::{Body, BodyDataStream};
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tokio_stream::StreamExt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+use super::{answer, question, TestApp};
+use crate::Role;
+
+/// How long an update may take to arrive, throttling included.
+const PATIENCE: Duration = Duration::from_secs(5);
+
+/// A client reading `update` events from the live endpoint of a poll.
+struct EventStream {
+    body: BodyDataStream,
+    buffer: String,
+}
+
+impl EventStream {
+    async fn open(app: &TestApp, poll_id: Uuid) -> Self {
+        let response = app
+            .open(Request::get(format!("/api/v1/polls/{poll_id}/live")).body(Body::empty()).unwrap())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+
+        EventStream {
+            body: response.into_body().into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    async fn next_update(&mut self) -> Value {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let event = self.buffer[..end].to_owned();
+                self.buffer.drain(..end + 2);
+                if !event.lines().any(|line| line == "event: update") {
+                    continue;
+                }
+                let data = event.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+                return serde_json::from_str(data).unwrap();
+            }
+            let chunk = tokio::time::timeout(PATIENCE, self.body.next())
+                .await
+                .expect("No update arrived")
+                .expect("The stream ended")
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+/// The next update sent over `socket`.
+async fn next_message(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> Value {
+    let message = tokio::time::timeout(PATIENCE, socket.next())
+        .await
+        .expect("No update arrived")
+        .expect("The socket closed")
+        .unwrap();
+    match message {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        other => panic!("Unexpected message {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn watchers_of_a_public_poll_see_running_results() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(
+            &admin,
+            json!({ "results_visibility": "Public" }),
+            vec![question("Referendum", topic, &["Yes", "No"])],
+        )
+        .await;
+
+    let mut screens = [EventStream::open(&app, poll_id).await, EventStream::open(&app, poll_id).await];
+    for screen in &mut screens {
+        let update = screen.next_update().await;
+        assert_eq!(update["participation"], 0);
+        assert_eq!(update["results"][0]["vote_results"], json!([0.0, 0.0]));
+    }
+
+    let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    for screen in &mut screens {
+        let update = screen.next_update().await;
+        assert_eq!(update["poll_id"], json!(poll_id));
+        assert_eq!(update["participation"], 1);
+        assert_eq!(update["results"][0]["vote_results"], json!([1.0, 0.0]));
+    }
+
+    let unknown = app.get(&format!("/api/v1/polls/{}/live", Uuid::new_v4()), None).await;
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn watchers_of_a_secret_poll_only_see_participation() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let address = app.serve().await;
+    let url = format!("ws://{address}/api/v1/polls/{poll_id}/live");
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let mut screen = EventStream::open(&app, poll_id).await;
+
+    let update = next_message(&mut socket).await;
+    assert_eq!(update, json!({ "poll_id": poll_id, "participation": 0 }));
+    assert_eq!(screen.next_update().await, update);
+
+    let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    let update = next_message(&mut socket).await;
+    assert_eq!(update, json!({ "poll_id": poll_id, "participation": 1 }));
+    assert_eq!(screen.next_update().await, update);
+}
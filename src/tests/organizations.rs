@@ -0,0 +1,59 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use time::{Date, Month};
+
+use super::TestApp;
+use crate::Role;
+
+#[tokio::test]
+async fn admins_cannot_enroll_voters_of_another_organization() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    app.create_organization(&admin, "assoc").await;
+    let voter = app.register("Grace").await;
+
+    let response = app
+        .post(
+            "/o/assoc/api/v1/voters",
+            Some(&admin),
+            json!({
+                "first_name": "Someone",
+                "last_name": "Else",
+                "email": voter.email,
+                "birth_date": Date::from_calendar_date(1990, Month::January, 1).unwrap(),
+                "status": "Public",
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    // Nothing tells the address belongs to a voter of another organization.
+    let member = app
+        .post(
+            "/api/v1/voters",
+            Some(&admin),
+            json!({
+                "first_name": "Grace",
+                "last_name": "Test",
+                "email": voter.email,
+                "birth_date": Date::from_calendar_date(1990, Month::January, 1).unwrap(),
+                "status": "Public",
+            }),
+        )
+        .await;
+    assert_eq!(member.status, StatusCode::CONFLICT);
+    assert_eq!(response.body["detail"], member.body["detail"]);
+    assert!(!response.body.to_string().contains(&voter.voter_id.to_string()));
+
+    let voters = app.get("/o/assoc/api/v1/voters", Some(&admin)).await;
+    assert_eq!(voters.status, StatusCode::OK, "{}", voters.body);
+    let emails = voters.body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|voter| voter["email"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert!(!emails.contains(&voter.email.as_str()));
+    let lookup = app.get(&format!("/o/assoc/api/v1/voters/{}", voter.voter_id), Some(&admin)).await;
+    assert_eq!(lookup.status, StatusCode::NOT_FOUND);
+}
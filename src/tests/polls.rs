@@ -0,0 +1,238 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::{answer, question, TestApp};
+use crate::Role;
+
+#[tokio::test]
+async fn poll_creators_create_polls_others_cannot() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+
+    let poll_id = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let poll = app.get(&format!("/api/v1/polls/{poll_id}"), None).await;
+    assert_eq!(poll.status, StatusCode::OK);
+    assert_eq!(poll.body["questions"][0]["vote_options"][1]["vote"], "No");
+
+    let refused = app
+        .post(
+            "/api/v1/polls",
+            Some(&voter),
+            json!({
+                "poll_id": uuid::Uuid::new_v4(),
+                "poll_opening_time": "2030-01-01T00:00:00Z",
+                "poll_closing_time": "2030-01-02T00:00:00Z",
+                "questions": [],
+            }),
+        )
+        .await;
+    assert_eq!(refused.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn ballots_are_counted_once_the_poll_closes() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voters = [
+        app.register_verified("Grace").await,
+        app.register_verified("Alan").await,
+        app.register_verified("Edsger").await,
+    ];
+    let unverified = app.register("Barbara").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let referendum = question("Referendum", topic, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app.open_poll(&admin, json!({}), vec![referendum]).await;
+
+    for (voter, choice) in voters.iter().zip(["Yes", "Yes", "No"]) {
+        let ballot = app.vote(voter, poll_id, json!([answer(choice, 1)])).await;
+        assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    }
+    let refused = app.vote(&unverified, poll_id, json!([answer("No", 1)])).await;
+    assert_eq!(refused.status, StatusCode::FORBIDDEN);
+
+    let participation = app.get(&format!("/api/v1/polls/{poll_id}/participation"), None).await;
+    assert_eq!(participation.body["ballots"], 3);
+
+    app.close(&admin, poll_id).await;
+    let late = app.vote(&voters[0], poll_id, json!([answer("No", 1)])).await;
+    assert_eq!(late.status, StatusCode::CONFLICT);
+
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([2.0, 1.0]));
+    assert_eq!(results["raw_vote_results"], json!([2, 1]));
+    assert_eq!(results["report"]["ballots"], 3);
+}
+
+#[tokio::test]
+async fn results_are_published_when_the_poll_closes() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+
+    let results = app.get(&format!("/api/v1/polls/{poll_id}/results"), None).await;
+    assert_eq!(results.status, StatusCode::OK);
+    assert_eq!(results.body["items"], json!([]));
+
+    app.close(&admin, poll_id).await;
+    let results = app.get(&format!("/api/v1/polls/{poll_id}/results"), None).await;
+    assert_eq!(results.body["items"][0]["vote_results"], json!([1.0, 0.0]));
+}
+
+#[tokio::test]
+async fn a_second_ballot_is_refused_and_not_counted() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let referendum = question("Referendum", topic, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app.open_poll(&admin, json!({}), vec![referendum]).await;
+
+    let first = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(first.status, StatusCode::CREATED, "{}", first.body);
+    for _ in 0..2 {
+        let again = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+        assert_eq!(again.status, StatusCode::CONFLICT, "{}", again.body);
+    }
+
+    let participation = app.get(&format!("/api/v1/polls/{poll_id}/participation"), None).await;
+    assert_eq!(participation.body["ballots"], 1);
+    app.close(&admin, poll_id).await;
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([1.0, 0.0]));
+}
+
+#[tokio::test]
+async fn quadratic_ballots_stay_within_the_budget() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(
+            &admin,
+            json!({ "credits": 10 }),
+            vec![question("QuadraticVoting", topic, &["Parks", "Roads"])],
+        )
+        .await;
+
+    for vote in [
+        json!([answer("Parks", 3), answer("Roads", 2)]),
+        json!([answer("Parks", u32::MAX), answer("Roads", u32::MAX)]),
+    ] {
+        let refused = app.vote(&voter, poll_id, vote).await;
+        assert_eq!(refused.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", refused.body);
+    }
+    let ballot = app.vote(&voter, poll_id, json!([answer("Parks", 3)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+}
+
+#[tokio::test]
+async fn answers_count_for_the_question_they_name() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let first = question("Referendum", topic, &["Yes", "No"]);
+    let second = question("Referendum", topic, &["Yes", "No"]);
+    let (first_id, second_id) = (first["question_id"].clone(), second["question_id"].clone());
+    let poll_id = app.open_poll(&admin, json!({}), vec![first, second]).await;
+
+    for vote in [json!([answer("Yes", 1)]), json!([answer("Maybe", 1)])] {
+        let refused = app.vote(&voter, poll_id, vote).await;
+        assert_eq!(refused.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", refused.body);
+    }
+    let mut yes = answer("Yes", 1);
+    yes["question_id"] = first_id.clone();
+    let mut no = answer("No", 1);
+    no["question_id"] = second_id.clone();
+    let ballot = app.vote(&voter, poll_id, json!([yes, no])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+
+    app.close(&admin, poll_id).await;
+    let first = app.results(poll_id, first_id.as_str().unwrap().parse().unwrap()).await;
+    let second = app.results(poll_id, second_id.as_str().unwrap().parse().unwrap()).await;
+    assert_eq!(first["vote_results"], json!([1.0, 0.0]));
+    assert_eq!(second["vote_results"], json!([0.0, 1.0]));
+}
+
+#[tokio::test]
+async fn polls_are_listed_page_by_page_by_topic_state_and_date() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let budget = app.create_topic(&admin, "Budget").await;
+    let roads = app.create_topic(&admin, "Roads").await;
+    let open = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", budget, &["Yes", "No"])])
+        .await;
+    let closed = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", budget, &["Yes", "No"])])
+        .await;
+    app.close(&admin, closed).await;
+    app.open_poll(&admin, json!({}), vec![question("Referendum", roads, &["Yes", "No"])])
+        .await;
+    let upcoming = uuid::Uuid::new_v4();
+    let mut referendum = question("Referendum", budget, &["Yes", "No"]);
+    referendum["poll_id"] = json!(upcoming);
+    let created = app
+        .post(
+            "/api/v1/polls",
+            Some(&admin),
+            json!({
+                "poll_id": upcoming,
+                "poll_opening_time": "2030-01-01T00:00:00Z",
+                "poll_closing_time": "2030-01-02T00:00:00Z",
+                "questions": [referendum],
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+
+    let ids = |polls: Vec<serde_json::Value>| {
+        polls.into_iter().map(|poll| poll["poll_id"].as_str().unwrap().to_owned()).collect::<Vec<_>>()
+    };
+    let by_opening = ids(app.list_all(&format!("/api/v1/polls?topic={budget}"), None, 1).await);
+    assert_eq!(by_opening, ids(app.list_all(&format!("/api/v1/polls?topic={budget}"), None, 500).await));
+    assert!(by_opening.contains(&open.to_string()) && by_opening.contains(&closed.to_string()));
+    assert_eq!(by_opening[2], upcoming.to_string());
+    let latest_first = ids(app.list_all(&format!("/api/v1/polls?topic={budget}&order=desc"), None, 2).await);
+    assert_eq!(latest_first, by_opening.iter().rev().cloned().collect::<Vec<_>>());
+    let closing_last = ids(
+        app.list_all(&format!("/api/v1/polls?topic={budget}&sort=closing_time&order=desc"), None, 2)
+            .await,
+    );
+    assert_eq!(closing_last[0], upcoming.to_string());
+
+    for (state, poll_id) in [("open", open), ("closed", closed), ("upcoming", upcoming)] {
+        let polls = ids(app.list_all(&format!("/api/v1/polls?topic={budget}&state={state}"), None, 50).await);
+        assert_eq!(polls, [poll_id.to_string()], "{state}");
+    }
+    let later = ids(
+        app.list_all("/api/v1/polls?opens_after=2029-12-31T00:00:00Z&opens_before=2030-06-01T00:00:00Z", None, 50)
+            .await,
+    );
+    assert_eq!(later, [upcoming.to_string()]);
+
+    for query in ["limit=0", "limit=501", "cursor=nonsense", "state=someday"] {
+        let refused = app.get(&format!("/api/v1/polls?{query}"), None).await;
+        assert_eq!(refused.status, StatusCode::UNPROCESSABLE_ENTITY, "{query}: {}", refused.body);
+    }
+}
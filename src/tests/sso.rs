@@ -0,0 +1,356 @@
+//! Single sign-on against a mock identity provider, served locally with its
+//! discovery document, keys and token endpoint.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use base64::{engine::general_purpose::STANDARD, engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use openidconnect::core::{CoreHmacKey, CoreJwsSigningAlgorithm};
+use openidconnect::url::Url;
+use openidconnect::PrivateSigningKey;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use time::{Date, Month, OffsetDateTime};
+use uuid::Uuid;
+
+use super::{TestApp, TestResponse, PUBLIC_URL};
+use crate::events::{self, Event};
+use crate::{oidc, tenant, Role};
+
+const CLIENT_ID: &str = "vote";
+const CLIENT_SECRET: &str = "mock provider secret";
+
+/// An identity provider that logs in whoever the test says, signing ID
+/// tokens with the client secret.
+struct MockProvider {
+    issuer: String,
+    grants: Mutex<HashMap<String, Grant>>,
+}
+
+/// What an authorization code stands for, until redeemed.
+struct Grant {
+    code_challenge: String,
+    nonce: String,
+    claims: Value,
+}
+
+impl MockProvider {
+    async fn start() -> Arc<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = Arc::new(MockProvider {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            grants: Mutex::default(),
+        });
+
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(|| async { Json(json!({ "keys": [] })) }))
+            .route("/token", post(token))
+            .with_state(provider.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        provider
+    }
+
+    /// A server whose default organization logs voters in through this
+    /// provider.
+    async fn app(&self, auto_provision: bool) -> TestApp {
+        let app = TestApp::new().await;
+        let mut organization = tenant::load(&app.state.pool, tenant::DEFAULT_ORGANIZATION).await.unwrap();
+        organization.settings.sso = Some(oidc::Settings {
+            issuer: self.issuer.clone(),
+            client_id: CLIENT_ID.to_owned(),
+            client_secret: Some(CLIENT_SECRET.to_owned()),
+            auto_provision,
+            groups_claim: "groups".to_owned(),
+            role_groups: Vec::new(),
+        });
+        events::record(&app.state.pool, None, &Event::OrganizationUpdated(organization))
+            .await
+            .unwrap();
+
+        app
+    }
+
+    /// Plays the voter logging in at the provider as `claims`, following the
+    /// redirect `start` sent the browser to, and returns the query of the
+    /// callback the provider sends them back to.
+    fn authorize(&self, start: &TestResponse, claims: Value) -> String {
+        assert_eq!(start.status, StatusCode::SEE_OTHER);
+        let url = Url::parse(start.headers[header::LOCATION].to_str().unwrap()).unwrap();
+        assert!(url.as_str().starts_with(&format!("{}/authorize?", self.issuer)), "{url}");
+        let query = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["client_id"], CLIENT_ID);
+        assert_eq!(query["redirect_uri"], format!("{PUBLIC_URL}/api/v1/session/oidc/callback"));
+        assert_eq!(query["code_challenge_method"], "S256");
+
+        let code = Uuid::new_v4().simple().to_string();
+        self.grants.lock().unwrap().insert(
+            code.clone(),
+            Grant {
+                code_challenge: query["code_challenge"].clone(),
+                nonce: query["nonce"].clone(),
+                claims,
+            },
+        );
+
+        format!("code={code}&state={}", query["state"])
+    }
+}
+
+async fn discovery(State(provider): State<Arc<MockProvider>>) -> Json<Value> {
+    let issuer = &provider.issuer;
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{issuer}/authorize"),
+        "token_endpoint": format!("{issuer}/token"),
+        "jwks_uri": format!("{issuer}/jwks"),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["HS256"],
+    }))
+}
+
+/// Redeems a code for an ID token, provided the client authenticates and
+/// proves it started the login.
+async fn token(
+    State(provider): State<Arc<MockProvider>>,
+    headers: HeaderMap,
+    Form(form): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let invalid = |error: &str| (StatusCode::BAD_REQUEST, Json(json!({ "error": error })));
+
+    // Client credentials are form-encoded before going into Basic auth.
+    let credentials = format!("{CLIENT_ID}:{}", CLIENT_SECRET.replace(' ', "+"));
+    let credentials = format!("Basic {}", STANDARD.encode(credentials));
+    if headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()) != Some(credentials.as_str()) {
+        return invalid("invalid_client");
+    }
+    let Some(grant) = form.get("code").and_then(|code| provider.grants.lock().unwrap().remove(code)) else {
+        return invalid("invalid_grant");
+    };
+    let verifier = form.get("code_verifier").map(String::as_str).unwrap_or_default();
+    if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != grant.code_challenge {
+        return invalid("invalid_grant");
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let mut claims = json!({
+        "iss": provider.issuer,
+        "aud": CLIENT_ID,
+        "iat": now,
+        "exp": now + 300,
+        "nonce": grant.nonce,
+    });
+    if let (Value::Object(claims), Value::Object(identity)) = (&mut claims, grant.claims) {
+        claims.extend(identity);
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "access_token": Uuid::new_v4().simple().to_string(),
+            "token_type": "Bearer",
+            "expires_in": 300,
+            "id_token": id_token(&claims),
+        })),
+    )
+}
+
+fn id_token(claims: &Value) -> String {
+    let message = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(json!({ "alg": "HS256", "typ": "JWT" }).to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string()),
+    );
+    let signature = CoreHmacKey::new(CLIENT_SECRET)
+        .sign(&CoreJwsSigningAlgorithm::HmacSha256, message.as_bytes())
+        .unwrap();
+
+    format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// The claims of the voter `subject` of the provider.
+fn identity(subject: &str, email: &str, email_verified: bool) -> Value {
+    json!({
+        "sub": subject,
+        "email": email,
+        "email_verified": email_verified,
+        "given_name": "Barbara",
+        "family_name": "Liskov",
+        "birthdate": "1939-11-07",
+    })
+}
+
+/// Logs in at the server as the voter `claims` describes, returning the
+/// callback's response.
+async fn log_in(app: &TestApp, provider: &MockProvider, claims: Value) -> TestResponse {
+    log_in_at(app, provider, "", claims).await
+}
+
+/// Logs in to the organization the path prefix `base` names.
+async fn log_in_at(app: &TestApp, provider: &MockProvider, base: &str, claims: Value) -> TestResponse {
+    let start = app.get(&format!("{base}/api/v1/session/oidc"), None).await;
+    let state_cookie = start.session_cookie().expect("No state cookie");
+    let callback = provider.authorize(&start, claims);
+
+    app.get_with_cookie(&format!("/api/v1/session/oidc/callback?{callback}"), &state_cookie)
+        .await
+}
+
+/// The voter a session cookie logs in as.
+async fn session(app: &TestApp, cookie: &str) -> Value {
+    let response = app.get_with_cookie("/api/v1/session", cookie).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body
+}
+
+#[tokio::test]
+async fn a_first_login_registers_the_voter_and_later_ones_find_them() {
+    let provider = MockProvider::start().await;
+    let app = provider.app(true).await;
+    let email = format!("barbara-{}@example.org", Uuid::new_v4().simple());
+
+    let first = log_in(&app, &provider, identity("barbara", &email, true)).await;
+    assert_eq!(first.status, StatusCode::SEE_OTHER, "{}", first.body);
+    assert_eq!(first.headers[header::LOCATION], "/");
+    let voter = session(&app, &first.session_cookie().unwrap()).await;
+    assert_eq!(voter["email"], json!(email));
+    assert_eq!(voter["first_name"], "Barbara");
+    assert!(voter["email_verified_at"].is_string());
+
+    let again = log_in(&app, &provider, identity("barbara", &email, true)).await;
+    assert_eq!(again.status, StatusCode::SEE_OTHER, "{}", again.body);
+    let same = session(&app, &again.session_cookie().unwrap()).await;
+    assert_eq!(same["voter_id"], voter["voter_id"]);
+}
+
+#[tokio::test]
+async fn a_verified_address_links_the_voter_registered_with_it() {
+    let provider = MockProvider::start().await;
+    let app = provider.app(false).await;
+    let registered = app.register("Barbara").await;
+
+    // Without auto-provisioning, an unverified address matches no one.
+    let unverified = log_in(&app, &provider, identity("barbara", &registered.email, false)).await;
+    assert_eq!(unverified.status, StatusCode::FORBIDDEN, "{}", unverified.body);
+
+    let verified = log_in(&app, &provider, identity("barbara", &registered.email, true)).await;
+    assert_eq!(verified.status, StatusCode::SEE_OTHER, "{}", verified.body);
+    let voter = session(&app, &verified.session_cookie().unwrap()).await;
+    assert_eq!(voter["voter_id"], json!(registered.voter_id));
+    assert!(voter["email_verified_at"].is_string());
+}
+
+#[tokio::test]
+async fn a_callback_must_come_back_to_the_browser_that_started_the_login() {
+    let provider = MockProvider::start().await;
+    let app = provider.app(true).await;
+    let email = format!("barbara-{}@example.org", Uuid::new_v4().simple());
+
+    let start = app.get("/api/v1/session/oidc", None).await;
+    let callback = provider.authorize(&start, identity("barbara", &email, true));
+    let elsewhere = app
+        .get_with_cookie(&format!("/api/v1/session/oidc/callback?{callback}"), "oidc_state=another")
+        .await;
+    assert_eq!(elsewhere.status, StatusCode::UNAUTHORIZED);
+    assert!(elsewhere.session_cookie().is_none());
+
+    // A state the server never issued is refused even when the cookie
+    // agrees with it.
+    let forged = app
+        .get_with_cookie("/api/v1/session/oidc/callback?code=stolen&state=forged", "oidc_state=forged")
+        .await;
+    assert_eq!(forged.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn an_id_token_for_another_login_is_refused() {
+    let provider = MockProvider::start().await;
+    let app = provider.app(true).await;
+    let email = format!("barbara-{}@example.org", Uuid::new_v4().simple());
+
+    let mut claims = identity("barbara", &email, true);
+    claims["nonce"] = json!("replayed");
+    let replayed = log_in(&app, &provider, claims).await;
+    assert_eq!(replayed.status, StatusCode::UNAUTHORIZED, "{}", replayed.body);
+    assert!(replayed.body["detail"].as_str().unwrap().contains("nonce"), "{}", replayed.body);
+    assert!(replayed.session_cookie().is_none());
+}
+
+#[tokio::test]
+async fn a_provider_only_grants_membership_and_roles_where_it_is_trusted() {
+    let provider = MockProvider::start().await;
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    app.create_organization(&admin, "alpha").await;
+    app.create_organization(&admin, "beta").await;
+    let grace = format!("grace-{}@example.org", Uuid::new_v4().simple());
+    let member_of_beta = app
+        .post(
+            "/o/beta/api/v1/voters",
+            Some(&admin),
+            json!({
+                "first_name": "Grace",
+                "last_name": "Hopper",
+                "email": grace,
+                "birth_date": Date::from_calendar_date(1906, Month::December, 9).unwrap(),
+                "status": "Public",
+            }),
+        )
+        .await;
+    assert_eq!(member_of_beta.status, StatusCode::CREATED, "{}", member_of_beta.body);
+
+    let trusted = app
+        .request(
+            Method::PATCH,
+            "/o/alpha/api/v1/organization",
+            Some(&admin),
+            Some(json!({
+                "settings": {
+                    "sso": {
+                        "issuer": provider.issuer,
+                        "client_id": CLIENT_ID,
+                        "client_secret": CLIENT_SECRET,
+                        "auto_provision": true,
+                        "role_groups": [{ "group": "vote-admins", "role": "Admin" }],
+                    },
+                },
+            })),
+        )
+        .await;
+    assert_eq!(trusted.status, StatusCode::OK, "{}", trusted.body);
+    assert!(trusted.body["settings"]["sso"].get("client_secret").is_none());
+
+    // Beta trusts no provider.
+    let start = app.get("/o/beta/api/v1/session/oidc", None).await;
+    assert_eq!(start.status, StatusCode::NOT_FOUND);
+
+    let email = format!("barbara-{}@example.org", Uuid::new_v4().simple());
+    let mut claims = identity("barbara", &email, true);
+    claims["groups"] = json!(["vote-admins"]);
+    let login = log_in_at(&app, &provider, "/o/alpha", claims).await;
+    assert_eq!(login.status, StatusCode::SEE_OTHER, "{}", login.body);
+    let cookie = login.session_cookie().unwrap();
+
+    let organizations = app.get_with_cookie("/api/v1/organizations", &cookie).await;
+    let slugs = organizations.body.as_array().unwrap().iter().map(|organization| &organization["slug"]);
+    assert_eq!(slugs.collect::<Vec<_>>(), [&json!("alpha")]);
+    let alpha = app.get_with_cookie("/o/alpha/api/v1/voters", &cookie).await;
+    assert_eq!(alpha.status, StatusCode::OK, "{}", alpha.body);
+    let beta = app.get_with_cookie("/o/beta/api/v1/voters", &cookie).await;
+    assert_eq!(beta.status, StatusCode::FORBIDDEN, "{}", beta.body);
+
+    // Nor can alpha's provider vouch for the address of a voter of beta.
+    let claimed = log_in_at(&app, &provider, "/o/alpha", identity("grace", &grace, true)).await;
+    assert_ne!(claimed.status, StatusCode::SEE_OTHER, "{}", claimed.body);
+    assert!(claimed.session_cookie().is_none());
+}
@@ -0,0 +1,127 @@
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+use totp_rs::TOTP;
+
+use super::{answer, question, Account, TestApp, TestResponse};
+use crate::Role;
+
+/// Sets up TOTP for `account`, confirming with the code of the current
+/// step, and returns the authenticator and the recovery codes.
+async fn enroll(app: &TestApp, account: &Account) -> (TOTP, Vec<String>) {
+    let setup = app.post("/api/v1/accounts/totp", Some(account), json!({})).await;
+    assert_eq!(setup.status, StatusCode::OK, "{}", setup.body);
+    let authenticator = TOTP::from_url(setup.body["otpauth_url"].as_str().unwrap()).unwrap();
+
+    let confirmed = app
+        .post(
+            "/api/v1/accounts/totp/confirm",
+            Some(account),
+            json!({ "code": authenticator.generate_current().unwrap() }),
+        )
+        .await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    let recovery_codes = serde_json::from_value(confirmed.body["recovery_codes"].clone()).unwrap();
+
+    (authenticator, recovery_codes)
+}
+
+/// The code of the next step, which the server accepts as clock skew.
+fn next_code(authenticator: &TOTP) -> String {
+    let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+    authenticator.generate(now + authenticator.step)
+}
+
+async fn post_with_code(
+    app: &TestApp,
+    uri: &str,
+    account: &Account,
+    code: &str,
+    body: Value,
+) -> TestResponse {
+    let request = Request::post(uri)
+        .header(header::COOKIE, &account.cookie)
+        .header("X-Second-Factor", code)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    app.send(request).await
+}
+
+#[tokio::test]
+async fn enrolled_voters_vote_and_delegate_with_a_fresh_code() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let delegate = app.register_verified("Alan").await;
+    let topic = app.create_topic(&admin, "Budget").await;
+    let poll_id = app
+        .open_poll(&admin, json!({}), vec![question("Referendum", topic, &["Yes", "No"])])
+        .await;
+    let ballots = format!("/api/v1/polls/{poll_id}/ballots");
+    let delegations = format!("/api/v1/voters/{}/delegations", voter.voter_id);
+    let end_date = (OffsetDateTime::now_utc() + time::Duration::days(30)).date();
+    let delegation = json!({ "to": delegate.voter_id, "topics": [topic], "end_date": end_date });
+    let (authenticator, _) = enroll(&app, &voter).await;
+
+    let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::UNAUTHORIZED, "{}", ballot.body);
+    let delegated = app.post(&delegations, Some(&voter), delegation.clone()).await;
+    assert_eq!(delegated.status, StatusCode::UNAUTHORIZED, "{}", delegated.body);
+
+    let code = next_code(&authenticator);
+    let ballot = post_with_code(&app, &ballots, &voter, &code, json!({ "vote": [answer("Yes", 1)] })).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+    // The code was used up by the ballot.
+    let delegated = post_with_code(&app, &delegations, &voter, &code, delegation).await;
+    assert_eq!(delegated.status, StatusCode::UNAUTHORIZED, "{}", delegated.body);
+
+    let participation = app.get(&format!("/api/v1/polls/{poll_id}/participation"), None).await;
+    assert_eq!(participation.body["ballots"], 1);
+}
+
+#[tokio::test]
+async fn a_recovery_code_works_once() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let delegate = app.register_verified("Alan").await;
+    let budget = app.create_topic(&admin, "Budget").await;
+    let roads = app.create_topic(&admin, "Roads").await;
+    let delegations = format!("/api/v1/voters/{}/delegations", voter.voter_id);
+    let end_date = (OffsetDateTime::now_utc() + time::Duration::days(30)).date();
+    let (_, recovery_codes) = enroll(&app, &voter).await;
+
+    let delegated = post_with_code(
+        &app,
+        &delegations,
+        &voter,
+        &recovery_codes[0],
+        json!({ "to": delegate.voter_id, "topics": [budget], "end_date": end_date }),
+    )
+    .await;
+    assert_eq!(delegated.status, StatusCode::CREATED, "{}", delegated.body);
+
+    let again = post_with_code(
+        &app,
+        &delegations,
+        &voter,
+        &recovery_codes[0],
+        json!({ "to": delegate.voter_id, "topics": [roads], "end_date": end_date }),
+    )
+    .await;
+    assert_eq!(again.status, StatusCode::UNAUTHORIZED, "{}", again.body);
+    let other = post_with_code(
+        &app,
+        &delegations,
+        &voter,
+        &recovery_codes[1],
+        json!({ "to": delegate.voter_id, "topics": [roads], "end_date": end_date }),
+    )
+    .await;
+    assert_eq!(other.status, StatusCode::CREATED, "{}", other.body);
+}
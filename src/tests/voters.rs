@@ -0,0 +1,52 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use time::{Date, Month};
+
+use super::TestApp;
+use crate::Role;
+
+#[tokio::test]
+async fn voters_are_listed_page_by_page_by_status() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    for last_name in ["Zuse", "Babbage", "Lovelace"] {
+        let created = app
+            .post(
+                "/api/v1/voters",
+                Some(&admin),
+                json!({
+                    "first_name": "Test",
+                    "last_name": last_name,
+                    "email": format!("{}@example.org", last_name.to_lowercase()),
+                    "birth_date": Date::from_calendar_date(1990, Month::January, 1).unwrap(),
+                    "status": "Private",
+                }),
+            )
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    }
+
+    let last_names = |voters: &[Value]| {
+        voters.iter().map(|voter| voter["last_name"].as_str().unwrap().to_owned()).collect::<Vec<_>>()
+    };
+    let private = app.list_all("/api/v1/voters?status=Private", Some(&admin), 1).await;
+    assert!(private.iter().all(|voter| voter["status"] == "Private"));
+    assert_eq!(private, app.list_all("/api/v1/voters?status=Private", Some(&admin), 500).await);
+    let names = last_names(&private);
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+    for last_name in ["Zuse", "Babbage", "Lovelace"] {
+        assert!(names.iter().any(|name| name == last_name), "{names:?}");
+    }
+    assert!(!names.iter().any(|name| name == "Test"), "{names:?}");
+
+    let by_email = app
+        .list_all("/api/v1/voters?status=Private&sort=email&order=desc", Some(&admin), 2)
+        .await;
+    let emails = by_email.iter().map(|voter| voter["email"].as_str().unwrap()).collect::<Vec<_>>();
+    let mut descending = emails.clone();
+    descending.sort_by(|a, b| b.cmp(a));
+    assert_eq!(emails, descending);
+}
@@ -0,0 +1,46 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use time::{Duration, OffsetDateTime};
+
+use super::{answer, question, TestApp};
+use crate::events::{self, Event};
+use crate::{tenant, Role, VotingWeight};
+
+#[tokio::test]
+async fn polls_count_with_the_weights_of_their_opening() {
+    let app = TestApp::new().await;
+    let admin = app.register_verified("Ada").await;
+    app.grant(&admin, Role::Admin).await;
+    let voter = app.register_verified("Grace").await;
+    let today = OffsetDateTime::now_utc().date();
+    let weight = VotingWeight {
+        organization_id: tenant::DEFAULT_ORGANIZATION,
+        voter_id: voter.voter_id,
+        weight: 3.0,
+        effective_from: today - Duration::days(30),
+    };
+    events::record(&app.state.pool, None, &Event::VotingWeightSet(weight)).await.unwrap();
+    let weights = format!("/api/v1/voters/{}/weights", voter.voter_id);
+
+    let backdated = app
+        .post(&weights, Some(&admin), json!({ "weight": 10.0, "effective_from": today - Duration::days(1) }))
+        .await;
+    assert_eq!(backdated.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", backdated.body);
+
+    let topic = app.create_topic(&admin, "Budget").await;
+    let referendum = question("Referendum", topic, &["Yes", "No"]);
+    let question_id = referendum["question_id"].as_str().unwrap().parse().unwrap();
+    let poll_id = app.open_poll(&admin, json!({}), vec![referendum]).await;
+    let ballot = app.vote(&voter, poll_id, json!([answer("Yes", 1)])).await;
+    assert_eq!(ballot.status, StatusCode::CREATED, "{}", ballot.body);
+
+    // A weight from today on comes too late for a poll already open.
+    let raised = app.post(&weights, Some(&admin), json!({ "weight": 10.0 })).await;
+    assert_eq!(raised.status, StatusCode::CREATED, "{}", raised.body);
+
+    app.close(&admin, poll_id).await;
+    let results = app.results(poll_id, question_id).await;
+    assert_eq!(results["vote_results"], json!([3.0, 0.0]));
+    assert_eq!(results["raw_vote_results"], json!([1, 0]));
+    assert_eq!(results["report"]["ballots_weight"], 3.0);
+}
@@ -0,0 +1,258 @@
+//! Time-based one-time passwords (RFC 6238) as a second factor.
+//!
+//! A voter enrolls by scanning the `otpauth://` URL of a fresh secret in any
+//! authenticator app and confirming with a first code, which also hands them
+//! ten single-use recovery codes. Codes are checked entirely locally, so
+//! nothing but the server clock is needed. Like passwords, secrets and
+//! recovery codes stay out of the event log.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use time::OffsetDateTime;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::db::{self, Pool};
+use crate::error::ApiError;
+
+const ISSUER: &str = "vote_server";
+const STEP_SECONDS: u64 = 30;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Where a voter stands with the second factor.
+pub struct Enrollment {
+    totp: TOTP,
+    confirmed: bool,
+}
+
+fn totp(secret: Vec<u8>, email: &str) -> Result<TOTP, ApiError> {
+    // Six digits every 30 seconds, one step of clock skew either way: what
+    // every authenticator app expects.
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        STEP_SECONDS,
+        secret,
+        Some(ISSUER.to_owned()),
+        email.to_owned(),
+    )
+    .map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+pub async fn load(pool: &Pool, voter_id: Uuid) -> Result<Option<Enrollment>, ApiError> {
+    let Some(row) = sqlx::query(
+        r#"
+        SELECT totp_enrollments.secret, totp_enrollments.confirmed_at, voters.email
+        FROM totp_enrollments
+        JOIN voters ON voters.voter_id = totp_enrollments.voter_id
+        WHERE totp_enrollments.voter_id = $1
+        "#,
+    )
+    .bind(voter_id.to_string())
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let secret = Secret::Encoded(row.try_get("secret")?)
+        .to_bytes()
+        .map_err(|e| ApiError::Internal(format!("{e:?}")))?;
+
+    Ok(Some(Enrollment {
+        totp: totp(secret, &row.try_get::<String, _>("email")?)?,
+        confirmed: db::optional_timestamp_column(&row, "confirmed_at")?.is_some(),
+    }))
+}
+
+impl Enrollment {
+    pub fn confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn secret(&self) -> String {
+        self.totp.get_secret_base32()
+    }
+
+    /// The URL authenticator apps import, usually shown as a QR code.
+    pub fn otpauth_url(&self) -> String {
+        self.totp.get_url()
+    }
+}
+
+/// Replaces any pending enrollment of `voter_id` with a fresh secret.
+pub async fn start_enrollment(pool: &Pool, voter_id: Uuid, email: &str) -> Result<Enrollment, ApiError> {
+    let secret = Secret::generate_secret()
+        .to_bytes()
+        .map_err(|e| ApiError::Internal(format!("{e:?}")))?;
+    let enrollment = Enrollment {
+        totp: totp(secret, email)?,
+        confirmed: false,
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM totp_enrollments WHERE voter_id = $1")
+        .bind(voter_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO totp_enrollments (voter_id, secret, confirmed_at, last_used_step)
+        VALUES ($1, $2, NULL, 0)
+        "#,
+    )
+    .bind(voter_id.to_string())
+    .bind(enrollment.secret())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(enrollment)
+}
+
+/// Checks a code from the authenticator app, refusing to accept the same
+/// time step twice so an intercepted code cannot be replayed.
+pub async fn check_code(
+    pool: &Pool,
+    voter_id: Uuid,
+    enrollment: &Enrollment,
+    code: &str,
+) -> Result<bool, ApiError> {
+    let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+    check_code_at(pool, voter_id, enrollment, code, now).await
+}
+
+/// [`check_code`] at `now`, in seconds since the epoch.
+async fn check_code_at(
+    pool: &Pool,
+    voter_id: Uuid,
+    enrollment: &Enrollment,
+    code: &str,
+    now: u64,
+) -> Result<bool, ApiError> {
+    // Tries each step the skew allows on its own, to learn which one the
+    // code is for: that step, not the current one, is then used up.
+    let exact = TOTP {
+        skew: 0,
+        ..enrollment.totp.clone()
+    };
+    let current = now / STEP_SECONDS;
+    let Some(step) = [current.saturating_sub(1), current, current + 1]
+        .into_iter()
+        .find(|step| exact.check(code, step * STEP_SECONDS))
+    else {
+        return Ok(false);
+    };
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE totp_enrollments
+        SET last_used_step = $1
+        WHERE voter_id = $2 AND last_used_step < $1
+        "#,
+    )
+    .bind(step as i64)
+    .bind(voter_id.to_string())
+    .execute(pool)
+    .await?;
+
+    Ok(updated.rows_affected() == 1)
+}
+
+/// Confirms the pending enrollment and returns new recovery codes, to be
+/// shown to the voter once.
+pub async fn confirm(pool: &Pool, voter_id: Uuid) -> Result<Vec<String>, ApiError> {
+    sqlx::query("UPDATE totp_enrollments SET confirmed_at = $1 WHERE voter_id = $2")
+        .bind(db::format_timestamp(OffsetDateTime::now_utc()))
+        .bind(voter_id.to_string())
+        .execute(pool)
+        .await?;
+
+    regenerate_recovery_codes(pool, voter_id).await
+}
+
+pub async fn regenerate_recovery_codes(pool: &Pool, voter_id: Uuid) -> Result<Vec<String>, ApiError> {
+    let codes = (0..RECOVERY_CODES)
+        .map(|_| {
+            (0..10)
+                .map(|_| RECOVERY_CODE_ALPHABET[OsRng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM recovery_codes WHERE voter_id = $1")
+        .bind(voter_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    for code in &codes {
+        sqlx::query("INSERT INTO recovery_codes (voter_id, code_hash) VALUES ($1, $2)")
+            .bind(voter_id.to_string())
+            .bind(digest(code))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(codes)
+}
+
+/// Consumes a recovery code of `voter_id`, if it is one.
+pub async fn use_recovery_code(pool: &Pool, voter_id: Uuid, code: &str) -> Result<bool, ApiError> {
+    let deleted = sqlx::query("DELETE FROM recovery_codes WHERE voter_id = $1 AND code_hash = $2")
+        .bind(voter_id.to_string())
+        .bind(digest(&code.trim().to_ascii_uppercase()))
+        .execute(pool)
+        .await?;
+
+    Ok(deleted.rows_affected() == 1)
+}
+
+pub async fn disable(pool: &Pool, voter_id: Uuid) -> Result<(), ApiError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM recovery_codes WHERE voter_id = $1")
+        .bind(voter_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM totp_enrollments WHERE voter_id = $1")
+        .bind(voter_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn digest(code: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestApp;
+
+    #[tokio::test]
+    async fn a_code_cannot_be_replayed_in_a_later_step() {
+        let app = TestApp::new().await;
+        let voter = app.register("Grace").await;
+        let pool = &app.state.pool;
+        let enrollment = start_enrollment(pool, voter.voter_id, &voter.email).await.unwrap();
+        let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+        let code = |steps: u64| enrollment.totp.generate(now + steps * STEP_SECONDS);
+
+        // A code from a clock running ahead uses up its own step, so it
+        // fails in the next step as well as in this one.
+        let early = code(1);
+        assert!(check_code_at(pool, voter.voter_id, &enrollment, &early, now).await.unwrap());
+        assert!(!check_code_at(pool, voter.voter_id, &enrollment, &early, now).await.unwrap());
+        let later = now + STEP_SECONDS;
+        assert!(!check_code_at(pool, voter.voter_id, &enrollment, &early, later).await.unwrap());
+        assert!(!check_code_at(pool, voter.voter_id, &enrollment, &code(0), later).await.unwrap());
+        assert!(check_code_at(pool, voter.voter_id, &enrollment, &code(2), later).await.unwrap());
+    }
+}
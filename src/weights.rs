@@ -0,0 +1,96 @@
+//! Voting weights.
+//!
+//! By default every ballot weighs 1. An organization that votes by shares or
+//! by membership class gives its members other weights, each from a given
+//! day on, today at the earliest. The log keeps the whole history as
+//! [`crate::events::Event::VotingWeightSet`]. The electorate of a poll
+//! records the weights in force on the day it opened, and the poll is
+//! counted with those, whatever changed since. A delegate carries the weight
+//! of each voter they represent.
+
+use std::collections::HashMap;
+
+use sqlx::{any::AnyRow, Row};
+use time::Date;
+use uuid::Uuid;
+
+use crate::db::{self, Pool};
+use crate::VotingWeight;
+
+/// Weight of a voter without any.
+pub fn unit() -> f64 {
+    1.0
+}
+
+/// Weights in force in the organization on `date`, by voter; voters who are
+/// missing weigh [`unit`].
+pub async fn on(pool: &Pool, organization_id: Uuid, date: Date) -> Result<HashMap<Uuid, f64>, sqlx::Error> {
+    // Rows come oldest first, so each voter ends up with the latest one.
+    let rows = sqlx::query(
+        r#"
+        SELECT voter_id, weight
+        FROM voting_weights
+        WHERE organization_id = $1 AND effective_from <= $2
+        ORDER BY effective_from
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(date.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut weights = HashMap::new();
+    for row in &rows {
+        weights.insert(db::parse_column(row, "voter_id")?, row.try_get("weight")?);
+    }
+
+    Ok(weights)
+}
+
+/// Weight of one voter on `date`.
+pub async fn of(pool: &Pool, organization_id: Uuid, voter_id: Uuid, date: Date) -> Result<f64, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT weight
+        FROM voting_weights
+        WHERE organization_id = $1 AND voter_id = $2 AND effective_from <= $3
+        ORDER BY effective_from DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(voter_id.to_string())
+    .bind(date.to_string())
+    .fetch_optional(pool)
+    .await?;
+
+    row.map_or(Ok(unit()), |row| row.try_get("weight"))
+}
+
+/// Every weight the voter was given in the organization, oldest first.
+pub async fn history(pool: &Pool, organization_id: Uuid, voter_id: Uuid) -> Result<Vec<VotingWeight>, sqlx::Error> {
+    sqlx::query(
+        r#"
+        SELECT organization_id, voter_id, weight, effective_from
+        FROM voting_weights
+        WHERE organization_id = $1 AND voter_id = $2
+        ORDER BY effective_from
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(voter_id.to_string())
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(weight_from_row)
+    .collect()
+}
+
+fn weight_from_row(row: &AnyRow) -> Result<VotingWeight, sqlx::Error> {
+    Ok(VotingWeight {
+        organization_id: db::parse_column(row, "organization_id")?,
+        voter_id: db::parse_column(row, "voter_id")?,
+        weight: row.try_get("weight")?,
+        effective_from: db::date_column(row, "effective_from")?,
+    })
+}